@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use rand::prelude::*;
+
+use crate::{
+    escaped_radiance, shade_parts, Background, Carrier, FloatTy, Hittable, LightSource, Ray, Rgb,
+    Vec3, TMIN,
+};
+
+/// Render passes of a camera ray, describing its first hit besides the
+/// light it brings back.
+#[derive(Debug, Clone)]
+pub struct Aovs {
+    /// Light along the ray, the sum of `emission`, `direct` and `indirect`.
+    pub color: Vec3,
+    /// Light emitted by the surface hit, or by the background on a miss.
+    pub emission: Vec3,
+    /// Light reflected after coming straight from a light or an emitter.
+    pub direct: Vec3,
+    /// Light reflected after bouncing on other surfaces.
+    pub indirect: Vec3,
+    /// Weight of the ray scattered by the material, which averages to its
+    /// albedo. Zero for emitters and on a miss.
+    pub albedo: Vec3,
+    /// Shading normal, zero on a miss.
+    pub normal: Vec3,
+    /// Distance from the origin of the ray to the hit, infinite on a miss.
+    pub depth: FloatTy,
+    /// World position of the hit, zero on a miss.
+    pub position: Vec3,
+    pub uv: [FloatTy; 2],
+    /// ID given to the object with `HittableExt::with_id`, zero on a miss.
+    pub object_id: u32,
+    /// Identifies the material for the duration of the render only, zero
+    /// on a miss.
+    pub material_id: usize,
+}
+
+/// Same as `compute_color`, also returning the passes of the first hit.
+pub fn compute_aovs<R: Rng>(
+    objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
+    ray: Ray,
+    background: &dyn Background,
+    rng: &mut R,
+) -> Aovs {
+    trace_aovs(&Rgb, objects, lights, ray, background, rng)
+}
+
+pub(crate) fn trace_aovs<C: Carrier, R: Rng>(
+    carrier: &C,
+    objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
+    ray: Ray,
+    background: &dyn Background,
+    rng: &mut R,
+) -> Aovs {
+    let record = match objects.is_hit_by(ray, TMIN, None) {
+        Some(record) => record,
+        None => {
+            let radiance = escaped_radiance(lights, ray, background, true);
+            let emission = carrier.to_rgb(carrier.emitted(radiance));
+            return Aovs {
+                color: emission,
+                emission,
+                direct: Vec3::zeros(),
+                indirect: Vec3::zeros(),
+                albedo: Vec3::zeros(),
+                normal: Vec3::zeros(),
+                depth: FloatTy::INFINITY,
+                position: Vec3::zeros(),
+                uv: [0.0, 0.0],
+                object_id: 0,
+                material_id: 0,
+            };
+        }
+    };
+
+    let shading = shade_parts(carrier, objects, lights, &record, background, rng);
+    Aovs {
+        color: carrier.to_rgb(shading.emitted + shading.direct + shading.indirect),
+        emission: carrier.to_rgb(shading.emitted),
+        direct: carrier.to_rgb(shading.direct),
+        indirect: carrier.to_rgb(shading.indirect),
+        albedo: shading.albedo,
+        normal: record.normal,
+        depth: record.t * ray.direction.norm(),
+        position: record.p.coords,
+        uv: [record.u, record.v],
+        object_id: record.object_id,
+        material_id: Arc::as_ptr(&record.material) as *const u8 as usize,
+    }
+}
@@ -0,0 +1,17 @@
+use crate::Vec3;
+
+mod sky;
+pub use sky::*;
+
+/// Radiance reaching the scene from infinitely far away, seen by the rays
+/// that escape without hitting anything.
+pub trait Background: Send + Sync + std::fmt::Debug {
+    fn radiance(&self, direction: Vec3) -> Vec3;
+}
+
+/// Constant color in every direction.
+impl Background for Vec3 {
+    fn radiance(&self, _direction: Vec3) -> Vec3 {
+        *self
+    }
+}
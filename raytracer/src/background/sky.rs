@@ -0,0 +1,312 @@
+use super::Background;
+use crate::fconsts;
+use crate::light::DirectionalLight;
+use crate::spectrum;
+use crate::utils::{clamp, fmax};
+use crate::{FloatTy, Vec3};
+
+/// Angular diameter of the sun seen from the ground, in degrees.
+pub const SUN_ANGULAR_DIAMETER: FloatTy = 0.53;
+
+/// Luminance of the sun disk outside of the atmosphere, in cd/m².
+const EXTRATERRESTRIAL_SUN_LUMINANCE: FloatTy = 1.96e9;
+const SUN_TEMPERATURE: FloatTy = 5778.0;
+const SPECTRUM_STEP: FloatTy = 5.0;
+
+/// The model is only fitted for a sun above the horizon.
+const MAX_SUN_ZENITH: FloatTy = 0.499 * fconsts::PI;
+
+/// Direction toward the sun for a day of the year (1 to 365), a solar time in
+/// hours (12 being noon) and a latitude in degrees, positive in the northern
+/// hemisphere. The scene is oriented with +Y up, +X east and -Z north.
+pub fn sun_direction(day_of_year: FloatTy, solar_time: FloatTy, latitude: FloatTy) -> Vec3 {
+    let declination = 0.4093 * (2.0 * fconsts::PI * (day_of_year - 81.0) / 368.0).sin();
+    let hour_angle = fconsts::PI * (solar_time - 12.0) / 12.0;
+    let latitude = latitude.to_radians();
+
+    let up =
+        latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos();
+    let east = -declination.cos() * hour_angle.sin();
+    let north =
+        latitude.cos() * declination.sin() - latitude.sin() * declination.cos() * hour_angle.cos();
+    Vec3::new(east, up, -north).normalize()
+}
+
+fn sun_solid_angle() -> FloatTy {
+    let cos_radius = (SUN_ANGULAR_DIAMETER.to_radians() / 2.0).cos();
+    2.0 * fconsts::PI * (1.0 - cos_radius)
+}
+
+/// Perez luminance distribution, relative to the zenith.
+#[derive(Debug, Clone, Copy)]
+struct Perez {
+    a: FloatTy,
+    b: FloatTy,
+    c: FloatTy,
+    d: FloatTy,
+    e: FloatTy,
+}
+
+impl Perez {
+    /// Coefficients given as `(slope, intercept)` of a linear function of the
+    /// turbidity.
+    fn new(coefficients: [(FloatTy, FloatTy); 5], turbidity: FloatTy) -> Self {
+        let c = |i: usize| coefficients[i].0 * turbidity + coefficients[i].1;
+        Perez {
+            a: c(0),
+            b: c(1),
+            c: c(2),
+            d: c(3),
+            e: c(4),
+        }
+    }
+
+    fn eval(&self, cos_theta: FloatTy, gamma: FloatTy) -> FloatTy {
+        let cos_gamma = gamma.cos();
+        (1.0 + self.a * (self.b / cos_theta).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+/// Evaluates `[T², T, 1] * m * [θ³, θ², θ, 1]`.
+fn zenith_chromaticity(m: [[FloatTy; 4]; 3], turbidity: FloatTy, theta: FloatTy) -> FloatTy {
+    let t = [turbidity * turbidity, turbidity, 1.0];
+    let th = [theta * theta * theta, theta * theta, theta, 1.0];
+    let mut value = 0.0;
+    for (i, row) in m.iter().enumerate() {
+        for (j, coefficient) in row.iter().enumerate() {
+            value += t[i] * coefficient * th[j];
+        }
+    }
+    value
+}
+
+/// Radiance of the sun disk after going through the atmosphere, attenuated
+/// by Rayleigh and aerosol scattering along its path.
+fn sun_radiance(sun_direction: Vec3, turbidity: FloatTy) -> Vec3 {
+    let cos_theta = sun_direction.y;
+    if cos_theta <= 0.0 {
+        return Vec3::zeros();
+    }
+
+    let theta = cos_theta.acos().to_degrees();
+    let air_mass = 1.0 / (cos_theta + 0.15 * (93.885 - theta).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+
+    let mut xyz = Vec3::zeros();
+    let mut unattenuated = 0.0;
+    let mut lambda = spectrum::LAMBDA_MIN;
+    while lambda <= spectrum::LAMBDA_MAX {
+        let micrometers = lambda / 1000.0;
+        let rayleigh = (-0.008735 * micrometers.powf(-4.08) * air_mass).exp();
+        let aerosol = (-beta * micrometers.powf(-1.3) * air_mass).exp();
+        let emitted = spectrum::planck(lambda, SUN_TEMPERATURE);
+        let cie = spectrum::cie_xyz(lambda);
+
+        xyz += cie * (emitted * rayleigh * aerosol);
+        unattenuated += cie.y * emitted;
+        lambda += SPECTRUM_STEP;
+    }
+
+    let rgb = spectrum::xyz_to_rgb(xyz).map(|c| fmax(c, 0.0));
+    rgb * (EXTRATERRESTRIAL_SUN_LUMINANCE / (unattenuated * spectrum::LUMENS_PER_WATT))
+}
+
+/// Clear sky following the analytic model of Preetham, Shirley and Smits
+/// (1999), in the same units as the lights built from photometric quantities.
+///
+/// Directions below the horizon see a diffuse ground lit by the sky and the
+/// sun. The sun itself is not part of the background, it has to be added to
+/// the lights of the scene with `sun` so that it is sampled explicitly.
+#[derive(Debug, Clone)]
+pub struct PreethamSky {
+    sun_direction: Vec3,
+    turbidity: FloatTy,
+    ground_albedo: Vec3,
+    intensity: FloatTy,
+    /// Perez distributions of the luminance and of the x and y chromaticity.
+    perez: [Perez; 3],
+    /// Zenith values of the luminance and chromaticity, divided by the value
+    /// of their distribution at the zenith.
+    zenith: [FloatTy; 3],
+    sun_radiance: Vec3,
+    ground_irradiance: Vec3,
+}
+
+impl PreethamSky {
+    /// `sun_direction` points toward the sun, `turbidity` goes from 2 for a
+    /// very clear sky to 10 for a hazy one.
+    pub fn new(sun_direction: Vec3, turbidity: FloatTy) -> Self {
+        let sun_direction = sun_direction.normalize();
+        let turbidity = clamp(turbidity, 1.7, 10.0);
+        let theta_s = clamp(sun_direction.y, -1.0, 1.0).acos().min(MAX_SUN_ZENITH);
+
+        let perez = [
+            Perez::new(
+                [
+                    (0.1787, -1.4630),
+                    (-0.3554, 0.4275),
+                    (-0.0227, 5.3251),
+                    (0.1206, -2.5771),
+                    (-0.0670, 0.3703),
+                ],
+                turbidity,
+            ),
+            Perez::new(
+                [
+                    (-0.0193, -0.2592),
+                    (-0.0665, 0.0008),
+                    (-0.0004, 0.2125),
+                    (-0.0641, -0.8989),
+                    (-0.0033, 0.0452),
+                ],
+                turbidity,
+            ),
+            Perez::new(
+                [
+                    (-0.0167, -0.2608),
+                    (-0.0950, 0.0092),
+                    (-0.0079, 0.2102),
+                    (-0.0441, -1.6537),
+                    (-0.0109, 0.0529),
+                ],
+                turbidity,
+            ),
+        ];
+
+        let chi = (4.0 / 9.0 - turbidity / 120.0) * (fconsts::PI - 2.0 * theta_s);
+        // zenith luminance in kcd/m²
+        let zenith_luminance =
+            (4.0453 * turbidity - 4.9710) * chi.tan() - 0.2155 * turbidity + 2.4192;
+        let zenith_x = zenith_chromaticity(
+            [
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ],
+            turbidity,
+            theta_s,
+        );
+        let zenith_y = zenith_chromaticity(
+            [
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ],
+            turbidity,
+            theta_s,
+        );
+
+        let zenith = [
+            zenith_luminance * 1000.0 / perez[0].eval(1.0, theta_s),
+            zenith_x / perez[1].eval(1.0, theta_s),
+            zenith_y / perez[2].eval(1.0, theta_s),
+        ];
+
+        let mut sky = PreethamSky {
+            sun_direction,
+            turbidity,
+            ground_albedo: Vec3::repeat(0.2),
+            intensity: 1.0,
+            perez,
+            zenith,
+            sun_radiance: sun_radiance(sun_direction, turbidity),
+            ground_irradiance: Vec3::zeros(),
+        };
+        sky.ground_irradiance = sky.irradiance();
+        sky
+    }
+
+    /// Sky for a place and time, see `sun_direction`.
+    pub fn from_location(
+        day_of_year: FloatTy,
+        solar_time: FloatTy,
+        latitude: FloatTy,
+        turbidity: FloatTy,
+    ) -> Self {
+        PreethamSky::new(sun_direction(day_of_year, solar_time, latitude), turbidity)
+    }
+
+    /// Sets the color of the ground seen below the horizon.
+    pub fn with_ground_albedo(self, ground_albedo: Vec3) -> Self {
+        PreethamSky {
+            ground_albedo,
+            ..self
+        }
+    }
+
+    /// Scales the radiance of the sky, the ground and the sun.
+    pub fn with_intensity(self, intensity: FloatTy) -> Self {
+        PreethamSky { intensity, ..self }
+    }
+
+    pub fn sun_direction(&self) -> Vec3 {
+        self.sun_direction
+    }
+
+    pub fn turbidity(&self) -> FloatTy {
+        self.turbidity
+    }
+
+    /// Light source for the sun disk, to be sampled with the other lights.
+    pub fn sun(&self) -> DirectionalLight {
+        DirectionalLight::new(
+            -self.sun_direction,
+            self.sun_radiance * (sun_solid_angle() * self.intensity),
+        )
+        .with_angular_diameter(SUN_ANGULAR_DIAMETER)
+    }
+
+    /// Radiance of the sky alone, before scaling by the intensity.
+    fn sky_radiance(&self, direction: Vec3) -> Vec3 {
+        // the distribution diverges toward the horizon
+        let cos_theta = fmax(direction.y, 0.001);
+        let gamma = clamp(direction.dot(&self.sun_direction), -1.0, 1.0).acos();
+
+        let value = |i: usize| self.zenith[i] * self.perez[i].eval(cos_theta, gamma);
+        let (luminance, x, y) = (value(0), value(1), value(2));
+        if y <= 0.0 {
+            return Vec3::zeros();
+        }
+
+        let xyz = Vec3::new(x * luminance / y, luminance, (1.0 - x - y) * luminance / y);
+        spectrum::xyz_to_rgb(xyz).map(|c| fmax(c, 0.0)) / spectrum::LUMENS_PER_WATT
+    }
+
+    /// Irradiance on the ground from the sky and the sun.
+    fn irradiance(&self) -> Vec3 {
+        const THETA_STEPS: usize = 32;
+        const PHI_STEPS: usize = 64;
+        let d_theta = fconsts::FRAC_PI_2 / THETA_STEPS as FloatTy;
+        let d_phi = 2.0 * fconsts::PI / PHI_STEPS as FloatTy;
+
+        let mut irradiance = Vec3::zeros();
+        for i in 0..THETA_STEPS {
+            let theta = (i as FloatTy + 0.5) * d_theta;
+            for j in 0..PHI_STEPS {
+                let phi = (j as FloatTy + 0.5) * d_phi;
+                let direction = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                irradiance +=
+                    self.sky_radiance(direction) * (theta.cos() * theta.sin() * d_theta * d_phi);
+            }
+        }
+
+        irradiance + self.sun_radiance * (sun_solid_angle() * fmax(self.sun_direction.y, 0.0))
+    }
+}
+
+impl Background for PreethamSky {
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        let direction = direction.normalize();
+        let radiance = if direction.y < 0.0 {
+            self.ground_albedo.component_mul(&self.ground_irradiance) / fconsts::PI
+        } else {
+            self.sky_radiance(direction)
+        };
+        radiance * self.intensity
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{FloatTy, Pt3, Ray, Vec3};
+use crate::{FloatTy, Pt3, Ray, RayDifferentials, Vec3};
 
 #[derive(Debug, Clone)]
 pub struct Camera {
@@ -38,4 +38,17 @@ impl Camera {
             self.lower_left + self.horizontal * u + self.vertical * v - self.origin,
         )
     }
+
+    /// Ray with the differentials of rays offset by `du` and `dv`, usually
+    /// the size of a pixel, for the filtering of textures.
+    pub fn get_ray_differential(&self, u: FloatTy, v: FloatTy, du: FloatTy, dv: FloatTy) -> Ray {
+        let x_ray = self.get_ray(u + du, v);
+        let y_ray = self.get_ray(u, v + dv);
+        self.get_ray(u, v).with_differentials(RayDifferentials {
+            x_origin: x_ray.origin,
+            x_direction: x_ray.direction,
+            y_origin: y_ray.origin,
+            y_direction: y_ray.direction,
+        })
+    }
 }
@@ -1,5 +1,39 @@
-use crate::utils::clamp;
-use crate::{FloatTy, Vec3};
+use crate::{DisplayPipeline, FloatTy, Vec3};
+
+/// Encoding of the values stored in an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// sRGB transfer curve, used by color images such as albedo maps.
+    Srgb,
+    /// Values proportional to light or storing data, like HDR images,
+    /// roughness or normal maps.
+    Linear,
+}
+
+impl ColorSpace {
+    pub fn to_linear(self, c: FloatTy) -> FloatTy {
+        match self {
+            ColorSpace::Srgb => srgb_to_linear(c),
+            ColorSpace::Linear => c,
+        }
+    }
+}
+
+pub fn srgb_to_linear(c: FloatTy) -> FloatTy {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: FloatTy) -> FloatTy {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
@@ -24,12 +58,8 @@ impl Color {
         }
     }
 
+    /// Clamped and sRGB encoded, see `DisplayPipeline` for tone mapping.
     pub fn to_rgb(self) -> [u8; 3] {
-        fn component(c: FloatTy) -> u8 {
-            let c = clamp(c.sqrt(), 0.0, 1.0);
-            (c * 255.0) as u8
-        }
-
-        [component(self.r), component(self.g), component(self.b)]
+        DisplayPipeline::new().to_srgb8(Vec3::new(self.r, self.g, self.b))
     }
 }
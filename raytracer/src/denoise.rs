@@ -0,0 +1,217 @@
+use crate::spectrum::luminance;
+use crate::{FloatTy, Vec3};
+
+/// Albedos below this are treated as black when dividing the image by the
+/// albedo.
+const MIN_ALBEDO: FloatTy = 0.01;
+
+/// Removes the noise of an image rendered with few samples, with a joint
+/// cross-bilateral filter guided by its albedo and normal passes.
+///
+/// The image is divided by its albedo so that textures are kept sharp, and
+/// pixels far brighter than their neighbors are clamped to avoid blotches.
+/// A first pass then averages neighbors with similar albedos and normals,
+/// and a second pass also compares their colors to the result of the first
+/// one, which keeps the shadows apart from lit areas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Denoiser {
+    /// Half size of the window of neighbors, in pixels.
+    pub radius: usize,
+    pub albedo_sigma: FloatTy,
+    pub normal_sigma: FloatTy,
+    /// Relative difference of colors over which neighbors are ignored, in
+    /// the second pass.
+    pub color_sigma: FloatTy,
+}
+
+impl Denoiser {
+    pub fn new() -> Self {
+        Denoiser {
+            radius: 6,
+            albedo_sigma: 0.1,
+            normal_sigma: 0.3,
+            color_sigma: 0.6,
+        }
+    }
+
+    pub fn with_radius(self, radius: usize) -> Self {
+        Denoiser { radius, ..self }
+    }
+
+    pub fn with_albedo_sigma(self, albedo_sigma: FloatTy) -> Self {
+        Denoiser {
+            albedo_sigma,
+            ..self
+        }
+    }
+
+    pub fn with_normal_sigma(self, normal_sigma: FloatTy) -> Self {
+        Denoiser {
+            normal_sigma,
+            ..self
+        }
+    }
+
+    pub fn with_color_sigma(self, color_sigma: FloatTy) -> Self {
+        Denoiser {
+            color_sigma,
+            ..self
+        }
+    }
+
+    /// Denoises `color` using the albedo and normal passes of the same
+    /// render, all given from the top left pixel row by row.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a buffer does not hold `width * height` pixels.
+    pub fn denoise(
+        &self,
+        width: usize,
+        height: usize,
+        color: &[Vec3],
+        albedo: &[Vec3],
+        normal: &[Vec3],
+    ) -> Vec<Vec3> {
+        let size = width * height;
+        assert!(
+            color.len() == size && albedo.len() == size && normal.len() == size,
+            "denoised buffers do not match the image size"
+        );
+
+        // emitters and the background have no albedo and are kept as is
+        let factors: Vec<Vec3> = albedo
+            .iter()
+            .map(|a| a.map(|c| if c < MIN_ALBEDO { 1.0 } else { c }))
+            .collect();
+        let irradiance: Vec<Vec3> = color
+            .iter()
+            .zip(&factors)
+            .map(|(c, f)| c.component_div(f))
+            .collect();
+
+        let image = Guides {
+            width,
+            height,
+            albedo,
+            normal,
+        };
+        let clamped = image.clamp_outliers(&irradiance);
+        let guide = self.filter(&image, &clamped, None);
+        self.filter(&image, &clamped, Some(&guide))
+            .iter()
+            .zip(&factors)
+            .map(|(c, f)| c.component_mul(f))
+            .collect()
+    }
+
+    fn filter(&self, image: &Guides, values: &[Vec3], guide: Option<&[Vec3]>) -> Vec<Vec3> {
+        let radius = self.radius as isize;
+        let spatial_sigma = (self.radius as FloatTy / 2.0).max(1.0);
+        let weight = |sigma: FloatTy, distance_squared: FloatTy| {
+            (-distance_squared / (2.0 * sigma * sigma)).exp()
+        };
+
+        let mut filtered = Vec::with_capacity(values.len());
+        for y in 0..image.height as isize {
+            for x in 0..image.width as isize {
+                let center = image.index(x, y);
+                let mut total = Vec3::zeros();
+                let mut total_weight = 0.0;
+
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        let (nx, ny) = (x + dx, y + dy);
+                        if nx < 0
+                            || ny < 0
+                            || nx >= image.width as isize
+                            || ny >= image.height as isize
+                        {
+                            continue;
+                        }
+                        let neighbor = image.index(nx, ny);
+
+                        let mut w = weight(spatial_sigma, (dx * dx + dy * dy) as FloatTy)
+                            * weight(
+                                self.albedo_sigma,
+                                (image.albedo[center] - image.albedo[neighbor]).norm_squared(),
+                            )
+                            * weight(
+                                self.normal_sigma,
+                                (image.normal[center] - image.normal[neighbor]).norm_squared(),
+                            );
+                        if let Some(guide) = guide {
+                            let (a, b) = (guide[center], guide[neighbor]);
+                            // relative so that dark and bright areas are
+                            // filtered alike
+                            let scale = 1e-4 + a.norm_squared() + b.norm_squared();
+                            w *= weight(self.color_sigma, (a - b).norm_squared() / scale);
+                        }
+
+                        total += values[neighbor] * w;
+                        total_weight += w;
+                    }
+                }
+                // the center pixel always has a weight of one
+                filtered.push(total / total_weight);
+            }
+        }
+        filtered
+    }
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser::new()
+    }
+}
+
+struct Guides<'a> {
+    width: usize,
+    height: usize,
+    albedo: &'a [Vec3],
+    normal: &'a [Vec3],
+}
+
+impl<'a> Guides<'a> {
+    fn index(&self, x: isize, y: isize) -> usize {
+        y as usize * self.width + x as usize
+    }
+
+    /// Scales down the pixels brighter than their 5x5 neighborhood by more
+    /// than three standard deviations.
+    fn clamp_outliers(&self, values: &[Vec3]) -> Vec<Vec3> {
+        let mut clamped = Vec::with_capacity(values.len());
+        for y in 0..self.height as isize {
+            for x in 0..self.width as isize {
+                let (mut sum, mut sum_squared, mut count) = (0.0, 0.0, 0.0);
+                for ny in (y - 2).max(0)..(y + 3).min(self.height as isize) {
+                    for nx in (x - 2).max(0)..(x + 3).min(self.width as isize) {
+                        if (nx, ny) != (x, y) {
+                            let l = luminance(values[self.index(nx, ny)]);
+                            sum += l;
+                            sum_squared += l * l;
+                            count += 1.0;
+                        }
+                    }
+                }
+
+                let value = values[self.index(x, y)];
+                let l = luminance(value);
+                if count == 0.0 {
+                    clamped.push(value);
+                    continue;
+                }
+                let mean = sum / count;
+                let deviation = (sum_squared / count - mean * mean).max(0.0).sqrt();
+                let limit = mean + 3.0 * deviation;
+                if l > limit && l > 0.0 {
+                    clamped.push(value * (limit / l));
+                } else {
+                    clamped.push(value);
+                }
+            }
+        }
+        clamped
+    }
+}
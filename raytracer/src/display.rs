@@ -0,0 +1,179 @@
+use nalgebra::Matrix3;
+
+use crate::spectrum;
+use crate::utils::{clamp, fmax};
+use crate::{linear_to_srgb, FloatTy, Vec3};
+
+/// Curve compressing scene radiance into the range of a display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapper {
+    /// Clips every value above one.
+    Clamp,
+    /// `c / (1 + c)` on each channel, never reaching white.
+    Reinhard,
+    /// Stephen Hill's fit of the ACES reference and sRGB output transforms.
+    Aces,
+    /// Troy Sobotka's AgX, desaturating bright colors toward white instead
+    /// of skewing their hue.
+    Agx,
+}
+
+impl ToneMapper {
+    /// Maps linear sRGB radiance to linear sRGB display values in `[0, 1]`.
+    pub fn apply(self, color: Vec3) -> Vec3 {
+        let color = color.map(|c| fmax(c, 0.0));
+        let mapped = match self {
+            ToneMapper::Clamp => color,
+            ToneMapper::Reinhard => color.map(|c| c / (1.0 + c)),
+            ToneMapper::Aces => aces(color),
+            ToneMapper::Agx => agx(color),
+        };
+        mapped.map(|c| clamp(c, 0.0, 1.0))
+    }
+}
+
+fn aces(color: Vec3) -> Vec3 {
+    // sRGB to the ACES working space, including the reference rendering
+    // transform's saturation adjustment
+    let input = Matrix3::new(
+        0.59719, 0.35458, 0.04823, //
+        0.07600, 0.90834, 0.01566, //
+        0.02840, 0.13383, 0.83777,
+    );
+    let output = Matrix3::new(
+        1.60475, -0.53108, -0.07367, //
+        -0.10208, 1.10813, -0.00605, //
+        -0.00327, -0.07276, 1.07602,
+    );
+    let v = input * color;
+    let curve = v.map(|c| {
+        let a = c * (c + 0.024_578_6) - 0.000_090_537;
+        let b = c * (0.983_729 * c + 0.432_951) + 0.238_081;
+        a / b
+    });
+    output * curve
+}
+
+fn agx(color: Vec3) -> Vec3 {
+    const MIN_EV: FloatTy = -12.473_93;
+    const MAX_EV: FloatTy = 4.026_069;
+
+    let inset = Matrix3::new(
+        0.842_479_062_253_094,
+        0.078_433_599_999_999_2,
+        0.079_223_745_147_764_3,
+        0.042_328_242_261_012_3,
+        0.878_468_636_469_772,
+        0.079_166_127_460_543_4,
+        0.042_375_654_905_705_1,
+        0.078_433_6,
+        0.879_142_973_793_104,
+    );
+    let outset = Matrix3::new(
+        1.196_879_005_120_17,
+        -0.098_020_881_140_136_8,
+        -0.099_029_744_079_720_5,
+        -0.052_896_851_757_456_2,
+        1.151_903_129_904_17,
+        -0.098_961_176_844_843_3,
+        -0.052_971_635_514_443_8,
+        -0.098_043_450_117_124_1,
+        1.151_073_672_641_16,
+    );
+
+    // log encoding of a range of exposures, followed by a sigmoid fit
+    let v = (inset * color).map(|c| {
+        let x = (clamp(c.log2(), MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.002_32
+    });
+    // the curve produces display encoded values
+    (outset * v).map(|c| fmax(c, 0.0).powf(2.2))
+}
+
+/// Conversion of rendered radiance to the 8-bit sRGB values of a display:
+/// exposure, white balance, tone mapping and sRGB encoding, in that order.
+///
+/// Float images are written before this pipeline, to keep the radiance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayPipeline {
+    /// Exposure in stops, each one doubling the brightness.
+    pub exposure: FloatTy,
+    /// Factors applied to each channel so that the white point renders
+    /// as neutral.
+    pub white_balance: Vec3,
+    pub tone_mapper: ToneMapper,
+}
+
+/// Temperature of a white left untouched by `with_white_balance`, close to
+/// the D65 white point of sRGB.
+pub const NEUTRAL_TEMPERATURE: FloatTy = 6500.0;
+
+impl DisplayPipeline {
+    /// Leaves the colors unchanged before clamping and encoding them.
+    pub fn new() -> Self {
+        DisplayPipeline {
+            exposure: 0.0,
+            white_balance: Vec3::repeat(1.0),
+            tone_mapper: ToneMapper::Clamp,
+        }
+    }
+
+    pub fn with_exposure(self, exposure: FloatTy) -> Self {
+        DisplayPipeline { exposure, ..self }
+    }
+
+    pub fn with_tone_mapper(self, tone_mapper: ToneMapper) -> Self {
+        DisplayPipeline {
+            tone_mapper,
+            ..self
+        }
+    }
+
+    /// Renders `white` as a gray of the same luminance.
+    pub fn with_white_point(self, white: Vec3) -> Self {
+        // very warm whites have no blue at all in sRGB
+        let white_balance = white.map(|c| spectrum::luminance(white) / fmax(c, 1e-3));
+        DisplayPipeline {
+            white_balance,
+            ..self
+        }
+    }
+
+    /// Neutralizes the tint of a black body light at `kelvin`: lower
+    /// temperatures make the image bluer and higher ones warmer.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `kelvin` is not a positive temperature.
+    pub fn with_white_balance(self, kelvin: FloatTy) -> Self {
+        assert!(
+            kelvin > 0.0 && kelvin.is_finite(),
+            "white balance temperature must be positive"
+        );
+        let white =
+            spectrum::blackbody(kelvin).component_div(&spectrum::blackbody(NEUTRAL_TEMPERATURE));
+        self.with_white_point(white)
+    }
+
+    /// Linear display values in `[0, 1]`, before sRGB encoding.
+    pub fn apply(&self, color: Vec3) -> Vec3 {
+        let exposed = color.component_mul(&self.white_balance) * self.exposure.exp2();
+        self.tone_mapper.apply(exposed)
+    }
+
+    pub fn to_srgb8(&self, color: Vec3) -> [u8; 3] {
+        let c = self
+            .apply(color)
+            .map(|c| (linear_to_srgb(c) * 255.0).round());
+        [c.x as u8, c.y as u8, c.z as u8]
+    }
+}
+
+impl Default for DisplayPipeline {
+    fn default() -> Self {
+        DisplayPipeline::new()
+    }
+}
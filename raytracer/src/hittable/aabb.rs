@@ -28,25 +28,22 @@ impl AABB {
     }
 }
 
-macro_rules! check_inner_comp {
-    ($self:expr, $comp:ident, $ray:expr, $tmin:expr, $tmax:expr) => {
-        let inv_dir = 1.0 / $ray.direction.$comp;
-        let t0 = ($self.min.$comp - $ray.origin.$comp) * inv_dir;
-        let t1 = ($self.max.$comp - $ray.origin.$comp) * inv_dir;
-        let (t0, t1) = if inv_dir < 0.0 { (t1, t0) } else { (t0, t1) };
-        let tmin = utils::fmax(t0, $tmin);
-        let tmax = $tmax.map(|tmax| utils::fmin(t1, tmax)).unwrap_or(t1);
-        if tmax <= tmin {
-            return false;
-        }
-    };
-}
-
 impl HitCheckable for AABB {
     fn check_hit_by(&self, ray: Ray, tmin: FloatTy, tmax: Option<FloatTy>) -> bool {
-        check_inner_comp!(self, x, ray, tmin, tmax);
-        check_inner_comp!(self, y, ray, tmin, tmax);
-        check_inner_comp!(self, z, ray, tmin, tmax);
+        // the range of the ray is narrowed by the slab of each axis in turn
+        let mut tmin = tmin;
+        let mut tmax = tmax.unwrap_or(FloatTy::INFINITY);
+        for axis in 0..3 {
+            let inv_dir = 1.0 / ray.direction[axis];
+            let t0 = (self.min[axis] - ray.origin[axis]) * inv_dir;
+            let t1 = (self.max[axis] - ray.origin[axis]) * inv_dir;
+            let (t0, t1) = if inv_dir < 0.0 { (t1, t0) } else { (t0, t1) };
+            tmin = utils::fmax(t0, tmin);
+            tmax = utils::fmin(t1, tmax);
+            if tmax <= tmin {
+                return false;
+            }
+        }
         true
     }
 }
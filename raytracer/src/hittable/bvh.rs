@@ -105,9 +105,9 @@ fn key_x_extractor(h: &dyn Hittable) -> FloatTy {
 }
 
 fn key_y_extractor(h: &dyn Hittable) -> FloatTy {
-    h.bounding_box().unwrap().min.x
+    h.bounding_box().unwrap().min.y
 }
 
 fn key_z_extractor(h: &dyn Hittable) -> FloatTy {
-    h.bounding_box().unwrap().min.x
+    h.bounding_box().unwrap().min.z
 }
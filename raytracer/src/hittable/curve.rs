@@ -0,0 +1,385 @@
+use std::sync::Arc;
+
+use super::{HitRecord, Hittable, AABB};
+use crate::fconsts;
+use crate::material::Material;
+use crate::utils::{clamp, fmax, Frame};
+use crate::{FloatTy, Pt3, Ray, Vec3};
+
+const MAX_SUBDIVISIONS: i32 = 10;
+
+/// Shape given to the width of a curve.
+#[derive(Debug, Clone, Copy)]
+pub enum CurveType {
+    /// Ribbon always facing the ray, cheap for thin fibers seen from afar.
+    Flat,
+    /// Ribbon turning from one normal at the start of the curve to another
+    /// at its end, like a grass blade.
+    Ribbon {
+        start_normal: Vec3,
+        end_normal: Vec3,
+    },
+    /// Tube whose shading normal goes around the curve, the intersection
+    /// being computed as for a flat curve.
+    Round,
+}
+
+fn lerp(t: FloatTy, a: FloatTy, b: FloatTy) -> FloatTy {
+    a * (1.0 - t) + b * t
+}
+
+/// Point and derivative of a cubic Bézier curve.
+fn eval_bezier(cp: &[Vec3; 4], t: FloatTy) -> (Vec3, Vec3) {
+    let a = cp[0].lerp(&cp[1], t);
+    let b = cp[1].lerp(&cp[2], t);
+    let c = cp[2].lerp(&cp[3], t);
+    let d = a.lerp(&b, t);
+    let e = b.lerp(&c, t);
+    let derivative = (e - d) * 3.0;
+    // the derivative vanishes when two control points coincide at an end
+    let derivative = if derivative.norm_squared() > 0.0 {
+        derivative
+    } else {
+        cp[3] - cp[0]
+    };
+    (d.lerp(&e, t), derivative)
+}
+
+/// Splits a Bézier curve in two at its middle, the halves sharing their
+/// middle control point.
+fn subdivide_bezier(cp: &[Vec3; 4]) -> [Vec3; 7] {
+    [
+        cp[0],
+        (cp[0] + cp[1]) / 2.0,
+        (cp[0] + cp[1] * 2.0 + cp[2]) / 4.0,
+        (cp[0] + cp[1] * 3.0 + cp[2] * 3.0 + cp[3]) / 8.0,
+        (cp[1] + cp[2] * 2.0 + cp[3]) / 4.0,
+        (cp[2] + cp[3]) / 2.0,
+        cp[3],
+    ]
+}
+
+/// Closest intersection found so far, in the space of the ray.
+#[derive(Debug, Clone, Copy)]
+struct CurveHit {
+    /// Distance along the normalized ray.
+    z: FloatTy,
+    /// Parameter of the curve at the hit.
+    t: FloatTy,
+}
+
+/// Cubic Bézier curve with a width varying linearly from its start to its
+/// end, for hair, fur and grass.
+///
+/// The `u` texture coordinate goes along the curve and `v` across it, from 0
+/// to 1. The tangent of hits follows the curve.
+#[derive(Debug, Clone)]
+pub struct Curve {
+    control_points: [Vec3; 4],
+    widths: [FloatTy; 2],
+    u_range: [FloatTy; 2],
+    curve_type: CurveType,
+    material: Arc<dyn Material>,
+    max_depth: i32,
+}
+
+impl Curve {
+    pub fn new(
+        control_points: [Pt3; 4],
+        start_width: FloatTy,
+        end_width: FloatTy,
+        curve_type: CurveType,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let control_points = [
+            control_points[0].coords,
+            control_points[1].coords,
+            control_points[2].coords,
+            control_points[3].coords,
+        ];
+
+        // subdivide until the segments are close enough to straight lines
+        let mut l0: FloatTy = 0.0;
+        for i in 0..2 {
+            let second = control_points[i] - control_points[i + 1] * 2.0 + control_points[i + 2];
+            l0 = fmax(l0, second.amax());
+        }
+        let eps = fmax(fmax(start_width, end_width) * 0.05, 1e-6);
+        let max_depth = if l0 > 0.0 {
+            let r0 = (fconsts::SQRT_2 * 6.0 * l0 / (8.0 * eps)).log2() / 2.0;
+            clamp(r0.floor(), 0.0, MAX_SUBDIVISIONS as FloatTy) as i32
+        } else {
+            0
+        };
+
+        Curve {
+            control_points,
+            widths: [start_width, end_width],
+            u_range: [0.0, 1.0],
+            curve_type,
+            material,
+            max_depth,
+        }
+    }
+
+    /// Range of the `u` texture coordinate covered by the curve, when it is a
+    /// segment of a longer strand.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the range is empty, which leaves the tangent undefined.
+    pub fn with_u_range(self, start: FloatTy, end: FloatTy) -> Self {
+        assert!(start != end, "the u range of a curve cannot be empty");
+        Curve {
+            u_range: [start, end],
+            ..self
+        }
+    }
+
+    fn width(&self, t: FloatTy) -> FloatTy {
+        lerp(t, self.widths[0], self.widths[1])
+    }
+
+    fn ribbon_normal(start_normal: Vec3, end_normal: Vec3, t: FloatTy) -> Vec3 {
+        let normal = start_normal.normalize().lerp(&end_normal.normalize(), t);
+        if normal.norm_squared() > 0.0 {
+            normal.normalize()
+        } else {
+            start_normal.normalize()
+        }
+    }
+
+    /// Recursively splits the curve, given in the space of the ray, until
+    /// its segments can be tested as lines.
+    #[allow(clippy::too_many_arguments)]
+    fn intersect(
+        &self,
+        cp: &[Vec3; 4],
+        t0: FloatTy,
+        t1: FloatTy,
+        depth: i32,
+        direction: Vec3,
+        zmin: FloatTy,
+        closest: &mut Option<CurveHit>,
+    ) {
+        let zmax = closest.map(|hit| hit.z).unwrap_or(FloatTy::INFINITY);
+
+        // the ray goes along +Z from the origin in this space
+        let half_width = fmax(self.width(t0), self.width(t1)) / 2.0;
+        let mut min = cp[0];
+        let mut max = cp[0];
+        for p in &cp[1..] {
+            min = min.inf(p);
+            max = max.sup(p);
+        }
+        if min.x - half_width > 0.0
+            || max.x + half_width < 0.0
+            || min.y - half_width > 0.0
+            || max.y + half_width < 0.0
+            || min.z - half_width > zmax
+            || max.z + half_width < zmin
+        {
+            return;
+        }
+
+        if depth > 0 {
+            let split = subdivide_bezier(cp);
+            let middle = (t0 + t1) / 2.0;
+            self.intersect(
+                &[split[0], split[1], split[2], split[3]],
+                t0,
+                middle,
+                depth - 1,
+                direction,
+                zmin,
+                closest,
+            );
+            self.intersect(
+                &[split[3], split[4], split[5], split[6]],
+                middle,
+                t1,
+                depth - 1,
+                direction,
+                zmin,
+                closest,
+            );
+            return;
+        }
+
+        // the ray must pass between the planes perpendicular to the segment
+        // at its ends
+        let edge = (cp[1].y - cp[0].y) * -cp[0].y + cp[0].x * (cp[0].x - cp[1].x);
+        if edge < 0.0 {
+            return;
+        }
+        let edge = (cp[2].y - cp[3].y) * -cp[3].y + cp[3].x * (cp[3].x - cp[2].x);
+        if edge < 0.0 {
+            return;
+        }
+
+        let segment = (cp[3] - cp[0]).xy();
+        let denom = segment.norm_squared();
+        if denom <= 0.0 {
+            return;
+        }
+        let w = -cp[0].xy().dot(&segment) / denom;
+        let t = clamp(lerp(w, t0, t1), t0, t1);
+
+        let mut hit_width = self.width(t);
+        if let CurveType::Ribbon {
+            start_normal,
+            end_normal,
+        } = self.curve_type
+        {
+            // ribbons look thinner when seen from the side
+            let normal = Curve::ribbon_normal(start_normal, end_normal, t);
+            hit_width *= normal.dot(&direction).abs();
+        }
+
+        let (point, _) = eval_bezier(cp, clamp(w, 0.0, 1.0));
+        if point.x * point.x + point.y * point.y > hit_width * hit_width * 0.25 {
+            return;
+        }
+        if point.z < zmin || point.z > zmax {
+            return;
+        }
+
+        *closest = Some(CurveHit { z: point.z, t });
+    }
+}
+
+impl Hittable for Curve {
+    fn is_hit_by(&self, ray: Ray, tmin: FloatTy, tmax: Option<FloatTy>) -> Option<HitRecord> {
+        let length = ray.direction.norm();
+        if length <= 0.0 {
+            return None;
+        }
+        let direction = ray.direction / length;
+
+        let frame = Frame::from_normal(direction);
+        let origin = ray.origin.coords;
+        let cp = [
+            frame.to_local(self.control_points[0] - origin),
+            frame.to_local(self.control_points[1] - origin),
+            frame.to_local(self.control_points[2] - origin),
+            frame.to_local(self.control_points[3] - origin),
+        ];
+
+        let mut closest = tmax.map(|tmax| CurveHit {
+            z: tmax * length,
+            t: -1.0,
+        });
+        self.intersect(
+            &cp,
+            0.0,
+            1.0,
+            self.max_depth,
+            direction,
+            tmin * length,
+            &mut closest,
+        );
+        let hit = closest.filter(|hit| hit.t >= 0.0)?;
+
+        let t = hit.z / length;
+        let p = ray.point_at_parameter(t);
+        let (center, tangent) = eval_bezier(&self.control_points, hit.t);
+        let along = tangent.normalize();
+        let half_width = fmax(self.width(hit.t) / 2.0, 1e-12);
+
+        let facing = match self.curve_type {
+            CurveType::Ribbon {
+                start_normal,
+                end_normal,
+            } => Curve::ribbon_normal(start_normal, end_normal, hit.t),
+            _ => -direction,
+        };
+        let facing = facing - along * along.dot(&facing);
+        let facing = if facing.norm_squared() > 0.0 {
+            facing.normalize()
+        } else {
+            Frame::from_normal(along).s
+        };
+        let across = facing.cross(&along);
+
+        // offset of the hit from the axis of the curve, from -1 to 1
+        let h = clamp((p.coords - center).dot(&across) / half_width, -1.0, 1.0);
+        let normal = match self.curve_type {
+            CurveType::Round => facing * (1.0 - h * h).sqrt() + across * h,
+            _ => facing,
+        };
+
+        let u = lerp(hit.t, self.u_range[0], self.u_range[1]);
+        let v = (h + 1.0) / 2.0;
+        // derivatives along the texture coordinates, not the curve parameter
+        let dpdu = tangent / (self.u_range[1] - self.u_range[0]);
+        Some(
+            HitRecord::new(ray, t, p, normal, u, v, self.material.clone())
+                .with_tangents(dpdu, across * (2.0 * half_width)),
+        )
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        // a Bézier curve stays inside the hull of its control points
+        let half_width = fmax(self.widths[0], self.widths[1]) / 2.0;
+        let mut min = self.control_points[0];
+        let mut max = self.control_points[0];
+        for p in &self.control_points[1..] {
+            min = min.inf(p);
+            max = max.sup(p);
+        }
+        Some(AABB::new(
+            Pt3::from(min.map(|c| c - half_width)),
+            Pt3::from(max.map(|c| c + half_width)),
+        ))
+    }
+}
+
+/// Chains curves through the points of a polyline, as a Catmull-Rom spline
+/// going through every point, with a width given at each point. The `u`
+/// texture coordinate goes from 0 at the first point to 1 at the last one.
+pub fn curves_from_polyline(
+    points: &[Pt3],
+    widths: &[FloatTy],
+    curve_type: CurveType,
+    material: Arc<dyn Material>,
+) -> Vec<Box<dyn Hittable>> {
+    let mut curves: Vec<Box<dyn Hittable>> = Vec::new();
+    if points.len() < 2 {
+        return curves;
+    }
+
+    let segment_count = points.len() - 1;
+    let width = |i: usize| {
+        widths
+            .get(i)
+            .or_else(|| widths.last())
+            .cloned()
+            .unwrap_or(0.0)
+    };
+    for i in 0..segment_count {
+        let previous = points[if i > 0 { i - 1 } else { i }];
+        let start = points[i];
+        let end = points[i + 1];
+        let next = points[if i + 2 < points.len() { i + 2 } else { i + 1 }];
+
+        let control_points = [
+            start,
+            start + (end - previous) / 6.0,
+            end - (next - start) / 6.0,
+            end,
+        ];
+        let curve = Curve::new(
+            control_points,
+            width(i),
+            width(i + 1),
+            curve_type,
+            material.clone(),
+        )
+        .with_u_range(
+            i as FloatTy / segment_count as FloatTy,
+            (i + 1) as FloatTy / segment_count as FloatTy,
+        );
+        curves.push(Box::new(curve));
+    }
+    curves
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use super::{HitRecord, Hittable, AABB};
+use crate::fconsts;
+use crate::{FloatTy, Ray, Texture};
+
+/// Makes the hits on an object pass through wherever the alpha of an opacity
+/// texture falls below a threshold, for foliage cards or fences.
+pub struct Cutout<H: Hittable> {
+    inner: H,
+    opacity: Arc<dyn Texture>,
+    threshold: FloatTy,
+}
+
+impl<H: Hittable> Cutout<H> {
+    pub fn new(inner: H, opacity: Arc<dyn Texture>, threshold: FloatTy) -> Self {
+        Cutout {
+            inner,
+            opacity,
+            threshold,
+        }
+    }
+}
+
+impl<H: Hittable> Hittable for Cutout<H> {
+    fn is_hit_by(&self, ray: Ray, tmin: FloatTy, tmax: Option<FloatTy>) -> Option<HitRecord> {
+        let mut tmin = tmin;
+        loop {
+            let record = self.inner.is_hit_by(ray, tmin, tmax)?;
+            if self.opacity.alpha(&record.surface_point()) >= self.threshold {
+                return Some(record);
+            }
+            tmin = record.t + fconsts::EPSILON;
+        }
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        self.inner.bounding_box()
+    }
+}
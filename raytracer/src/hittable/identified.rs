@@ -0,0 +1,39 @@
+use super::{HitRecord, Hittable, AABB};
+use crate::{FloatTy, Ray};
+
+/// Gives an ID to the hits on an object, for the object ID pass. IDs set
+/// inside the object, like the parts of a model, take precedence.
+pub struct Identified<H: Hittable> {
+    inner: H,
+    id: u32,
+}
+
+impl<H: Hittable> Identified<H> {
+    pub fn new(inner: H, id: u32) -> Self {
+        Identified { inner, id }
+    }
+}
+
+impl<H: Hittable> Hittable for Identified<H> {
+    fn is_hit_by(&self, ray: Ray, tmin: FloatTy, tmax: Option<FloatTy>) -> Option<HitRecord> {
+        let record = self.inner.is_hit_by(ray, tmin, tmax)?;
+        if record.object_id == 0 {
+            Some(record.with_object_id(self.id))
+        } else {
+            Some(record)
+        }
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        self.inner.bounding_box()
+    }
+}
+
+/// Numbers the objects of a list from one, in order.
+pub fn numbered(objects: Vec<Box<dyn Hittable>>) -> Vec<Box<dyn Hittable>> {
+    objects
+        .into_iter()
+        .zip(1..)
+        .map(|(object, id)| Box::new(Identified::new(object, id)) as Box<dyn Hittable>)
+        .collect()
+}
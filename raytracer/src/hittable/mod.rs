@@ -2,21 +2,30 @@ use std::sync::Arc;
 
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::{FloatTy, Mat44, Pt3, Vec3};
+use crate::texture::SurfacePoint;
+use crate::{FloatTy, Mat44, Pt3, Texture, Vec3};
 
 mod aabb;
 mod bvh;
+mod curve;
+mod cutout;
+mod identified;
 mod operation;
 mod plane;
 mod rect;
 mod sphere;
+mod strands;
 mod triangle;
 pub use aabb::*;
 pub use bvh::*;
+pub use curve::*;
+pub use cutout::*;
+pub use identified::*;
 pub use operation::*;
 pub use plane::*;
 pub use rect::*;
 pub use sphere::*;
+pub use strands::*;
 pub use triangle::*;
 
 #[derive(Debug, Clone)]
@@ -24,11 +33,21 @@ pub struct HitRecord {
     pub ray: Ray,
     pub t: FloatTy,
     pub p: Pt3,
+    /// Hit point in the space of the object, before its transforms.
+    pub local_p: Pt3,
     pub normal: Vec3,
     pub u: FloatTy,
     pub v: FloatTy,
+    /// Partial derivative of the hit point along `u`, zero when the surface
+    /// has no parameterization.
+    pub tangent: Vec3,
+    /// Partial derivative of the hit point along `v`, zero when the surface
+    /// has no parameterization.
+    pub bitangent: Vec3,
     pub front_face: bool,
     pub material: Arc<dyn Material>,
+    /// ID of the object hit, zero when it has none.
+    pub object_id: u32,
 }
 
 impl HitRecord {
@@ -52,13 +71,86 @@ impl HitRecord {
             ray,
             t,
             p,
+            local_p: p,
             normal,
             u,
             v,
+            tangent: Vec3::zeros(),
+            bitangent: Vec3::zeros(),
             front_face,
             material,
+            object_id: 0,
         }
     }
+
+    pub fn with_tangents(self, tangent: Vec3, bitangent: Vec3) -> Self {
+        HitRecord {
+            tangent,
+            bitangent,
+            ..self
+        }
+    }
+
+    /// Hit point in the space of the object, when it differs from `p`.
+    pub fn with_local_point(self, local_p: Pt3) -> Self {
+        HitRecord { local_p, ..self }
+    }
+
+    pub fn with_object_id(self, object_id: u32) -> Self {
+        HitRecord { object_id, ..self }
+    }
+
+    /// Where the textures of the material are looked up.
+    pub fn surface_point(&self) -> SurfacePoint {
+        let [dudx, dvdx, dudy, dvdy] = self.uv_differentials().unwrap_or([0.0; 4]);
+        SurfacePoint {
+            u: self.u,
+            v: self.v,
+            local_p: self.local_p,
+            p: self.p,
+            normal: self.normal,
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+        }
+    }
+
+    /// Change of the texture coordinates between the hit and the points hit
+    /// by the differentials of the ray on the tangent plane of the surface.
+    fn uv_differentials(&self) -> Option<[FloatTy; 4]> {
+        let differentials = self.ray.differentials?;
+        let offset = |origin: Pt3, direction: Vec3| {
+            let denominator = self.normal.dot(&direction);
+            if denominator.abs() <= FloatTy::EPSILON {
+                return None;
+            }
+            let t = self.normal.dot(&(self.p - origin)) / denominator;
+            Some(origin + direction * t - self.p)
+        };
+        let dpdx = offset(differentials.x_origin, differentials.x_direction)?;
+        let dpdy = offset(differentials.y_origin, differentials.y_direction)?;
+
+        // least squares solution of dp = tangent * du + bitangent * dv
+        let a = self.tangent.norm_squared();
+        let b = self.tangent.dot(&self.bitangent);
+        let c = self.bitangent.norm_squared();
+        let determinant = a * c - b * b;
+        if determinant <= FloatTy::EPSILON * a * c {
+            return None;
+        }
+        let solve = |dp: Vec3| {
+            let along_u = self.tangent.dot(&dp);
+            let along_v = self.bitangent.dot(&dp);
+            (
+                (c * along_u - b * along_v) / determinant,
+                (a * along_v - b * along_u) / determinant,
+            )
+        };
+        let (dudx, dvdx) = solve(dpdx);
+        let (dudy, dvdy) = solve(dpdy);
+        Some([dudx, dvdx, dudy, dvdy])
+    }
 }
 
 pub trait Hittable: Sync + Send {
@@ -70,6 +162,16 @@ pub trait Hittable: Sync + Send {
 
 pub type HittableList = Vec<Box<dyn Hittable>>;
 
+impl Hittable for Box<dyn Hittable> {
+    fn is_hit_by(&self, ray: Ray, tmin: FloatTy, tmax: Option<FloatTy>) -> Option<HitRecord> {
+        self.as_ref().is_hit_by(ray, tmin, tmax)
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        self.as_ref().bounding_box()
+    }
+}
+
 impl Hittable for HittableList {
     fn is_hit_by(&self, ray: Ray, tmin: FloatTy, tmax: Option<FloatTy>) -> Option<HitRecord> {
         self.as_slice().is_hit_by(ray, tmin, tmax)
@@ -132,6 +234,14 @@ pub trait HittableExt: Hittable + Sized {
     fn transform(self, transform: Mat44) -> TransformHittable<Self> {
         TransformHittable::new(self, transform)
     }
+
+    fn cutout(self, opacity: Arc<dyn Texture>, threshold: FloatTy) -> Cutout<Self> {
+        Cutout::new(self, opacity, threshold)
+    }
+
+    fn with_id(self, id: u32) -> Identified<Self> {
+        Identified::new(self, id)
+    }
 }
 
 impl<T: Sized + Hittable> HittableExt for T {}
@@ -5,6 +5,7 @@ pub struct TransformHittable<H: Hittable> {
     inner: H,
     transform: Mat44,
     inverse: Mat44,
+    normal_transform: Mat44,
 }
 
 impl<H: Hittable> TransformHittable<H> {
@@ -14,6 +15,7 @@ impl<H: Hittable> TransformHittable<H> {
             inner,
             transform,
             inverse,
+            normal_transform: inverse.transpose(),
         }
     }
 }
@@ -80,15 +82,36 @@ impl<H: Hittable> Hittable for TransformHittable<H> {
         );
 
         if let Some(record) = self.inner.is_hit_by(new_ray, tmin, tmax) {
-            Some(HitRecord::new(
-                new_ray,
-                record.t,
-                self.transform.transform_point(&record.p),
-                self.transform.transform_vector(&record.normal),
-                record.u,
-                record.v,
-                record.material,
-            ))
+            let local_p = record.local_p;
+            // normals are transformed by the inverse transpose to stay
+            // orthogonal to the surface under non-uniform scaling
+            let outward_normal = if record.front_face {
+                record.normal
+            } else {
+                -record.normal
+            };
+            let normal = self
+                .normal_transform
+                .transform_vector(&outward_normal)
+                .normalize();
+
+            Some(
+                HitRecord::new(
+                    ray,
+                    record.t,
+                    self.transform.transform_point(&record.p),
+                    normal,
+                    record.u,
+                    record.v,
+                    record.material,
+                )
+                .with_tangents(
+                    self.transform.transform_vector(&record.tangent),
+                    self.transform.transform_vector(&record.bitangent),
+                )
+                .with_local_point(local_p)
+                .with_object_id(record.object_id),
+            )
         } else {
             None
         }
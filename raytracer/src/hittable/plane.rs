@@ -42,6 +42,15 @@ impl Plane {
         let (ub, vb) = self.uv_base;
         (ub.dot(&p.coords), vb.dot(&p.coords))
     }
+
+    fn compute_tangents(&self) -> (Vec3, Vec3) {
+        let (ub, vb) = self.uv_base;
+        let (ub2, vb2) = (ub.norm_squared(), vb.norm_squared());
+        if ub2 <= fconsts::EPSILON || vb2 <= fconsts::EPSILON {
+            return (Vec3::zeros(), Vec3::zeros());
+        }
+        (ub / ub2, vb / vb2)
+    }
 }
 
 impl Hittable for Plane {
@@ -55,15 +64,11 @@ impl Hittable for Plane {
         if utils::is_in_range(t, tmin, tmax) {
             let p = ray.point_at_parameter(t);
             let (u, v) = self.compute_uv(p);
-            Some(HitRecord::new(
-                ray,
-                t,
-                p,
-                self.normal,
-                u,
-                v,
-                self.material.clone(),
-            ))
+            let (tangent, bitangent) = self.compute_tangents();
+            Some(
+                HitRecord::new(ray, t, p, self.normal, u, v, self.material.clone())
+                    .with_tangents(tangent, bitangent),
+            )
         } else {
             None
         }
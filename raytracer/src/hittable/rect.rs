@@ -54,18 +54,15 @@ impl Hittable for XYRect {
         let u = (x - self.x0) / (self.x1 - self.x0);
         let v = (y - self.y0) / (self.y1 - self.y0);
 
+        let tangent = Vec3::x() * (self.x1 - self.x0);
+        let bitangent = Vec3::y() * (self.y1 - self.y0);
         let outward_normal = Vec3::z();
         let p = ray.point_at_parameter(t);
 
-        Some(HitRecord::new(
-            ray,
-            t,
-            p,
-            outward_normal,
-            u,
-            v,
-            self.material.clone(),
-        ))
+        Some(
+            HitRecord::new(ray, t, p, outward_normal, u, v, self.material.clone())
+                .with_tangents(tangent, bitangent),
+        )
     }
 
     fn bounding_box(&self) -> Option<AABB> {
@@ -123,18 +120,15 @@ impl Hittable for YZRect {
         let u = (y - self.y0) / (self.y1 - self.y0);
         let v = (z - self.z0) / (self.z1 - self.z0);
 
+        let tangent = Vec3::y() * (self.y1 - self.y0);
+        let bitangent = Vec3::z() * (self.z1 - self.z0);
         let outward_normal = Vec3::x();
         let p = ray.point_at_parameter(t);
 
-        Some(HitRecord::new(
-            ray,
-            t,
-            p,
-            outward_normal,
-            u,
-            v,
-            self.material.clone(),
-        ))
+        Some(
+            HitRecord::new(ray, t, p, outward_normal, u, v, self.material.clone())
+                .with_tangents(tangent, bitangent),
+        )
     }
 
     fn bounding_box(&self) -> Option<AABB> {
@@ -192,18 +186,15 @@ impl Hittable for XZRect {
         let u = (x - self.x0) / (self.x1 - self.x0);
         let v = (z - self.z0) / (self.z1 - self.z0);
 
+        let tangent = Vec3::x() * (self.x1 - self.x0);
+        let bitangent = Vec3::z() * (self.z1 - self.z0);
         let outward_normal = Vec3::y();
         let p = ray.point_at_parameter(t);
 
-        Some(HitRecord::new(
-            ray,
-            t,
-            p,
-            outward_normal,
-            u,
-            v,
-            self.material.clone(),
-        ))
+        Some(
+            HitRecord::new(ray, t, p, outward_normal, u, v, self.material.clone())
+                .with_tangents(tangent, bitangent),
+        )
     }
 
     fn bounding_box(&self) -> Option<AABB> {
@@ -30,6 +30,20 @@ impl Sphere {
         let v = (theta + fconsts::FRAC_PI_2) / fconsts::PI;
         (u, v)
     }
+
+    fn compute_tangents(&self, p: Pt3) -> (Vec3, Vec3) {
+        let d = (p - self.center) / self.radius;
+        let cos_theta = (d.x * d.x + d.z * d.z).sqrt();
+        if cos_theta <= fconsts::EPSILON {
+            // the parameterization is degenerate at the poles
+            return (Vec3::x(), Vec3::z());
+        }
+
+        let tangent = Vec3::new(d.z, 0.0, -d.x) * (2.0 * fconsts::PI * self.radius);
+        let bitangent = Vec3::new(-d.y * d.x / cos_theta, cos_theta, -d.y * d.z / cos_theta)
+            * (fconsts::PI * self.radius);
+        (tangent, bitangent)
+    }
 }
 
 impl Hittable for Sphere {
@@ -50,30 +64,38 @@ impl Hittable for Sphere {
         if utils::is_in_range(t1, tmin, tmax) {
             let p = ray.point_at_parameter(t1);
             let (u, v) = self.compute_uv(p);
-            return Some(HitRecord::new(
-                ray,
-                t1,
-                p,
-                (p - self.center) / self.radius,
-                u,
-                v,
-                self.material.clone(),
-            ));
+            let (tangent, bitangent) = self.compute_tangents(p);
+            return Some(
+                HitRecord::new(
+                    ray,
+                    t1,
+                    p,
+                    (p - self.center) / self.radius,
+                    u,
+                    v,
+                    self.material.clone(),
+                )
+                .with_tangents(tangent, bitangent),
+            );
         }
 
         let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
         if utils::is_in_range(t2, tmin, tmax) {
             let p = ray.point_at_parameter(t2);
             let (u, v) = self.compute_uv(p);
-            return Some(HitRecord::new(
-                ray,
-                t2,
-                p,
-                (p - self.center) / self.radius,
-                u,
-                v,
-                self.material.clone(),
-            ));
+            let (tangent, bitangent) = self.compute_tangents(p);
+            return Some(
+                HitRecord::new(
+                    ray,
+                    t2,
+                    p,
+                    (p - self.center) / self.radius,
+                    u,
+                    v,
+                    self.material.clone(),
+                )
+                .with_tangents(tangent, bitangent),
+            );
         } else {
             None
         }
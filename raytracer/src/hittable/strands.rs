@@ -0,0 +1,173 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::{curves_from_polyline, CurveType, Hittable};
+use crate::material::Material;
+use crate::{FloatTy, Pt3};
+
+#[derive(Debug)]
+pub enum StrandError {
+    Io(std::io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for StrandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrandError::Io(err) => write!(f, "cannot read strand file: {}", err),
+            StrandError::Parse(msg) => write!(f, "invalid strand file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StrandError {}
+
+impl From<std::io::Error> for StrandError {
+    fn from(err: std::io::Error) -> Self {
+        StrandError::Io(err)
+    }
+}
+
+fn parse_number(token: &str) -> Result<FloatTy, StrandError> {
+    token
+        .parse()
+        .map_err(|_| StrandError::Parse(format!("invalid number `{}`", token)))
+}
+
+pub fn load_strands<P: AsRef<Path>>(
+    path: P,
+    curve_type: CurveType,
+    material: Arc<dyn Material>,
+) -> Result<Vec<Box<dyn Hittable>>, StrandError> {
+    let content = fs::read_to_string(path)?;
+    parse_strands(&content, curve_type, material)
+}
+
+/// Parses strands written one per line as the `x y z width` values of their
+/// points one after the other. Empty lines and lines starting with `#` are
+/// skipped.
+pub fn parse_strands(
+    content: &str,
+    curve_type: CurveType,
+    material: Arc<dyn Material>,
+) -> Result<Vec<Box<dyn Hittable>>, StrandError> {
+    let mut curves = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let values = line
+            .split_whitespace()
+            .map(parse_number)
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() % 4 != 0 || values.len() < 8 {
+            return Err(StrandError::Parse(format!(
+                "line {} does not hold at least two `x y z width` points",
+                index + 1
+            )));
+        }
+
+        let points: Vec<Pt3> = values
+            .chunks(4)
+            .map(|p| Pt3::new(p[0], p[1], p[2]))
+            .collect();
+        let widths: Vec<FloatTy> = values.chunks(4).map(|p| p[3]).collect();
+        curves.extend(curves_from_polyline(
+            &points,
+            &widths,
+            curve_type,
+            material.clone(),
+        ));
+    }
+    Ok(curves)
+}
+
+pub fn load_polylines<P: AsRef<Path>>(
+    path: P,
+    root_width: FloatTy,
+    tip_width: FloatTy,
+    curve_type: CurveType,
+    material: Arc<dyn Material>,
+) -> Result<Vec<Box<dyn Hittable>>, StrandError> {
+    let content = fs::read_to_string(path)?;
+    parse_polylines(&content, root_width, tip_width, curve_type, material)
+}
+
+/// Parses the `v` vertices and `l` polylines of an OBJ file, as written by
+/// Blender for curves and hair converted to meshes. The width goes from
+/// `root_width` at the first point of each polyline to `tip_width` at its
+/// last one.
+pub fn parse_polylines(
+    content: &str,
+    root_width: FloatTy,
+    tip_width: FloatTy,
+    curve_type: CurveType,
+    material: Arc<dyn Material>,
+) -> Result<Vec<Box<dyn Hittable>>, StrandError> {
+    let mut vertices = Vec::new();
+    let mut curves = Vec::new();
+
+    for line in content.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let coords = tokens
+                    .take(3)
+                    .map(parse_number)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if coords.len() != 3 {
+                    return Err(StrandError::Parse(
+                        "vertex without 3 coordinates".to_owned(),
+                    ));
+                }
+                vertices.push(Pt3::new(coords[0], coords[1], coords[2]));
+            }
+            Some("l") => {
+                let points = tokens
+                    .map(|token| {
+                        // elements may also reference texture coordinates
+                        let index = token.split('/').next().unwrap_or(token);
+                        let index: i64 = index.parse().map_err(|_| {
+                            StrandError::Parse(format!("invalid index `{}`", token))
+                        })?;
+                        // indices start at 1, negative ones count back from
+                        // the last vertex read
+                        let resolved = if index < 0 {
+                            vertices.len() as i64 + index
+                        } else {
+                            index - 1
+                        };
+                        usize::try_from(resolved)
+                            .ok()
+                            .and_then(|resolved| vertices.get(resolved))
+                            .cloned()
+                            .ok_or_else(|| {
+                                StrandError::Parse(format!("index {} out of range", index))
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let last = points.len().saturating_sub(1).max(1) as FloatTy;
+                let widths: Vec<FloatTy> = (0..points.len())
+                    .map(|i| {
+                        let t = i as FloatTy / last;
+                        root_width * (1.0 - t) + tip_width * t
+                    })
+                    .collect();
+                curves.extend(curves_from_polyline(
+                    &points,
+                    &widths,
+                    curve_type,
+                    material.clone(),
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(curves)
+}
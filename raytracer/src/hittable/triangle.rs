@@ -62,12 +62,15 @@ impl TriangleBuilder {
             TriangleTexCoords::NoTexture
         };
 
+        let tangents = texcoords.compute_tangents(v1 - v0, v2 - v0);
+
         Triangle {
             v0,
             v1,
             v2,
             normal,
             texcoords,
+            tangents,
             material: self.material,
         }
     }
@@ -109,6 +112,24 @@ impl TriangleTexCoords {
             }
         }
     }
+
+    /// Derives the tangent and bitangent of the triangle from the UV
+    /// derivatives along its edges.
+    pub fn compute_tangents(&self, edge1: Vec3, edge2: Vec3) -> (Vec3, Vec3) {
+        if let TriangleTexCoords::Barycentric(a, b, c) = self {
+            let (du1, dv1) = (b[0] - a[0], b[1] - a[1]);
+            let (du2, dv2) = (c[0] - a[0], c[1] - a[1]);
+            let det = du1 * dv2 - dv1 * du2;
+            if det.abs() > fconsts::EPSILON {
+                let inv_det = 1.0 / det;
+                let tangent = (edge1 * dv2 - edge2 * dv1) * inv_det;
+                let bitangent = (edge2 * du1 - edge1 * du2) * inv_det;
+                return (tangent, bitangent);
+            }
+        }
+
+        (edge1, edge2)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -118,6 +139,7 @@ pub struct Triangle {
     pub v2: Pt3,
     pub normal: TriangleNormal,
     pub texcoords: TriangleTexCoords,
+    pub tangents: (Vec3, Vec3),
     pub material: Arc<dyn Material>,
 }
 
@@ -158,15 +180,12 @@ impl Hittable for Triangle {
         let normal = self.normal.compute_normal(u, v);
         let (u, v) = self.texcoords.compute_uv(u, v);
 
-        Some(HitRecord::new(
-            ray,
-            t,
-            p,
-            normal,
-            u,
-            v,
-            self.material.clone(),
-        ))
+        let (tangent, bitangent) = self.tangents;
+
+        Some(
+            HitRecord::new(ray, t, p, normal, u, v, self.material.clone())
+                .with_tangents(tangent, bitangent),
+        )
     }
 
     fn bounding_box(&self) -> Option<AABB> {
@@ -1,12 +1,26 @@
+use std::ops::{Add, AddAssign};
+
 use nalgebra::{Matrix4, Point3, Vector3};
 use rand;
 use rand::prelude::*;
 
+use crate::hittable::{HitCheckable, HitRecord};
+use crate::material::{MaterialScatter, Subsurface};
+
+mod aov;
+pub mod background;
 mod camera;
 mod color;
+mod denoise;
+mod display;
 pub mod hittable;
+pub mod light;
 pub mod material;
+pub mod medium;
 mod ray;
+pub mod scenes;
+mod spectral;
+pub mod spectrum;
 pub mod texture;
 mod utils;
 
@@ -15,52 +29,257 @@ pub type Vec3 = Vector3<FloatTy>;
 pub type Pt3 = Point3<FloatTy>;
 pub type Mat44 = Matrix4<FloatTy>;
 
+pub use crate::aov::*;
+pub use crate::background::Background;
 pub use crate::camera::*;
 pub use crate::color::*;
+pub use crate::denoise::*;
+pub use crate::display::*;
 pub use crate::hittable::{Hittable, HittableExt};
+pub use crate::light::LightSource;
 pub use crate::ray::*;
+pub use crate::spectral::{compute_spectral_aovs, compute_spectral_color};
 pub use crate::texture::Texture;
 
 const Q: FloatTy = 0.7;
+const TMIN: FloatTy = 0.01;
+
+/// Direct lighting at a hit point from the light sources that cannot be
+/// reached by scattered rays, calling `contribution` with the BSDF and the
+/// incoming radiance of every light that is not occluded.
+fn sample_lights<F: FnMut(Vec3, Vec3)>(
+    objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
+    record: &HitRecord,
+    mut contribution: F,
+) {
+    for light in lights {
+        if let Some(sample) = light.sample(record.p) {
+            let bsdf = record.material.eval(&record.ray, record, sample.direction);
+            if bsdf.iter().all(|c| *c <= 0.0) {
+                continue;
+            }
+
+            let shadow_ray = Ray::new(record.p, sample.direction);
+            let tmax = sample.distance.map(|d| d - TMIN);
+            if !objects.check_hit_by(shadow_ray, TMIN, tmax) {
+                contribution(bsdf, sample.radiance);
+            }
+        }
+    }
+}
 
 pub fn compute_color<R: Rng>(
     objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
+    ray: Ray,
+    background: &dyn Background,
+    rng: &mut R,
+) -> Vec3 {
+    trace(&Rgb, objects, lights, ray, background, true, rng)
+}
+
+/// What paths carry through the scene: RGB colors, or the values of a
+/// spectrum at a few wavelengths in spectral rendering.
+trait Carrier {
+    type Light: Copy + Add<Output = Self::Light> + AddAssign;
+
+    fn black(&self) -> Self::Light;
+
+    /// Light of a radiance given in linear RGB.
+    fn emitted(&self, radiance: Vec3) -> Self::Light;
+
+    /// `light` scaled by a reflectance or scattering weight given in linear
+    /// RGB.
+    fn reflected(&self, light: Self::Light, weight: Vec3) -> Self::Light;
+
+    /// Gives the ray the wavelengths followed by the path.
+    fn ray(&self, ray: Ray) -> Ray;
+
+    /// Light leaving a dispersive surface, which only some wavelengths can
+    /// follow.
+    fn dispersed(&self, light: Self::Light) -> Self::Light;
+
+    fn to_rgb(&self, light: Self::Light) -> Vec3;
+}
+
+/// Paths carrying RGB colors, on which materials are never dispersive.
+struct Rgb;
+
+impl Carrier for Rgb {
+    type Light = Vec3;
+
+    fn black(&self) -> Vec3 {
+        Vec3::zeros()
+    }
+
+    fn emitted(&self, radiance: Vec3) -> Vec3 {
+        radiance
+    }
+
+    fn reflected(&self, light: Vec3, weight: Vec3) -> Vec3 {
+        light.component_mul(&weight)
+    }
+
+    fn ray(&self, ray: Ray) -> Ray {
+        ray
+    }
+
+    fn dispersed(&self, light: Vec3) -> Vec3 {
+        light
+    }
+
+    fn to_rgb(&self, light: Vec3) -> Vec3 {
+        light
+    }
+}
+
+/// `sees_lights` tells whether the lights at infinity are visible to the ray,
+/// which is not the case when they were already sampled from its origin.
+fn trace<C: Carrier, R: Rng>(
+    carrier: &C,
+    objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
     ray: Ray,
-    background: Vec3,
+    background: &dyn Background,
+    sees_lights: bool,
     rng: &mut R,
+) -> C::Light {
+    let (emitted, reflected) =
+        trace_parts(carrier, objects, lights, ray, background, sees_lights, rng);
+    emitted + reflected
+}
+
+/// Same as `trace`, keeping apart the light emitted by what the ray hits,
+/// background included, and the light reflected there.
+fn trace_parts<C: Carrier, R: Rng>(
+    carrier: &C,
+    objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
+    ray: Ray,
+    background: &dyn Background,
+    sees_lights: bool,
+    rng: &mut R,
+) -> (C::Light, C::Light) {
+    if let Some(record) = objects.is_hit_by(ray, TMIN, None) {
+        let shading = shade_parts(carrier, objects, lights, &record, background, rng);
+        (shading.emitted, shading.direct + shading.indirect)
+    } else {
+        let radiance = escaped_radiance(lights, ray, background, sees_lights);
+        (carrier.emitted(radiance), carrier.black())
+    }
+}
+
+fn escaped_radiance(
+    lights: &[Box<dyn LightSource>],
+    ray: Ray,
+    background: &dyn Background,
+    sees_lights: bool,
 ) -> Vec3 {
-    if let Some(record) = objects.is_hit_by(ray, 0.01, None) {
-        let emitted = record.material.emit(record.u, record.v, record.p);
+    let direction = ray.direction.normalize();
+    let mut color = background.radiance(direction);
+    if sees_lights {
+        for light in lights {
+            color += light.radiance(direction);
+        }
+    }
+    color
+}
 
-        if let Some(material_scatter) = record.material.scatter(&record.ray, &record) {
-            let scat_value = if rng.gen::<FloatTy>() < Q {
-                if let Some(scattered) = material_scatter.scattered {
-                    let brdf = material_scatter.attenuation;
+/// Translucent material under the surface that a ray scattered at a hit
+/// goes into, if any.
+fn entered_subsurface<'a>(
+    record: &HitRecord,
+    material_scatter: &MaterialScatter<'a>,
+    scattered: &Ray,
+) -> Option<&'a Subsurface> {
+    material_scatter
+        .subsurface
+        .filter(|_| record.front_face && scattered.direction.dot(&record.normal) < 0.0)
+}
 
-                    /*
-                    let cos_theta = Vec3::dot(scattered.direction, record.normal);
-                    let scattered_color =
-                        compute_color(objects, scattered, depth + 1, max_depth, background);
-                    emitted + Vec3::memberwise_product(scattered_color, brdf) * cos_theta * 2.0
-                    */
+/// Light leaving a surface point, split by the path it took.
+#[derive(Debug, Clone)]
+struct Shading<L> {
+    /// Light emitted by the surface itself.
+    emitted: L,
+    /// Light arriving from a light source or an emitter in a single bounce.
+    direct: L,
+    /// Light that bounced on other surfaces first.
+    indirect: L,
+    /// Weight of the scattered ray, whose average is the albedo of the
+    /// surface.
+    albedo: Vec3,
+}
 
-                    let scattered_color = compute_color(objects, scattered, background, rng);
+/// Light leaving a surface point toward the origin of the ray that hit it.
+fn shade_parts<C: Carrier, R: Rng>(
+    carrier: &C,
+    objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
+    record: &HitRecord,
+    background: &dyn Background,
+    rng: &mut R,
+) -> Shading<C::Light> {
+    let mut shading = Shading {
+        emitted: carrier.emitted(record.material.emit(&record.ray, record)),
+        direct: carrier.black(),
+        indirect: carrier.black(),
+        albedo: Vec3::zeros(),
+    };
+
+    if let Some(material_scatter) = record.material.scatter(&record.ray, record) {
+        sample_lights(objects, lights, record, |bsdf, radiance| {
+            shading.direct += carrier.reflected(carrier.emitted(radiance), bsdf)
+        });
+        shading.albedo = material_scatter.attenuation;
 
-                    scattered_color.component_mul(&brdf) / Q
+        if rng.gen::<FloatTy>() < Q {
+            if let Some(scattered) = material_scatter.scattered {
+                let scattered = carrier.ray(scattered);
+                let brdf = material_scatter.attenuation / Q;
+
+                let subsurface = entered_subsurface(record, &material_scatter, &scattered);
+                let (direct, indirect) = if let Some(subsurface) = subsurface {
+                    match subsurface.exit(objects, record, scattered, rng) {
+                        Some((exit, weight)) => {
+                            // light diffusing under the surface is direct
+                            // lighting of the object as a whole
+                            let exit =
+                                shade_parts(carrier, objects, lights, &exit, background, rng);
+                            (
+                                carrier.reflected(exit.emitted + exit.direct, weight),
+                                carrier.reflected(exit.indirect, weight),
+                            )
+                        }
+                        None => (carrier.black(), carrier.black()),
+                    }
                 } else {
-                    Vec3::zeros()
-                }
-            } else {
-                Vec3::zeros()
-            };
-
-            emitted + scat_value
-        } else {
-            emitted
+                    // directions the material cannot evaluate, like perfect
+                    // reflections, are never reached by light sampling
+                    let sees_lights = material_scatter.is_specular;
+                    trace_parts(
+                        carrier,
+                        objects,
+                        lights,
+                        scattered,
+                        background,
+                        sees_lights,
+                        rng,
+                    )
+                };
+
+                shading.direct += carrier.reflected(direct, brdf);
+                shading.indirect += carrier.reflected(indirect, brdf);
+            }
+        }
+
+        if record.material.is_dispersive() {
+            shading.direct = carrier.dispersed(shading.direct);
+            shading.indirect = carrier.dispersed(shading.indirect);
         }
-    } else {
-        background
     }
+    shading
 }
 
 pub mod fconsts {
@@ -68,4 +287,6 @@ pub mod fconsts {
     pub const PI: FloatTy = std::f64::consts::PI as _;
     pub const EPSILON: FloatTy = std::f32::EPSILON as _;
     pub const FRAC_PI_2: FloatTy = std::f64::consts::FRAC_PI_2 as _;
+    pub const LN_2: FloatTy = std::f64::consts::LN_2 as _;
+    pub const SQRT_2: FloatTy = std::f64::consts::SQRT_2 as _;
 }
@@ -0,0 +1,71 @@
+use super::{LightSample, LightSource};
+use crate::fconsts;
+use crate::utils::Frame;
+use crate::{FloatTy, Pt3, Vec3};
+
+use rand;
+use rand::prelude::*;
+
+/// Light at infinity, like the sun, arriving from a single direction or from
+/// a small disk when given an angular diameter.
+#[derive(Debug, Clone)]
+pub struct DirectionalLight {
+    direction: Vec3,
+    irradiance: Vec3,
+    cos_half_angle: FloatTy,
+}
+
+impl DirectionalLight {
+    /// `direction` is the direction the light travels in, `irradiance` the
+    /// power received by a surface perpendicular to it.
+    pub fn new(direction: Vec3, irradiance: Vec3) -> Self {
+        DirectionalLight {
+            direction: direction.normalize(),
+            irradiance,
+            cos_half_angle: 1.0,
+        }
+    }
+
+    /// Spreads the light over a disk of the given angular diameter in
+    /// degrees, giving soft shadows.
+    pub fn with_angular_diameter(self, angular_diameter: FloatTy) -> Self {
+        DirectionalLight {
+            cos_half_angle: (angular_diameter.to_radians() / 2.0).cos(),
+            ..self
+        }
+    }
+}
+
+impl LightSource for DirectionalLight {
+    fn sample(&self, _point: Pt3) -> Option<LightSample> {
+        let to_light = -self.direction;
+        let direction = if self.cos_half_angle < 1.0 {
+            let mut rng = rand::thread_rng();
+            let cos_theta = 1.0 - rng.gen::<FloatTy>() * (1.0 - self.cos_half_angle);
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * fconsts::PI * rng.gen::<FloatTy>();
+            Frame::from_normal(to_light).to_world(Vec3::new(
+                sin_theta * phi.cos(),
+                sin_theta * phi.sin(),
+                cos_theta,
+            ))
+        } else {
+            to_light
+        };
+
+        Some(LightSample {
+            direction,
+            distance: None,
+            radiance: self.irradiance,
+        })
+    }
+
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        if self.cos_half_angle >= 1.0 || -direction.dot(&self.direction) < self.cos_half_angle {
+            return Vec3::zeros();
+        }
+
+        let solid_angle = 2.0 * fconsts::PI * (1.0 - self.cos_half_angle);
+        self.irradiance / solid_angle
+    }
+}
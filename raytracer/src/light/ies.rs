@@ -0,0 +1,294 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use nalgebra::Matrix3;
+
+use crate::fconsts;
+use crate::utils::fmax;
+use crate::{FloatTy, Mat44, Vec3};
+
+#[derive(Debug)]
+pub enum IesError {
+    Io(std::io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for IesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IesError::Io(err) => write!(f, "cannot read IES file: {}", err),
+            IesError::Parse(msg) => write!(f, "invalid IES file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for IesError {}
+
+impl From<std::io::Error> for IesError {
+    fn from(err: std::io::Error) -> Self {
+        IesError::Io(err)
+    }
+}
+
+const PHOTOMETRIC_TYPE_C: usize = 1;
+
+/// Luminous intensity distribution of a luminaire, read from an IES LM-63
+/// photometric file.
+///
+/// In the local space of the profile the luminaire points down: a vertical
+/// angle of 0° is the -Y axis and 180° the +Y axis, while horizontal angles
+/// turn around Y starting from +X toward +Z. The orientation transform places
+/// this local space in the scene.
+#[derive(Debug, Clone)]
+pub struct IesProfile {
+    vertical_angles: Vec<FloatTy>,
+    horizontal_angles: Vec<FloatTy>,
+    /// Candela values, one row of vertical angles per horizontal angle.
+    candela: Vec<Vec<FloatTy>>,
+    max_candela: FloatTy,
+    world_to_local: Mat44,
+}
+
+impl IesProfile {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, IesError> {
+        let content = fs::read_to_string(path)?;
+        IesProfile::parse(&content)
+    }
+
+    /// Parses the content of an LM-63 file. Only type C photometry, used by
+    /// nearly all architectural luminaires, is supported.
+    pub fn parse(content: &str) -> Result<Self, IesError> {
+        let mut lines = content.lines();
+        let tilt = lines
+            .by_ref()
+            .map(str::trim)
+            .find(|line| line.starts_with("TILT="))
+            .ok_or_else(|| IesError::Parse("missing TILT line".to_owned()))?;
+
+        let mut values = Values::new(lines.flat_map(|line| {
+            line.split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|token| !token.is_empty())
+        }));
+
+        if tilt == "TILT=INCLUDE" {
+            // tilt data only matters for lamps that change output with their
+            // inclination, it is skipped
+            values.next()?;
+            let count = values.next_count()?;
+            for _ in 0..2 * count {
+                values.next()?;
+            }
+        } else if tilt != "TILT=NONE" {
+            return Err(IesError::Parse(
+                "external tilt files are not supported".to_owned(),
+            ));
+        }
+
+        let _lamp_count = values.next()?;
+        let _lumens_per_lamp = values.next()?;
+        let multiplier = values.next()?;
+        let vertical_count = values.next_count()?;
+        let horizontal_count = values.next_count()?;
+        let photometric_type = values.next_count()?;
+        let _units = values.next()?;
+        let _width = values.next()?;
+        let _length = values.next()?;
+        let _height = values.next()?;
+        let ballast_factor = values.next()?;
+        let _future_use = values.next()?;
+        let _input_watts = values.next()?;
+
+        if photometric_type != PHOTOMETRIC_TYPE_C {
+            return Err(IesError::Parse(format!(
+                "unsupported photometric type {}",
+                photometric_type
+            )));
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(IesError::Parse("empty candela table".to_owned()));
+        }
+
+        let vertical_angles = values.take(vertical_count)?;
+        let horizontal_angles = values.take(horizontal_count)?;
+        let scale = multiplier * ballast_factor;
+        let mut candela = Vec::with_capacity(horizontal_count);
+        for _ in 0..horizontal_count {
+            let row = values.take(vertical_count)?;
+            candela.push(row.into_iter().map(|c| fmax(c * scale, 0.0)).collect());
+        }
+
+        let is_sorted = |angles: &[FloatTy]| angles.windows(2).all(|w| w[0] < w[1]);
+        if !is_sorted(&vertical_angles) || !is_sorted(&horizontal_angles) {
+            return Err(IesError::Parse("angles are not increasing".to_owned()));
+        }
+
+        let max_candela = candela
+            .iter()
+            .flat_map(|row: &Vec<FloatTy>| row.iter().cloned())
+            .fold(0.0, fmax);
+
+        Ok(IesProfile {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+            max_candela,
+            world_to_local: Mat44::identity(),
+        })
+    }
+
+    /// Places the profile in the scene with a transform from its local space.
+    /// Only the rotational part of the transform is used, its scale and
+    /// shear being left out. `None` if the transform cannot be inverted.
+    pub fn with_orientation(self, transform: Mat44) -> Option<Self> {
+        // the closest rotation, from the polar decomposition of the linear
+        // part of the transform
+        let linear = Matrix3::<FloatTy>::from_fn(|i, j| transform[(i, j)]);
+        let svd = linear.svd(true, true);
+        let smallest = svd.singular_values.min();
+        if smallest.is_nan() || smallest <= fconsts::EPSILON * svd.singular_values.max() {
+            return None;
+        }
+        let rotation = svd.u? * svd.v_t?;
+        Some(IesProfile {
+            world_to_local: rotation.transpose().to_homogeneous(),
+            ..self
+        })
+    }
+
+    /// Highest luminous intensity of the luminaire, in candela.
+    pub fn max_candela(&self) -> FloatTy {
+        self.max_candela
+    }
+
+    /// Luminous intensity in `direction`, in candela.
+    pub fn candela(&self, direction: Vec3) -> FloatTy {
+        let local = self.world_to_local.transform_vector(&direction);
+        let norm = local.norm();
+        if norm <= 0.0 {
+            return 0.0;
+        }
+
+        let local = local / norm;
+        let vertical = (-local.y).clamp(-1.0, 1.0).acos().to_degrees();
+        let horizontal = local.z.atan2(local.x).to_degrees();
+        let horizontal = if horizontal < 0.0 {
+            horizontal + 360.0
+        } else {
+            horizontal
+        };
+
+        self.lookup(vertical, self.fold_horizontal(horizontal))
+    }
+
+    /// Intensity in `direction` relative to the brightest direction of the
+    /// luminaire, between 0 and 1.
+    pub fn intensity(&self, direction: Vec3) -> FloatTy {
+        if self.max_candela <= 0.0 {
+            return 0.0;
+        }
+        self.candela(direction) / self.max_candela
+    }
+
+    /// Brings a horizontal angle in [0, 360) into the range covered by the
+    /// file, using the symmetry implied by its last angle.
+    fn fold_horizontal(&self, angle: FloatTy) -> FloatTy {
+        let last = *self.horizontal_angles.last().unwrap();
+        if last <= 0.0 {
+            // rotationally symmetric
+            0.0
+        } else if last <= 90.0 {
+            // symmetric in each quadrant
+            let angle = angle % 180.0;
+            if angle > 90.0 {
+                180.0 - angle
+            } else {
+                angle
+            }
+        } else if last <= 180.0 {
+            // symmetric about the 0-180 plane
+            if angle > 180.0 {
+                360.0 - angle
+            } else {
+                angle
+            }
+        } else {
+            angle
+        }
+    }
+
+    fn lookup(&self, vertical: FloatTy, horizontal: FloatTy) -> FloatTy {
+        let (v0, v1, tv) = match interval(&self.vertical_angles, vertical) {
+            Some(interval) => interval,
+            // no light is emitted outside of the measured vertical angles
+            None => return 0.0,
+        };
+
+        let (h0, h1, th) = if self.horizontal_angles.len() == 1 {
+            (0, 0, 0.0)
+        } else {
+            let last = self.horizontal_angles.len() - 1;
+            let horizontal = horizontal
+                .max(self.horizontal_angles[0])
+                .min(self.horizontal_angles[last]);
+            interval(&self.horizontal_angles, horizontal).unwrap_or((last, last, 0.0))
+        };
+
+        let row = |h: usize| {
+            let candela = &self.candela[h];
+            candela[v0] * (1.0 - tv) + candela[v1] * tv
+        };
+        row(h0) * (1.0 - th) + row(h1) * th
+    }
+}
+
+/// Indices of the two angles around `angle` and the interpolation factor
+/// between them, `None` if it is outside of the table.
+fn interval(angles: &[FloatTy], angle: FloatTy) -> Option<(usize, usize, FloatTy)> {
+    let last = angles.len() - 1;
+    if angle < angles[0] || angle > angles[last] {
+        return None;
+    }
+    if last == 0 {
+        return Some((0, 0, 0.0));
+    }
+
+    let i = match angles.iter().position(|a| *a > angle) {
+        Some(i) => i - 1,
+        None => last - 1,
+    };
+    let t = (angle - angles[i]) / (angles[i + 1] - angles[i]);
+    Some((i, i + 1, t.min(1.0)))
+}
+
+struct Values<'a, I: Iterator<Item = &'a str>> {
+    tokens: I,
+}
+
+impl<'a, I: Iterator<Item = &'a str>> Values<'a, I> {
+    fn new(tokens: I) -> Self {
+        Values { tokens }
+    }
+
+    fn next(&mut self) -> Result<FloatTy, IesError> {
+        let token = self
+            .tokens
+            .next()
+            .ok_or_else(|| IesError::Parse("unexpected end of file".to_owned()))?;
+        token
+            .parse()
+            .map_err(|_| IesError::Parse(format!("invalid number `{}`", token)))
+    }
+
+    fn next_count(&mut self) -> Result<usize, IesError> {
+        let value = self.next()?;
+        if value < 0.0 || value.fract() != 0.0 {
+            return Err(IesError::Parse(format!("invalid count `{}`", value)));
+        }
+        Ok(value as usize)
+    }
+
+    fn take(&mut self, count: usize) -> Result<Vec<FloatTy>, IesError> {
+        (0..count).map(|_| self.next()).collect()
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{FloatTy, Pt3, Vec3};
+
+mod directional;
+mod ies;
+mod point;
+mod spot;
+pub use directional::*;
+pub use ies::*;
+pub use point::*;
+pub use spot::*;
+
+#[derive(Debug, Clone)]
+pub struct LightSample {
+    /// Unit direction from the shaded point toward the light.
+    pub direction: Vec3,
+    /// Distance to the light along `direction`, `None` for lights at infinity.
+    pub distance: Option<FloatTy>,
+    /// Radiance arriving at the shaded point, divided by the probability of
+    /// sampling `direction`.
+    pub radiance: Vec3,
+}
+
+/// Light source that cannot be hit by rays and has to be sampled explicitly
+/// from every shaded point.
+pub trait LightSource: Send + Sync + std::fmt::Debug {
+    fn sample(&self, point: Pt3) -> Option<LightSample>;
+
+    /// Radiance seen by a ray escaping the scene in `direction`, for lights at
+    /// infinity covering a visible part of the sky.
+    fn radiance(&self, _direction: Vec3) -> Vec3 {
+        Vec3::zeros()
+    }
+}
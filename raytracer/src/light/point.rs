@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use super::{IesProfile, LightSample, LightSource};
+use crate::fconsts;
+use crate::spectrum;
+use crate::{FloatTy, Pt3, Vec3};
+
+/// Infinitesimal light emitting the same intensity in every direction.
+#[derive(Debug, Clone)]
+pub struct PointLight {
+    position: Pt3,
+    intensity: Vec3,
+    profile: Option<Arc<IesProfile>>,
+}
+
+impl PointLight {
+    pub fn new(position: Pt3, intensity: Vec3) -> Self {
+        PointLight {
+            position,
+            intensity,
+            profile: None,
+        }
+    }
+
+    /// Point light of the given color radiating `watts` in total. The color
+    /// only gives the chromaticity of the light, black giving no light.
+    pub fn from_power(position: Pt3, color: Vec3, watts: FloatTy) -> Self {
+        let color = spectrum::normalize_luminance(color);
+        PointLight::new(position, color * (watts / (4.0 * fconsts::PI)))
+    }
+
+    /// Luminaire of the given color whose intensity in every direction is
+    /// given by the candela values of a photometric profile.
+    pub fn from_profile(position: Pt3, color: Vec3, profile: Arc<IesProfile>) -> Self {
+        let color = spectrum::normalize_luminance(color);
+        let intensity = color * (profile.max_candela() / spectrum::LUMENS_PER_WATT);
+        PointLight::new(position, intensity).with_profile(profile)
+    }
+
+    /// Modulates the intensity by emission direction, relative to the
+    /// brightest direction of the profile.
+    pub fn with_profile(self, profile: Arc<IesProfile>) -> Self {
+        PointLight {
+            profile: Some(profile),
+            ..self
+        }
+    }
+}
+
+impl LightSource for PointLight {
+    fn sample(&self, point: Pt3) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance2 = to_light.norm_squared();
+        if distance2 <= 0.0 {
+            return None;
+        }
+
+        let distance = distance2.sqrt();
+        let direction = to_light / distance;
+        let profile = self
+            .profile
+            .as_ref()
+            .map(|profile| profile.intensity(-direction))
+            .unwrap_or(1.0);
+        if profile <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance: Some(distance),
+            radiance: self.intensity * (profile / distance2),
+        })
+    }
+}
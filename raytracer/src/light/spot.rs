@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use super::{IesProfile, LightSample, LightSource};
+use crate::{FloatTy, Pt3, Vec3};
+
+/// Point light restricted to a cone, fading out smoothly between the falloff
+/// angle and the cone angle.
+#[derive(Debug, Clone)]
+pub struct SpotLight {
+    position: Pt3,
+    direction: Vec3,
+    intensity: Vec3,
+    cos_cone: FloatTy,
+    cos_falloff: FloatTy,
+    profile: Option<Arc<IesProfile>>,
+}
+
+impl SpotLight {
+    /// Angles are half-angles from the axis of the spot, in degrees.
+    pub fn new(
+        position: Pt3,
+        direction: Vec3,
+        intensity: Vec3,
+        cone_angle: FloatTy,
+        falloff_angle: FloatTy,
+    ) -> Self {
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            intensity,
+            cos_cone: cone_angle.to_radians().cos(),
+            cos_falloff: falloff_angle.min(cone_angle).to_radians().cos(),
+            profile: None,
+        }
+    }
+
+    /// Modulates the intensity by emission direction, relative to the
+    /// brightest direction of the profile, on top of the cone falloff.
+    pub fn with_profile(self, profile: Arc<IesProfile>) -> Self {
+        SpotLight {
+            profile: Some(profile),
+            ..self
+        }
+    }
+
+    fn falloff(&self, cos_theta: FloatTy) -> FloatTy {
+        if cos_theta <= self.cos_cone {
+            return 0.0;
+        }
+        if cos_theta >= self.cos_falloff {
+            return 1.0;
+        }
+
+        let t = (cos_theta - self.cos_cone) / (self.cos_falloff - self.cos_cone);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl LightSource for SpotLight {
+    fn sample(&self, point: Pt3) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance2 = to_light.norm_squared();
+        if distance2 <= 0.0 {
+            return None;
+        }
+
+        let distance = distance2.sqrt();
+        let direction = to_light / distance;
+        let falloff = self.falloff(-direction.dot(&self.direction))
+            * self
+                .profile
+                .as_ref()
+                .map(|profile| profile.intensity(-direction))
+                .unwrap_or(1.0);
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance: Some(distance),
+            radiance: self.intensity * (falloff / distance2),
+        })
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use super::microfacet::Ggx;
+use super::{utils, Material, MaterialScatter};
+use crate::hittable::HitRecord;
+use crate::utils::{fmax, Frame};
+use crate::{FloatTy, Ray, Vec3};
+
+use rand;
+use rand::prelude::*;
+
+/// Clear dielectric layer over another material, like the varnish of wood or
+/// the clearcoat of a car paint.
+///
+/// Light arriving on the coat is reflected by its Fresnel term, the rest goes
+/// through to the base and loses again the part reflected back inside the
+/// coat on its way out.
+#[derive(Debug)]
+pub struct Coated {
+    base: Arc<dyn Material>,
+    reflective_index: FloatTy,
+    distribution: Ggx,
+    tint: Vec3,
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, reflective_index: FloatTy) -> Self {
+        Coated {
+            base,
+            reflective_index,
+            distribution: Ggx::from_roughness(0.0),
+            tint: Vec3::repeat(1.0),
+        }
+    }
+
+    pub fn with_roughness(self, roughness: FloatTy) -> Self {
+        Coated {
+            distribution: Ggx::from_roughness(roughness),
+            ..self
+        }
+    }
+
+    /// Color of the base seen through the coat at normal incidence, darker at
+    /// grazing angles where light travels longer in the coat.
+    pub fn with_tint(self, tint: Vec3) -> Self {
+        Coated { tint, ..self }
+    }
+
+    fn fresnel(&self, cos: FloatTy) -> FloatTy {
+        utils::fresnel_dielectric(cos, self.reflective_index)
+    }
+
+    /// Transmittance of the coat and of its interfaces for light reaching the
+    /// base from `wi` and leaving toward `wo`, besides the Fresnel reflection
+    /// of `wo` that decides which layer is sampled.
+    fn transmittance(&self, wo: Vec3, wi: Vec3) -> Vec3 {
+        let cos_o = fmax(wo.z.abs(), 1e-3);
+        let cos_i = fmax(wi.z.abs(), 1e-3);
+        let path_length = (1.0 / cos_o + 1.0 / cos_i) / 2.0;
+        self.tint.map(|c| c.powf(path_length)) * (1.0 - self.fresnel(cos_i))
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        // the coat is on the outside of the surface
+        if !record.front_face {
+            return self.base.scatter(ray, record);
+        }
+
+        let mut rng = rand::thread_rng();
+        let frame = Frame::from_normal(record.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        if wo.z <= 0.0 {
+            return self.base.scatter(ray, record);
+        }
+
+        if rng.gen::<FloatTy>() < self.fresnel(wo.z) {
+            let m = self
+                .distribution
+                .sample_visible_normal(wo, rng.gen(), rng.gen());
+            let wi = utils::reflect_local(wo, m);
+            let scattered = if wi.z > 0.0 {
+                Some(Ray::new(record.p, frame.to_world(wi)))
+            } else {
+                None
+            };
+
+            // the macro-surface Fresnel term used to pick the coat is replaced
+            // by the one of the sampled microfacet
+            let fresnel = self.fresnel(wo.dot(&m)) / self.fresnel(wo.z);
+            let shadowing = self.distribution.g2(wo, wi) / self.distribution.g1(wo);
+            Some(MaterialScatter {
+                attenuation: Vec3::repeat(fresnel * shadowing),
+                scattered,
+                is_specular: self.distribution.is_smooth(),
+                subsurface: None,
+            })
+        } else {
+            let mut scatter = self.base.scatter(ray, record)?;
+            if let Some(scattered) = &scatter.scattered {
+                let wi = frame.to_local(scattered.direction.normalize());
+                scatter.attenuation = scatter
+                    .attenuation
+                    .component_mul(&self.transmittance(wo, wi));
+            }
+            Some(scatter)
+        }
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        if !record.front_face {
+            return self.base.eval(ray, record, direction);
+        }
+
+        let frame = Frame::from_normal(record.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        if wo.z <= 0.0 {
+            return self.base.eval(ray, record, direction);
+        }
+
+        let base = self
+            .base
+            .eval(ray, record, direction)
+            .component_mul(&self.transmittance(wo, wi))
+            * (1.0 - self.fresnel(wo.z));
+
+        if wi.z <= 0.0 || self.distribution.is_smooth() {
+            return base;
+        }
+        let h = (wo + wi).normalize();
+        let coat = self.fresnel(wo.dot(&h)) * self.distribution.reflection(wo, wi);
+        base + Vec3::repeat(coat)
+    }
+
+    fn emit(&self, ray: &Ray, record: &HitRecord) -> Vec3 {
+        self.base.emit(ray, record)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.base.is_dispersive()
+    }
+}
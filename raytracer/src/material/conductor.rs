@@ -0,0 +1,121 @@
+use super::microfacet::Ggx;
+use super::utils;
+use super::{Material, MaterialScatter};
+use crate::hittable::HitRecord;
+use crate::utils::Frame;
+use crate::{FloatTy, Ray, Vec3};
+
+use rand;
+use rand::prelude::*;
+
+/// Rough metal described by its complex index of refraction `eta + i * k`
+/// and a GGX microfacet distribution.
+///
+/// The roughness can differ along the tangent and the bitangent of the
+/// surface, stretching highlights like on brushed metal.
+#[derive(Debug, Clone)]
+pub struct Conductor {
+    eta: Vec3,
+    k: Vec3,
+    distribution: Ggx,
+}
+
+impl Conductor {
+    pub fn new(eta: Vec3, k: Vec3, roughness: FloatTy) -> Self {
+        Conductor {
+            eta,
+            k,
+            distribution: Ggx::from_roughness(roughness),
+        }
+    }
+
+    pub fn gold(roughness: FloatTy) -> Self {
+        Conductor::new(
+            Vec3::new(0.143, 0.374, 1.442),
+            Vec3::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: FloatTy) -> Self {
+        Conductor::new(
+            Vec3::new(0.200, 0.924, 1.102),
+            Vec3::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: FloatTy) -> Self {
+        Conductor::new(
+            Vec3::new(1.657, 0.880, 0.521),
+            Vec3::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    /// Sets different roughnesses along the tangent and the bitangent of the
+    /// hit, the highlights being stretched along the rougher direction.
+    pub fn with_anisotropic_roughness(
+        self,
+        along_tangent: FloatTy,
+        along_bitangent: FloatTy,
+    ) -> Self {
+        Conductor {
+            distribution: Ggx::new(
+                along_tangent * along_tangent,
+                along_bitangent * along_bitangent,
+            ),
+            ..self
+        }
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let mut rng = rand::thread_rng();
+        let frame = Frame::from_tangents(record.normal, record.tangent, record.bitangent);
+        let wo = frame.to_local(-ray.direction.normalize());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let m = self
+            .distribution
+            .sample_visible_normal(wo, rng.gen(), rng.gen());
+        let wi = utils::reflect_local(wo, m);
+
+        let scattered = if wi.z > 0.0 {
+            Some(Ray::new(record.p, frame.to_world(wi)))
+        } else {
+            None
+        };
+
+        let fresnel = utils::fresnel_conductor(wo.dot(&m), self.eta, self.k);
+        let shadowing = self.distribution.g2(wo, wi) / self.distribution.g1(wo);
+
+        Some(MaterialScatter {
+            attenuation: fresnel * shadowing,
+            scattered,
+            is_specular: self.distribution.is_smooth(),
+            subsurface: None,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        if self.distribution.is_smooth() {
+            return Vec3::zeros();
+        }
+
+        let frame = Frame::from_tangents(record.normal, record.tangent, record.bitangent);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Vec3::zeros();
+        }
+
+        let h = (wo + wi).normalize();
+
+        let fresnel = utils::fresnel_conductor(wo.dot(&h), self.eta, self.k);
+        fresnel * self.distribution.reflection(wo, wi)
+    }
+}
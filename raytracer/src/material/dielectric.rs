@@ -1,4 +1,4 @@
-use super::{utils, Material, MaterialScatter};
+use super::{utils, Ior, Material, MaterialScatter};
 use crate::hittable::HitRecord;
 use crate::utils::fmin;
 use crate::{FloatTy, Ray, Vec3};
@@ -8,31 +8,34 @@ use rand::prelude::*;
 
 #[derive(Debug, Clone)]
 pub struct Dielectric {
-    reflective_index: FloatTy,
+    reflective_index: Ior,
 }
 
 impl Dielectric {
-    pub fn new(reflective_index: FloatTy) -> Self {
-        Dielectric { reflective_index }
+    pub fn new<I: Into<Ior>>(reflective_index: I) -> Self {
+        Dielectric {
+            reflective_index: reflective_index.into(),
+        }
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter> {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
         let mut rng = rand::thread_rng();
         let attenuation = Vec3::repeat(1.0);
+        let reflective_index = self.reflective_index.at(ray.wavelength);
 
         let n1_over_n2 = if record.front_face {
-            1.0 / self.reflective_index
+            1.0 / reflective_index
         } else {
-            self.reflective_index
+            reflective_index
         };
 
         let uv = ray.direction.normalize();
         let cos_theta = fmin((-uv).dot(&record.normal), 1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
-        let reflect_prob = utils::schlick(cos_theta, self.reflective_index);
+        let reflect_prob = utils::schlick(cos_theta, reflective_index);
 
         if n1_over_n2 * sin_theta > 1.0 || rng.gen::<FloatTy>() < reflect_prob {
             let reflected = utils::reflect(uv, record.normal);
@@ -40,6 +43,8 @@ impl Material for Dielectric {
             Some(MaterialScatter {
                 attenuation,
                 scattered: Some(scattered),
+                is_specular: true,
+                subsurface: None,
             })
         } else {
             let refracted = utils::refract(uv, record.normal, n1_over_n2);
@@ -47,7 +52,13 @@ impl Material for Dielectric {
             Some(MaterialScatter {
                 attenuation,
                 scattered: Some(scattered),
+                is_specular: true,
+                subsurface: None,
             })
         }
     }
+
+    fn is_dispersive(&self) -> bool {
+        self.reflective_index.is_dispersive()
+    }
 }
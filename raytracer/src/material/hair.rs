@@ -0,0 +1,419 @@
+use super::{utils, Material, MaterialScatter};
+use crate::fconsts;
+use crate::hittable::HitRecord;
+use crate::utils::{clamp, fmax, Frame};
+use crate::{FloatTy, Ray, Vec3};
+
+use rand;
+use rand::prelude::*;
+
+/// Number of scattering lobes modeled explicitly, the remaining light being
+/// gathered in a last isotropic lobe.
+const P_MAX: usize = 3;
+const SQRT_PI_OVER_8: FloatTy = 0.626_657_068_657_750_1;
+
+const EUMELANIN_SIGMA_A: [FloatTy; 3] = [0.419, 0.697, 1.37];
+const PHEOMELANIN_SIGMA_A: [FloatTy; 3] = [0.187, 0.4, 1.05];
+
+fn safe_sqrt(x: FloatTy) -> FloatTy {
+    fmax(x, 0.0).sqrt()
+}
+
+fn safe_asin(x: FloatTy) -> FloatTy {
+    clamp(x, -1.0, 1.0).asin()
+}
+
+/// Modified Bessel function of the first kind and order zero.
+fn i0(x: FloatTy) -> FloatTy {
+    let mut value = 0.0;
+    let mut x2i = 1.0;
+    let mut ifact = 1.0;
+    let mut i4 = 1.0;
+    for i in 0..10 {
+        if i > 1 {
+            ifact *= i as FloatTy;
+        }
+        value += x2i / (i4 * ifact * ifact);
+        x2i *= x * x;
+        i4 *= 4.0;
+    }
+    value
+}
+
+fn log_i0(x: FloatTy) -> FloatTy {
+    if x > 12.0 {
+        x + 0.5 * (-(2.0 * fconsts::PI).ln() + (1.0 / x).ln() + 1.0 / (8.0 * x))
+    } else {
+        i0(x).ln()
+    }
+}
+
+/// Longitudinal scattering function.
+fn mp(
+    cos_theta_i: FloatTy,
+    cos_theta_o: FloatTy,
+    sin_theta_i: FloatTy,
+    sin_theta_o: FloatTy,
+    v: FloatTy,
+) -> FloatTy {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+    if v <= 0.1 {
+        // the direct formula overflows for low roughnesses
+        (log_i0(a) - b - 1.0 / v + fconsts::LN_2 + (1.0 / (2.0 * v)).ln()).exp()
+    } else {
+        (-b).exp() * i0(a) / ((1.0 / v).sinh() * 2.0 * v)
+    }
+}
+
+/// Attenuation of each lobe, `transmittance` being the one of a single path
+/// through the fiber.
+fn ap(cos_theta_o: FloatTy, eta: FloatTy, h: FloatTy, transmittance: Vec3) -> [Vec3; P_MAX + 1] {
+    let cos_gamma_o = safe_sqrt(1.0 - h * h);
+    let cos_theta = cos_theta_o * cos_gamma_o;
+    let f = utils::fresnel_dielectric(cos_theta, eta);
+
+    let r = Vec3::repeat(f);
+    let tt = transmittance * ((1.0 - f) * (1.0 - f));
+    let trt = tt.component_mul(&transmittance) * f;
+    let rest = trt
+        .component_mul(&transmittance)
+        .zip_map(&transmittance, |trt, t| trt * f / fmax(1.0 - t * f, 1e-4));
+    [r, tt, trt, rest]
+}
+
+/// Azimuthal angle of the light leaving lobe `p`.
+fn phi(p: usize, gamma_o: FloatTy, gamma_t: FloatTy) -> FloatTy {
+    let p = p as FloatTy;
+    2.0 * p * gamma_t - 2.0 * gamma_o + p * fconsts::PI
+}
+
+fn logistic(x: FloatTy, s: FloatTy) -> FloatTy {
+    let x = x.abs();
+    (-x / s).exp() / (s * (1.0 + (-x / s).exp()).powi(2))
+}
+
+fn logistic_cdf(x: FloatTy, s: FloatTy) -> FloatTy {
+    1.0 / (1.0 + (-x / s).exp())
+}
+
+fn trimmed_logistic(x: FloatTy, s: FloatTy, a: FloatTy, b: FloatTy) -> FloatTy {
+    logistic(x, s) / (logistic_cdf(b, s) - logistic_cdf(a, s))
+}
+
+fn sample_trimmed_logistic(u: FloatTy, s: FloatTy, a: FloatTy, b: FloatTy) -> FloatTy {
+    let k = logistic_cdf(b, s) - logistic_cdf(a, s);
+    let x = -s * (1.0 / (u * k + logistic_cdf(a, s)) - 1.0).ln();
+    clamp(x, a, b)
+}
+
+/// Azimuthal scattering function.
+fn np(phi_diff: FloatTy, p: usize, s: FloatTy, gamma_o: FloatTy, gamma_t: FloatTy) -> FloatTy {
+    let mut dphi = phi_diff - phi(p, gamma_o, gamma_t);
+    while dphi > fconsts::PI {
+        dphi -= 2.0 * fconsts::PI;
+    }
+    while dphi < -fconsts::PI {
+        dphi += 2.0 * fconsts::PI;
+    }
+    trimmed_logistic(dphi, s, -fconsts::PI, fconsts::PI)
+}
+
+/// Angles of a direction in the local frame of the fiber, whose first axis
+/// follows the fiber: `(sin θ, cos θ, φ)`.
+fn angles(w: Vec3) -> (FloatTy, FloatTy, FloatTy) {
+    let sin_theta = w.x;
+    (
+        sin_theta,
+        safe_sqrt(1.0 - sin_theta * sin_theta),
+        w.z.atan2(w.y),
+    )
+}
+
+/// Scattering of light by hair and fur fibers, following the model of
+/// Chiang et al. (2016) built on the one of Marschner et al. (2003) and
+/// d'Eon et al. (2011).
+///
+/// The fiber follows the tangent of the hit, and the `v` texture coordinate
+/// goes across its width, as on curve primitives.
+#[derive(Debug, Clone)]
+pub struct Hair {
+    sigma_a: Vec3,
+    eta: FloatTy,
+    /// Longitudinal variance of each lobe.
+    v: [FloatTy; P_MAX + 1],
+    /// Azimuthal logistic scale.
+    s: FloatTy,
+    sin_2k_alpha: [FloatTy; 3],
+    cos_2k_alpha: [FloatTy; 3],
+}
+
+impl Hair {
+    /// `sigma_a` is the absorption coefficient inside the fiber, relative to
+    /// its diameter. The longitudinal and azimuthal roughnesses go from 0 to
+    /// 1, typical hair being around 0.3.
+    pub fn new(
+        sigma_a: Vec3,
+        longitudinal_roughness: FloatTy,
+        azimuthal_roughness: FloatTy,
+    ) -> Self {
+        let beta_m = clamp(longitudinal_roughness, 0.01, 1.0);
+        let beta_n = clamp(azimuthal_roughness, 0.01, 1.0);
+
+        let v0 = (0.726 * beta_m + 0.812 * beta_m.powi(2) + 3.7 * beta_m.powi(20)).powi(2);
+        let s =
+            SQRT_PI_OVER_8 * (0.265 * beta_n + 1.194 * beta_n.powi(2) + 5.372 * beta_n.powi(22));
+
+        Hair {
+            sigma_a,
+            eta: 1.55,
+            v: [v0, 0.25 * v0, 4.0 * v0, 4.0 * v0],
+            s,
+            sin_2k_alpha: [0.0; 3],
+            cos_2k_alpha: [1.0; 3],
+        }
+        .with_scale_angle(2.0)
+    }
+
+    /// Hair colored by its concentration of eumelanin, from 0 for blond to 8
+    /// for black hair, and of pheomelanin, giving red hair.
+    pub fn from_melanin(
+        eumelanin: FloatTy,
+        pheomelanin: FloatTy,
+        longitudinal_roughness: FloatTy,
+        azimuthal_roughness: FloatTy,
+    ) -> Self {
+        let sigma_a = Vec3::from(EUMELANIN_SIGMA_A) * eumelanin
+            + Vec3::from(PHEOMELANIN_SIGMA_A) * pheomelanin;
+        Hair::new(sigma_a, longitudinal_roughness, azimuthal_roughness)
+    }
+
+    /// Hair whose multiple scattering gives approximately `color`.
+    pub fn from_color(
+        color: Vec3,
+        longitudinal_roughness: FloatTy,
+        azimuthal_roughness: FloatTy,
+    ) -> Self {
+        let beta_n = clamp(azimuthal_roughness, 0.01, 1.0);
+        let fit = 5.969 - 0.215 * beta_n + 2.532 * beta_n.powi(2) - 10.73 * beta_n.powi(3)
+            + 5.574 * beta_n.powi(4)
+            + 0.245 * beta_n.powi(5);
+        let sigma_a = color.map(|c| (clamp(c, 1e-4, 1.0).ln() / fit).powi(2));
+        Hair::new(sigma_a, longitudinal_roughness, azimuthal_roughness)
+    }
+
+    /// Tilt of the cuticle scales in degrees, shifting the lobes along the
+    /// fiber.
+    pub fn with_scale_angle(self, alpha: FloatTy) -> Self {
+        let mut sin_2k_alpha = [alpha.to_radians().sin(), 0.0, 0.0];
+        let mut cos_2k_alpha = [safe_sqrt(1.0 - sin_2k_alpha[0].powi(2)), 0.0, 0.0];
+        for i in 1..3 {
+            sin_2k_alpha[i] = 2.0 * cos_2k_alpha[i - 1] * sin_2k_alpha[i - 1];
+            cos_2k_alpha[i] = cos_2k_alpha[i - 1].powi(2) - sin_2k_alpha[i - 1].powi(2);
+        }
+        Hair {
+            sin_2k_alpha,
+            cos_2k_alpha,
+            ..self
+        }
+    }
+
+    pub fn with_ior(self, eta: FloatTy) -> Self {
+        Hair { eta, ..self }
+    }
+
+    fn frame(record: &HitRecord) -> Frame {
+        let n = record.normal;
+        Frame::from_tangents(n, record.tangent, n.cross(&record.tangent))
+    }
+
+    /// Offset of the hit across the fiber, from -1 to 1.
+    fn offset(record: &HitRecord) -> FloatTy {
+        clamp(2.0 * record.v - 1.0, -1.0, 1.0)
+    }
+
+    /// Longitudinal angle of `wo` tilted by the scales for lobe `p`.
+    fn tilt(&self, p: usize, sin_theta_o: FloatTy, cos_theta_o: FloatTy) -> (FloatTy, FloatTy) {
+        let (sin_theta, cos_theta) = match p {
+            0 => (
+                sin_theta_o * self.cos_2k_alpha[1] - cos_theta_o * self.sin_2k_alpha[1],
+                cos_theta_o * self.cos_2k_alpha[1] + sin_theta_o * self.sin_2k_alpha[1],
+            ),
+            1 => (
+                sin_theta_o * self.cos_2k_alpha[0] + cos_theta_o * self.sin_2k_alpha[0],
+                cos_theta_o * self.cos_2k_alpha[0] - sin_theta_o * self.sin_2k_alpha[0],
+            ),
+            2 => (
+                sin_theta_o * self.cos_2k_alpha[2] + cos_theta_o * self.sin_2k_alpha[2],
+                cos_theta_o * self.cos_2k_alpha[2] - sin_theta_o * self.sin_2k_alpha[2],
+            ),
+            _ => (sin_theta_o, cos_theta_o),
+        };
+        (sin_theta, cos_theta.abs())
+    }
+
+    /// Lobe attenuations and the angles of the refracted ray inside the
+    /// fiber, `(γo, γt)`.
+    fn lobes(&self, wo: Vec3, h: FloatTy) -> ([Vec3; P_MAX + 1], FloatTy, FloatTy) {
+        let (sin_theta_o, cos_theta_o, _) = angles(wo);
+
+        let sin_theta_t = sin_theta_o / self.eta;
+        let cos_theta_t = fmax(safe_sqrt(1.0 - sin_theta_t * sin_theta_t), 1e-4);
+        let etap =
+            safe_sqrt(self.eta * self.eta - sin_theta_o * sin_theta_o) / fmax(cos_theta_o, 1e-4);
+        let sin_gamma_t = clamp(h / etap, -1.0, 1.0);
+        let cos_gamma_t = safe_sqrt(1.0 - sin_gamma_t * sin_gamma_t);
+
+        let transmittance = self
+            .sigma_a
+            .map(|s| (-s * 2.0 * cos_gamma_t / cos_theta_t).exp());
+        let ap = ap(cos_theta_o, self.eta, h, transmittance);
+        (ap, safe_asin(h), sin_gamma_t.asin())
+    }
+
+    /// Selection probabilities of the lobes when sampling.
+    fn lobe_pdf(ap: &[Vec3; P_MAX + 1]) -> [FloatTy; P_MAX + 1] {
+        let weights = [ap[0].mean(), ap[1].mean(), ap[2].mean(), ap[3].mean()];
+        let sum: FloatTy = weights.iter().sum();
+        if sum <= 0.0 {
+            return [0.25; P_MAX + 1];
+        }
+        [
+            weights[0] / sum,
+            weights[1] / sum,
+            weights[2] / sum,
+            weights[3] / sum,
+        ]
+    }
+
+    /// BSDF times the cosine with the normal in the local frame.
+    fn local_eval(&self, wo: Vec3, wi: Vec3, h: FloatTy) -> Vec3 {
+        let (sin_theta_o, cos_theta_o, phi_o) = angles(wo);
+        let (sin_theta_i, cos_theta_i, phi_i) = angles(wi);
+        let (ap, gamma_o, gamma_t) = self.lobes(wo, h);
+        let phi_diff = phi_i - phi_o;
+
+        let mut value = Vec3::zeros();
+        for (p, ap) in ap.iter().enumerate().take(P_MAX) {
+            let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+            value += ap
+                * (mp(
+                    cos_theta_i,
+                    cos_theta_op,
+                    sin_theta_i,
+                    sin_theta_op,
+                    self.v[p],
+                ) * np(phi_diff, p, self.s, gamma_o, gamma_t));
+        }
+        value += ap[P_MAX]
+            * (mp(
+                cos_theta_i,
+                cos_theta_o,
+                sin_theta_i,
+                sin_theta_o,
+                self.v[P_MAX],
+            ) / (2.0 * fconsts::PI));
+        value
+    }
+
+    fn local_pdf(&self, wo: Vec3, wi: Vec3, h: FloatTy) -> FloatTy {
+        let (sin_theta_o, cos_theta_o, phi_o) = angles(wo);
+        let (sin_theta_i, cos_theta_i, phi_i) = angles(wi);
+        let (ap, gamma_o, gamma_t) = self.lobes(wo, h);
+        let lobe_pdf = Hair::lobe_pdf(&ap);
+        let phi_diff = phi_i - phi_o;
+
+        let mut pdf = 0.0;
+        for (p, lobe_pdf) in lobe_pdf.iter().enumerate().take(P_MAX) {
+            let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+            pdf += mp(
+                cos_theta_i,
+                cos_theta_op,
+                sin_theta_i,
+                sin_theta_op,
+                self.v[p],
+            ) * lobe_pdf
+                * np(phi_diff, p, self.s, gamma_o, gamma_t);
+        }
+        pdf + mp(
+            cos_theta_i,
+            cos_theta_o,
+            sin_theta_i,
+            sin_theta_o,
+            self.v[P_MAX],
+        ) * lobe_pdf[P_MAX]
+            / (2.0 * fconsts::PI)
+    }
+
+    fn sample<R: Rng>(&self, wo: Vec3, h: FloatTy, rng: &mut R) -> Vec3 {
+        let (sin_theta_o, cos_theta_o, phi_o) = angles(wo);
+        let (ap, gamma_o, gamma_t) = self.lobes(wo, h);
+        let lobe_pdf = Hair::lobe_pdf(&ap);
+
+        let u = rng.gen::<FloatTy>();
+        let mut p = P_MAX;
+        let mut cdf = 0.0;
+        for (i, pdf) in lobe_pdf.iter().enumerate().take(P_MAX) {
+            cdf += pdf;
+            if u < cdf {
+                p = i;
+                break;
+            }
+        }
+
+        // longitudinal angle around the tilted lobe
+        let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+        let v = self.v[p];
+        let u0 = fmax(rng.gen::<FloatTy>(), 1e-5);
+        let cos_theta = 1.0 + v * (u0 + (1.0 - u0) * (-2.0 / v).exp()).ln();
+        let sin_theta = safe_sqrt(1.0 - cos_theta * cos_theta);
+        let cos_phi = (2.0 * fconsts::PI * rng.gen::<FloatTy>()).cos();
+        let sin_theta_i = -cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op;
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i * sin_theta_i);
+
+        let dphi = if p < P_MAX {
+            phi(p, gamma_o, gamma_t)
+                + sample_trimmed_logistic(rng.gen(), self.s, -fconsts::PI, fconsts::PI)
+        } else {
+            2.0 * fconsts::PI * rng.gen::<FloatTy>()
+        };
+        let phi_i = phi_o + dphi;
+
+        Vec3::new(
+            sin_theta_i,
+            cos_theta_i * phi_i.cos(),
+            cos_theta_i * phi_i.sin(),
+        )
+    }
+}
+
+impl Material for Hair {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let mut rng = rand::thread_rng();
+        let frame = Hair::frame(record);
+        let h = Hair::offset(record);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = self.sample(wo, h, &mut rng);
+
+        let pdf = self.local_pdf(wo, wi, h);
+        let attenuation = if pdf > 0.0 {
+            self.local_eval(wo, wi, h) / pdf
+        } else {
+            Vec3::zeros()
+        };
+
+        Some(MaterialScatter {
+            attenuation,
+            scattered: Some(Ray::new(record.p, frame.to_world(wi))),
+            is_specular: false,
+            subsurface: None,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        let frame = Hair::frame(record);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        self.local_eval(wo, wi, Hair::offset(record))
+    }
+}
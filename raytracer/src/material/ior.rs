@@ -0,0 +1,71 @@
+use crate::FloatTy;
+
+/// Wavelength in nanometers used when a ray carries none, the sodium d line
+/// at which indices of refraction are usually given.
+pub const REFERENCE_WAVELENGTH: FloatTy = 587.6;
+
+/// Index of refraction of a dielectric, optionally varying with wavelength.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ior {
+    Constant(FloatTy),
+    /// Cauchy's equation `a + b / λ²`, with λ in micrometers.
+    Cauchy {
+        a: FloatTy,
+        b: FloatTy,
+    },
+    /// Sellmeier equation `n² = 1 + Σ b λ² / (λ² - c)`, with λ in
+    /// micrometers.
+    Sellmeier {
+        b: [FloatTy; 3],
+        c: [FloatTy; 3],
+    },
+}
+
+impl Ior {
+    /// Borosilicate crown glass, the most common optical glass.
+    pub fn bk7() -> Self {
+        Ior::Sellmeier {
+            b: [1.039_612_12, 0.231_792_344, 1.010_469_45],
+            c: [0.006_000_698_67, 0.020_017_914_4, 103.560_653],
+        }
+    }
+
+    pub fn fused_silica() -> Self {
+        Ior::Sellmeier {
+            b: [0.696_166_3, 0.407_942_6, 0.897_479_4],
+            c: [0.004_679_148, 0.013_512_063, 97.934_002_5],
+        }
+    }
+
+    pub fn diamond() -> Self {
+        Ior::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030_625, 0.011_236, 0.0],
+        }
+    }
+
+    /// Index of refraction at `wavelength` in nanometers, or at the
+    /// reference wavelength when there is none.
+    pub fn at(&self, wavelength: Option<FloatTy>) -> FloatTy {
+        let micrometers = wavelength.unwrap_or(REFERENCE_WAVELENGTH) / 1000.0;
+        let l2 = micrometers * micrometers;
+        match *self {
+            Ior::Constant(ior) => ior,
+            Ior::Cauchy { a, b } => a + b / l2,
+            Ior::Sellmeier { b, c } => {
+                let terms: FloatTy = b.iter().zip(c.iter()).map(|(b, c)| b * l2 / (l2 - c)).sum();
+                (1.0 + terms).sqrt()
+            }
+        }
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Ior::Constant(_))
+    }
+}
+
+impl From<FloatTy> for Ior {
+    fn from(ior: FloatTy) -> Self {
+        Ior::Constant(ior)
+    }
+}
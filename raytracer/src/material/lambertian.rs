@@ -1,6 +1,8 @@
 use super::{utils, Material, MaterialScatter};
+use crate::fconsts;
 use crate::hittable::HitRecord;
 use crate::texture::SolidTexture;
+use crate::utils::fmax;
 use crate::{Ray, Texture, Vec3};
 
 const HEMISPHERE_MODE: bool = false;
@@ -25,7 +27,7 @@ impl Lambertian<SolidTexture> {
 }
 
 impl<T: Texture> Material for Lambertian<T> {
-    fn scatter(&self, _: &Ray, record: &HitRecord) -> Option<MaterialScatter> {
+    fn scatter(&self, _: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
         let mut rng = rand::thread_rng();
         let new_direction = if HEMISPHERE_MODE {
             utils::random_unit_hemisphere(&mut rng, record.normal)
@@ -34,10 +36,17 @@ impl<T: Texture> Material for Lambertian<T> {
         };
 
         let scattered = Ray::new(record.p, new_direction);
-        let attenuation = self.texture.value(record.u, record.v);
+        let attenuation = self.texture.value(&record.surface_point());
         Some(MaterialScatter {
             attenuation,
             scattered: Some(scattered),
+            is_specular: false,
+            subsurface: None,
         })
     }
+
+    fn eval(&self, _: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        let cos = fmax(record.normal.dot(&direction), 0.0);
+        self.texture.value(&record.surface_point()) * (cos / fconsts::PI)
+    }
 }
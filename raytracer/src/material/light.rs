@@ -1,30 +1,104 @@
+use std::sync::Arc;
+
 use super::{Material, MaterialScatter};
+use crate::fconsts;
 use crate::hittable::HitRecord;
-use crate::{FloatTy, Pt3, Ray, Vec3};
+use crate::light::IesProfile;
+use crate::spectrum;
+use crate::texture::SolidTexture;
+use crate::{FloatTy, Ray, Texture, Vec3};
 
 #[derive(Debug, Clone)]
 pub struct Light {
-    emittance: Vec3,
+    emittance: Arc<dyn Texture>,
+    intensity: FloatTy,
+    two_sided: bool,
+    /// Whether `intensity` describes the total emitted power, which then has
+    /// to be shared between the two faces of a two-sided light.
+    split_power: bool,
+    profile: Option<Arc<IesProfile>>,
 }
 
 impl Light {
     pub fn white() -> Self {
+        Light::new(Vec3::repeat(1.0))
+    }
+
+    pub fn new(emittance: Vec3) -> Self {
+        Light::textured(Arc::new(SolidTexture::new(emittance)), 1.0)
+    }
+
+    /// Emits the color of `texture` at the hit point, scaled by `intensity`.
+    pub fn textured(emittance: Arc<dyn Texture>, intensity: FloatTy) -> Self {
         Light {
-            emittance: Vec3::repeat(1.0),
+            emittance,
+            intensity,
+            two_sided: true,
+            split_power: false,
+            profile: None,
         }
     }
 
-    pub fn new(emittance: Vec3) -> Self {
-        Light { emittance }
+    /// Emits the color of a black body at `kelvin`, with a luminance of
+    /// `intensity`.
+    pub fn from_temperature(kelvin: FloatTy, intensity: FloatTy) -> Self {
+        Light::new(spectrum::blackbody(kelvin) * intensity)
+    }
+
+    /// Diffuse emitter of the given color radiating `watts` in total from a
+    /// surface of `area` square units. The color only gives the chromaticity
+    /// of the light, its luminance is normalized away, and black colors give
+    /// lights that do not emit.
+    pub fn from_power(color: Vec3, watts: FloatTy, area: FloatTy) -> Self {
+        let color = spectrum::normalize_luminance(color);
+        Light {
+            split_power: true,
+            ..Light::textured(
+                Arc::new(SolidTexture::new(color)),
+                watts / (fconsts::PI * area),
+            )
+        }
+    }
+
+    /// Same as `from_power` with a luminous flux in lumens.
+    pub fn from_luminous_power(color: Vec3, lumens: FloatTy, area: FloatTy) -> Self {
+        Light::from_power(color, lumens / spectrum::LUMENS_PER_WATT, area)
+    }
+
+    /// Sets whether the light emits from both faces or only from the side its
+    /// outward normal points to.
+    pub fn with_two_sided(self, two_sided: bool) -> Self {
+        Light { two_sided, ..self }
+    }
+
+    /// Modulates the emitted radiance by the direction it leaves the surface
+    /// in, relative to the brightest direction of the profile.
+    pub fn with_profile(self, profile: Arc<IesProfile>) -> Self {
+        Light {
+            profile: Some(profile),
+            ..self
+        }
     }
 }
 
 impl Material for Light {
-    fn scatter(&self, _: &Ray, _: &HitRecord) -> Option<MaterialScatter> {
+    fn scatter(&self, _: &Ray, _: &HitRecord) -> Option<MaterialScatter<'_>> {
         None
     }
 
-    fn emit(&self, _u: FloatTy, _v: FloatTy, _point: Pt3) -> Vec3 {
-        self.emittance
+    fn emit(&self, ray: &Ray, record: &HitRecord) -> Vec3 {
+        if !self.two_sided && !record.front_face {
+            return Vec3::zeros();
+        }
+
+        let mut intensity = if self.two_sided && self.split_power {
+            self.intensity / 2.0
+        } else {
+            self.intensity
+        };
+        if let Some(profile) = &self.profile {
+            intensity *= profile.intensity(-ray.direction);
+        }
+        self.emittance.value(&record.surface_point()) * intensity
     }
 }
@@ -20,7 +20,7 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter> {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
         let mut rng = rand::thread_rng();
         let reflected = utils::reflect(ray.direction.normalize(), record.normal);
         let scattered = if reflected.dot(&record.normal) > 0.0 {
@@ -34,6 +34,8 @@ impl Material for Metal {
         Some(MaterialScatter {
             attenuation: self.albedo,
             scattered,
+            is_specular: true,
+            subsurface: None,
         })
     }
 }
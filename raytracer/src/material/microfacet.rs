@@ -0,0 +1,131 @@
+use crate::fconsts;
+use crate::utils::fmax;
+use crate::{FloatTy, Vec3};
+
+const MIN_ALPHA: FloatTy = 1e-3;
+
+/// Trowbridge-Reitz (GGX) microfacet distribution, expressed in a local
+/// shading frame where the macro normal is +Z.
+#[derive(Debug, Clone, Copy)]
+pub struct Ggx {
+    pub alpha_x: FloatTy,
+    pub alpha_y: FloatTy,
+}
+
+impl Ggx {
+    pub fn new(alpha_x: FloatTy, alpha_y: FloatTy) -> Self {
+        Ggx {
+            alpha_x: fmax(alpha_x, MIN_ALPHA),
+            alpha_y: fmax(alpha_y, MIN_ALPHA),
+        }
+    }
+
+    /// Builds an isotropic distribution from a perceptual roughness in `[0, 1]`.
+    pub fn from_roughness(roughness: FloatTy) -> Self {
+        let alpha = roughness * roughness;
+        Ggx::new(alpha, alpha)
+    }
+
+    /// Whether the surface is so smooth that it is handled as a perfect
+    /// mirror, whose reflection is left to scattered rays.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x <= MIN_ALPHA && self.alpha_y <= MIN_ALPHA
+    }
+
+    pub fn d(&self, m: Vec3) -> FloatTy {
+        if m.z <= 0.0 {
+            return 0.0;
+        }
+
+        let x = m.x / self.alpha_x;
+        let y = m.y / self.alpha_y;
+        let denom = x * x + y * y + m.z * m.z;
+        1.0 / (fconsts::PI * self.alpha_x * self.alpha_y * denom * denom)
+    }
+
+    pub fn lambda(&self, w: Vec3) -> FloatTy {
+        let cos2 = w.z * w.z;
+        if cos2 <= 0.0 {
+            return 0.0;
+        }
+
+        let ax = self.alpha_x * w.x;
+        let ay = self.alpha_y * w.y;
+        let alpha2_tan2 = (ax * ax + ay * ay) / cos2;
+        ((1.0 + alpha2_tan2).sqrt() - 1.0) / 2.0
+    }
+
+    pub fn g1(&self, w: Vec3) -> FloatTy {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated Smith masking-shadowing term.
+    pub fn g2(&self, wo: Vec3, wi: Vec3) -> FloatTy {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// BSDF times the cosine of `wi` for a reflection off the microfacets,
+    /// without the Fresnel term.
+    pub fn reflection(&self, wo: Vec3, wi: Vec3) -> FloatTy {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+
+        let h = (wo + wi).normalize();
+        self.d(h) * self.g2(wo, wi) / (4.0 * wo.z)
+    }
+
+    /// Microfacet normal refracting `wo` into `wi`, `eta` being the ratio of
+    /// the index on the side of `wi` over the one on the side of `wo`.
+    pub fn refraction_half_vector(wo: Vec3, wi: Vec3, eta: FloatTy) -> Vec3 {
+        let h = (wo + wi * eta).normalize();
+        if h.z < 0.0 {
+            -h
+        } else {
+            h
+        }
+    }
+
+    /// BSDF times the cosine of `wi` for a refraction through the microfacets,
+    /// without the Fresnel term. Like the sampling routines, it does not
+    /// account for the radiance compression by `eta²`.
+    pub fn transmission(&self, wo: Vec3, wi: Vec3, eta: FloatTy) -> FloatTy {
+        if wo.z <= 0.0 || wi.z >= 0.0 {
+            return 0.0;
+        }
+
+        let h = Ggx::refraction_half_vector(wo, wi, eta);
+        let cos_o = wo.dot(&h);
+        let cos_i = wi.dot(&h);
+        if cos_o <= 0.0 || cos_i >= 0.0 {
+            return 0.0;
+        }
+
+        let denom = cos_o + eta * cos_i;
+        self.d(h) * self.g2(wo, wi) * eta * eta * cos_i.abs() * cos_o / (wo.z * denom * denom)
+    }
+
+    /// Samples a microfacet normal from the distribution of normals visible
+    /// from `wo` (Heitz 2018). `wo` must lie in the upper hemisphere.
+    pub fn sample_visible_normal(&self, wo: Vec3, u1: FloatTy, u2: FloatTy) -> Vec3 {
+        let vh = Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).normalize();
+
+        let len2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if len2 > 0.0 {
+            Vec3::new(-vh.y, vh.x, 0.0) / len2.sqrt()
+        } else {
+            Vec3::x()
+        };
+        let t2 = vh.cross(&t1);
+
+        let r = u1.sqrt();
+        let phi = 2.0 * fconsts::PI * u2;
+        let p1 = r * phi.cos();
+        let p2 = r * phi.sin();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * p2;
+
+        let nh = t1 * p1 + t2 * p2 + vh * fmax(1.0 - p1 * p1 - p2 * p2, 0.0).sqrt();
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, fmax(nh.z, 0.0)).normalize()
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use super::{Material, MaterialScatter};
+use crate::hittable::HitRecord;
+use crate::utils::clamp;
+use crate::{FloatTy, Ray, Texture, Vec3};
+
+use rand;
+use rand::prelude::*;
+
+/// Blend of two materials, like dust over a metal, choosing between them at
+/// each scattering event in proportion to their weight.
+#[derive(Debug)]
+pub struct MixMaterial {
+    first: Arc<dyn Material>,
+    second: Arc<dyn Material>,
+    weight: Arc<dyn Texture>,
+}
+
+impl MixMaterial {
+    /// The first channel of `weight` is the fraction of `second` in the mix,
+    /// between 0 and 1.
+    pub fn new(
+        first: Arc<dyn Material>,
+        second: Arc<dyn Material>,
+        weight: Arc<dyn Texture>,
+    ) -> Self {
+        MixMaterial {
+            first,
+            second,
+            weight,
+        }
+    }
+
+    fn weight(&self, record: &HitRecord) -> FloatTy {
+        clamp(self.weight.value(&record.surface_point()).x, 0.0, 1.0)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let mut rng = rand::thread_rng();
+        if rng.gen::<FloatTy>() < self.weight(record) {
+            self.second.scatter(ray, record)
+        } else {
+            self.first.scatter(ray, record)
+        }
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        let weight = self.weight(record);
+        self.first.eval(ray, record, direction) * (1.0 - weight)
+            + self.second.eval(ray, record, direction) * weight
+    }
+
+    fn emit(&self, ray: &Ray, record: &HitRecord) -> Vec3 {
+        let weight = self.weight(record);
+        self.first.emit(ray, record) * (1.0 - weight) + self.second.emit(ray, record) * weight
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.first.is_dispersive() || self.second.is_dispersive()
+    }
+}
@@ -1,26 +1,68 @@
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
-use crate::{FloatTy, Pt3, Vec3};
+use crate::Vec3;
 
+mod coated;
+mod conductor;
 mod dielectric;
+mod hair;
+mod ior;
 mod lambertian;
 mod light;
 mod metal;
+mod microfacet;
+mod mix;
+mod normal_map;
+mod principled;
+mod rough_dielectric;
+mod subsurface;
+mod thin_film;
 mod utils;
+pub use coated::*;
+pub use conductor::*;
 pub use dielectric::*;
+pub use hair::*;
+pub use ior::*;
 pub use lambertian::*;
 pub use light::*;
 pub use metal::*;
+pub use mix::*;
+pub use normal_map::*;
+pub use principled::*;
+pub use rough_dielectric::*;
+pub use subsurface::*;
+pub use thin_film::*;
 
 #[derive(Debug, Clone)]
-pub struct MaterialScatter {
+pub struct MaterialScatter<'a> {
     pub attenuation: Vec3,
     pub scattered: Option<Ray>,
+    /// Whether the scattered ray comes from a lobe that `eval` leaves out,
+    /// like a perfect reflection, so that lights are seen along it instead
+    /// of being sampled.
+    pub is_specular: bool,
+    /// Light transport under the surface of a translucent material, followed
+    /// by the integrator when the scattered ray goes inside the object.
+    pub subsurface: Option<&'a Subsurface>,
 }
 
 pub trait Material: Send + Sync + std::fmt::Debug {
-    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter>;
-    fn emit(&self, _u: FloatTy, _v: FloatTy, _point: Pt3) -> Vec3 {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>>;
+
+    /// BSDF times the cosine with the normal for light arriving along the unit
+    /// `direction`, used when sampling light sources directly. Materials
+    /// without a finite BSDF, like mirrors, always return zero.
+    fn eval(&self, _ray: &Ray, _record: &HitRecord, _direction: Vec3) -> Vec3 {
+        Vec3::zeros()
+    }
+
+    fn emit(&self, _ray: &Ray, _record: &HitRecord) -> Vec3 {
         Vec3::repeat(0.0)
     }
+
+    /// Whether the scattering depends on the wavelength of the ray, which
+    /// restricts spectral paths to a single wavelength.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
 }
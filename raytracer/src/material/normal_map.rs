@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use super::{Material, MaterialScatter};
+use crate::fconsts;
+use crate::hittable::HitRecord;
+use crate::texture::SurfacePoint;
+use crate::utils::Frame;
+use crate::{FloatTy, Ray, Texture, Vec3};
+
+const BUMP_DELTA: FloatTy = 0.0005;
+
+/// Orthonormal tangent frame around the shading normal of a hit, following
+/// the tangent of the surface when it has one.
+fn tangent_frame(record: &HitRecord) -> Frame {
+    Frame::from_tangents(record.normal, record.tangent, record.bitangent)
+}
+
+/// Replaces the shading normal of the record, keeping the geometric normal
+/// when the perturbed one would face away from the incoming ray.
+fn with_shading_normal(ray: &Ray, record: &HitRecord, normal: Vec3) -> HitRecord {
+    let mut record = record.clone();
+    if normal.dot(&ray.direction) < 0.0 {
+        record.normal = normal;
+    }
+    record
+}
+
+/// Perturbs the shading normal of another material from a tangent-space
+/// normal map, whose channels encode the normal as `(n + 1) / 2`.
+#[derive(Debug)]
+pub struct NormalMapped {
+    inner: Arc<dyn Material>,
+    normal_map: Arc<dyn Texture>,
+}
+
+impl NormalMapped {
+    pub fn new(inner: Arc<dyn Material>, normal_map: Arc<dyn Texture>) -> Self {
+        NormalMapped { inner, normal_map }
+    }
+
+    fn shading_normal(&self, record: &HitRecord) -> Vec3 {
+        let encoded = self.normal_map.value(&record.surface_point());
+        let local = encoded * 2.0 - Vec3::repeat(1.0);
+        if local.norm_squared() <= fconsts::EPSILON {
+            return record.normal;
+        }
+        tangent_frame(record).to_world(local).normalize()
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let record = with_shading_normal(ray, record, self.shading_normal(record));
+        self.inner.scatter(ray, &record)
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        let record = with_shading_normal(ray, record, self.shading_normal(record));
+        self.inner.eval(ray, &record, direction)
+    }
+
+    fn emit(&self, ray: &Ray, record: &HitRecord) -> Vec3 {
+        self.inner.emit(ray, record)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.inner.is_dispersive()
+    }
+}
+
+/// Perturbs the shading normal of another material as if the surface was
+/// displaced along its normal by a height texture, read from its first channel.
+#[derive(Debug)]
+pub struct BumpMapped {
+    inner: Arc<dyn Material>,
+    height: Arc<dyn Texture>,
+    scale: FloatTy,
+}
+
+impl BumpMapped {
+    pub fn new(inner: Arc<dyn Material>, height: Arc<dyn Texture>, scale: FloatTy) -> Self {
+        BumpMapped {
+            inner,
+            height,
+            scale,
+        }
+    }
+
+    fn shading_normal(&self, record: &HitRecord) -> Vec3 {
+        if record.tangent.norm_squared() <= fconsts::EPSILON
+            || record.bitangent.norm_squared() <= fconsts::EPSILON
+        {
+            return record.normal;
+        }
+
+        // the points are moved along the tangents as well for solid textures,
+        // the local ones approximately when the object is transformed
+        let point = record.surface_point();
+        let along_u = SurfacePoint {
+            u: point.u + BUMP_DELTA,
+            local_p: point.local_p + record.tangent * BUMP_DELTA,
+            p: point.p + record.tangent * BUMP_DELTA,
+            ..point
+        };
+        let along_v = SurfacePoint {
+            v: point.v + BUMP_DELTA,
+            local_p: point.local_p + record.bitangent * BUMP_DELTA,
+            p: point.p + record.bitangent * BUMP_DELTA,
+            ..point
+        };
+        let height = self.height.value(&point).x;
+        let du = (self.height.value(&along_u).x - height) / BUMP_DELTA;
+        let dv = (self.height.value(&along_v).x - height) / BUMP_DELTA;
+
+        let n = record.normal;
+        let dpdu = record.tangent + n * (du * self.scale);
+        let dpdv = record.bitangent + n * (dv * self.scale);
+        let bumped = dpdu.cross(&dpdv).normalize();
+
+        // the cross product follows the parameterization, not the side of
+        // the surface the ray comes from
+        let unbumped = record.tangent.cross(&record.bitangent);
+        if unbumped.dot(&n) < 0.0 {
+            -bumped
+        } else {
+            bumped
+        }
+    }
+}
+
+impl Material for BumpMapped {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let record = with_shading_normal(ray, record, self.shading_normal(record));
+        self.inner.scatter(ray, &record)
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        let record = with_shading_normal(ray, record, self.shading_normal(record));
+        self.inner.eval(ray, &record, direction)
+    }
+
+    fn emit(&self, ray: &Ray, record: &HitRecord) -> Vec3 {
+        self.inner.emit(ray, record)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.inner.is_dispersive()
+    }
+}
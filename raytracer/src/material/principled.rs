@@ -0,0 +1,380 @@
+use std::sync::Arc;
+
+use super::microfacet::Ggx;
+use super::utils;
+use super::{Material, MaterialScatter};
+use crate::hittable::HitRecord;
+use crate::texture::{SolidTexture, SurfacePoint};
+use crate::utils::{clamp, Frame};
+use crate::{fconsts, FloatTy, Ray, Texture, Vec3};
+
+use rand;
+use rand::prelude::*;
+
+const CLEARCOAT_ROUGHNESS: FloatTy = 0.1;
+const CLEARCOAT_F0: FloatTy = 0.04;
+const DEFAULT_IOR: FloatTy = 1.5;
+
+fn constant(value: FloatTy) -> Arc<dyn Texture> {
+    Arc::new(SolidTexture::new(Vec3::repeat(value)))
+}
+
+/// Scalar parameters are read from the first channel of their texture.
+fn scalar(texture: &Arc<dyn Texture>, point: &SurfacePoint) -> FloatTy {
+    clamp(texture.value(point).x, 0.0, 1.0)
+}
+
+fn luminance(c: Vec3) -> FloatTy {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+fn schlick_weight(cos: FloatTy) -> FloatTy {
+    let m = clamp(1.0 - cos, 0.0, 1.0);
+    let m2 = m * m;
+    m2 * m2 * m
+}
+
+fn schlick_fresnel(f0: Vec3, cos: FloatTy) -> Vec3 {
+    f0 + (Vec3::repeat(1.0) - f0) * schlick_weight(cos)
+}
+
+fn lerp(a: Vec3, b: Vec3, t: FloatTy) -> Vec3 {
+    a * (1.0 - t) + b * t
+}
+
+pub struct PrincipledBuilder {
+    base_color: Arc<dyn Texture>,
+    metallic: Arc<dyn Texture>,
+    roughness: Arc<dyn Texture>,
+    specular: Arc<dyn Texture>,
+    specular_tint: Arc<dyn Texture>,
+    sheen: Arc<dyn Texture>,
+    clearcoat: Arc<dyn Texture>,
+    transmission: Arc<dyn Texture>,
+    emission: Arc<dyn Texture>,
+    ior: FloatTy,
+}
+
+impl PrincipledBuilder {
+    pub fn new(base_color: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder {
+            base_color,
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            specular_tint: constant(0.0),
+            sheen: constant(0.0),
+            clearcoat: constant(0.0),
+            transmission: constant(0.0),
+            emission: constant(0.0),
+            ior: DEFAULT_IOR,
+        }
+    }
+
+    pub fn with_metallic(self, metallic: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder { metallic, ..self }
+    }
+
+    pub fn with_roughness(self, roughness: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder { roughness, ..self }
+    }
+
+    pub fn with_specular(self, specular: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder { specular, ..self }
+    }
+
+    pub fn with_specular_tint(self, specular_tint: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder {
+            specular_tint,
+            ..self
+        }
+    }
+
+    pub fn with_sheen(self, sheen: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder { sheen, ..self }
+    }
+
+    pub fn with_clearcoat(self, clearcoat: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder { clearcoat, ..self }
+    }
+
+    pub fn with_transmission(self, transmission: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder {
+            transmission,
+            ..self
+        }
+    }
+
+    pub fn with_emission(self, emission: Arc<dyn Texture>) -> Self {
+        PrincipledBuilder { emission, ..self }
+    }
+
+    pub fn with_ior(self, ior: FloatTy) -> Self {
+        PrincipledBuilder { ior, ..self }
+    }
+
+    pub fn build(self) -> Principled {
+        Principled {
+            base_color: self.base_color,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            specular: self.specular,
+            specular_tint: self.specular_tint,
+            sheen: self.sheen,
+            clearcoat: self.clearcoat,
+            transmission: self.transmission,
+            emission: self.emission,
+            ior: self.ior,
+        }
+    }
+}
+
+/// Disney-style "uber" material layering a diffuse base, a metallic/dielectric
+/// GGX specular lobe, sheen, a clearcoat and a rough transmission lobe.
+#[derive(Debug)]
+pub struct Principled {
+    base_color: Arc<dyn Texture>,
+    metallic: Arc<dyn Texture>,
+    roughness: Arc<dyn Texture>,
+    specular: Arc<dyn Texture>,
+    specular_tint: Arc<dyn Texture>,
+    sheen: Arc<dyn Texture>,
+    clearcoat: Arc<dyn Texture>,
+    transmission: Arc<dyn Texture>,
+    emission: Arc<dyn Texture>,
+    ior: FloatTy,
+}
+
+/// Parameters of a `Principled` material resolved at a given surface point.
+struct Lobes {
+    base_color: Vec3,
+    specular_color: Vec3,
+    sheen_color: Vec3,
+    distribution: Ggx,
+    diffuse_weight: FloatTy,
+    specular_weight: FloatTy,
+    clearcoat_weight: FloatTy,
+    transmission_weight: FloatTy,
+    roughness: FloatTy,
+}
+
+impl Lobes {
+    /// Fraction of the light going through the clearcoat, and Fresnel
+    /// reflectance of the specular layer above the diffuse base, seen from `wo`.
+    fn layering(&self, wo: Vec3) -> (FloatTy, Vec3) {
+        let clearcoat_fresnel = CLEARCOAT_F0 + (1.0 - CLEARCOAT_F0) * schlick_weight(wo.z);
+        let base_energy = 1.0 - self.clearcoat_weight * clearcoat_fresnel;
+        (base_energy, schlick_fresnel(self.specular_color, wo.z))
+    }
+
+    /// Burley diffuse with retro-reflection and sheen, times pi.
+    fn diffuse(&self, wo: Vec3, wi: Vec3) -> Vec3 {
+        let h = (wi + wo).normalize();
+        let cos_d = wi.dot(&h);
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let retro = (1.0 + (fd90 - 1.0) * schlick_weight(wi.z))
+            * (1.0 + (fd90 - 1.0) * schlick_weight(wo.z));
+        self.base_color * retro + self.sheen_color * schlick_weight(cos_d)
+    }
+}
+
+impl Principled {
+    fn eta(&self, record: &HitRecord) -> FloatTy {
+        if record.front_face {
+            self.ior
+        } else {
+            1.0 / self.ior
+        }
+    }
+
+    fn lobes(&self, point: &SurfacePoint) -> Lobes {
+        let base_color = self.base_color.value(point);
+        let metallic = scalar(&self.metallic, point);
+        let roughness = scalar(&self.roughness, point);
+        let specular = scalar(&self.specular, point);
+        let specular_tint = scalar(&self.specular_tint, point);
+        let sheen = scalar(&self.sheen, point);
+        let clearcoat = scalar(&self.clearcoat, point);
+        let transmission = scalar(&self.transmission, point);
+
+        let lum = luminance(base_color);
+        let tint = if lum > 0.0 {
+            base_color / lum
+        } else {
+            Vec3::repeat(1.0)
+        };
+
+        let dielectric_specular = lerp(Vec3::repeat(1.0), tint, specular_tint) * (specular * 0.08);
+
+        Lobes {
+            base_color,
+            specular_color: lerp(dielectric_specular, base_color, metallic),
+            sheen_color: tint * sheen,
+            distribution: Ggx::from_roughness(roughness),
+            diffuse_weight: (1.0 - metallic) * (1.0 - transmission),
+            specular_weight: 1.0 - (1.0 - metallic) * transmission,
+            clearcoat_weight: 0.25 * clearcoat,
+            transmission_weight: (1.0 - metallic) * transmission,
+            roughness,
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let mut rng = rand::thread_rng();
+        let lobes = self.lobes(&record.surface_point());
+        let frame = Frame::from_normal(record.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        // The clearcoat and specular layers reflect part of the light before it
+        // reaches the layers below them.
+        let (base_energy, specular_fresnel) = lobes.layering(wo);
+
+        let diffuse_albedo = (1.0 - luminance(specular_fresnel))
+            * lobes.diffuse_weight
+            * luminance(lobes.base_color + lobes.sheen_color);
+        let probabilities = [
+            base_energy * diffuse_albedo,
+            base_energy * lobes.specular_weight * luminance(specular_fresnel),
+            1.0 - base_energy,
+            base_energy * lobes.transmission_weight,
+        ];
+        let total: FloatTy = probabilities.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut pick = rng.gen::<FloatTy>() * total;
+        let mut lobe = 0;
+        while lobe < probabilities.len() - 1 && pick >= probabilities[lobe] {
+            pick -= probabilities[lobe];
+            lobe += 1;
+        }
+        let probability = probabilities[lobe] / total;
+        if probability <= 0.0 {
+            return None;
+        }
+
+        let (wi, weight, transmitted) = match lobe {
+            0 => {
+                let wi = utils::random_unit_sphere(&mut rng, Vec3::z()).normalize();
+                let weight = lobes.diffuse(wo, wi)
+                    * (lobes.diffuse_weight * base_energy * (1.0 - luminance(specular_fresnel)));
+                (wi, weight, false)
+            }
+            1 => {
+                let m = lobes
+                    .distribution
+                    .sample_visible_normal(wo, rng.gen(), rng.gen());
+                let wi = utils::reflect_local(wo, m);
+                let fresnel = schlick_fresnel(lobes.specular_color, wo.dot(&m));
+                let shadowing = lobes.distribution.g2(wo, wi) / lobes.distribution.g1(wo);
+                (
+                    wi,
+                    fresnel * (shadowing * lobes.specular_weight * base_energy),
+                    false,
+                )
+            }
+            2 => {
+                let distribution = Ggx::from_roughness(CLEARCOAT_ROUGHNESS);
+                let m = distribution.sample_visible_normal(wo, rng.gen(), rng.gen());
+                let wi = utils::reflect_local(wo, m);
+                let fresnel = CLEARCOAT_F0 + (1.0 - CLEARCOAT_F0) * schlick_weight(wo.dot(&m));
+                let shadowing = distribution.g2(wo, wi) / distribution.g1(wo);
+                (
+                    wi,
+                    Vec3::repeat(fresnel * shadowing * lobes.clearcoat_weight),
+                    false,
+                )
+            }
+            _ => {
+                let eta = self.eta(record);
+                let m = lobes
+                    .distribution
+                    .sample_visible_normal(wo, rng.gen(), rng.gen());
+                let fresnel = utils::fresnel_dielectric(wo.dot(&m), eta);
+                let refracted = if rng.gen::<FloatTy>() < fresnel {
+                    None
+                } else {
+                    utils::refract_local(wo, m, eta)
+                };
+                let shadowing = |wi| lobes.distribution.g2(wo, wi) / lobes.distribution.g1(wo);
+                let weight = lobes.transmission_weight * base_energy;
+                match refracted {
+                    Some(wi) => (wi, lobes.base_color * (shadowing(wi) * weight), true),
+                    None => {
+                        let wi = utils::reflect_local(wo, m);
+                        (wi, Vec3::repeat(shadowing(wi) * weight), false)
+                    }
+                }
+            }
+        };
+
+        let scattered = if (wi.z < 0.0) == transmitted {
+            Some(Ray::new(record.p, frame.to_world(wi)))
+        } else {
+            None
+        };
+
+        Some(MaterialScatter {
+            attenuation: weight / probability,
+            scattered,
+            // the specular and transmission lobes share the distribution
+            is_specular: (lobe == 1 || lobe == 3) && lobes.distribution.is_smooth(),
+            subsurface: None,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        let lobes = self.lobes(&record.surface_point());
+        let frame = Frame::from_normal(record.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        if wo.z <= 0.0 {
+            return Vec3::zeros();
+        }
+
+        let (base_energy, specular_fresnel) = lobes.layering(wo);
+        let eta = self.eta(record);
+        let transmission_weight = lobes.transmission_weight * base_energy;
+
+        if wi.z > 0.0 {
+            let h = (wo + wi).normalize();
+            let reflection = if lobes.distribution.is_smooth() {
+                0.0
+            } else {
+                lobes.distribution.reflection(wo, wi)
+            };
+            let clearcoat = Ggx::from_roughness(CLEARCOAT_ROUGHNESS).reflection(wo, wi);
+
+            let diffuse = lobes.diffuse(wo, wi)
+                * (lobes.diffuse_weight * base_energy * (1.0 - luminance(specular_fresnel)) * wi.z
+                    / fconsts::PI);
+            let specular = schlick_fresnel(lobes.specular_color, wo.dot(&h))
+                * (reflection * lobes.specular_weight * base_energy);
+            let clearcoat = (CLEARCOAT_F0 + (1.0 - CLEARCOAT_F0) * schlick_weight(wo.dot(&h)))
+                * clearcoat
+                * lobes.clearcoat_weight;
+            let transmission =
+                utils::fresnel_dielectric(wo.dot(&h), eta) * reflection * transmission_weight;
+
+            diffuse + specular + Vec3::repeat(clearcoat + transmission)
+        } else {
+            if lobes.distribution.is_smooth() {
+                return Vec3::zeros();
+            }
+            let h = Ggx::refraction_half_vector(wo, wi, eta);
+            let fresnel = utils::fresnel_dielectric(wo.dot(&h), eta);
+            let transmission = lobes.distribution.transmission(wo, wi, eta);
+            lobes.base_color * ((1.0 - fresnel) * transmission * transmission_weight)
+        }
+    }
+
+    fn emit(&self, _ray: &Ray, record: &HitRecord) -> Vec3 {
+        self.emission.value(&record.surface_point())
+    }
+}
@@ -0,0 +1,100 @@
+use super::microfacet::Ggx;
+use super::utils;
+use super::{Ior, Material, MaterialScatter};
+use crate::hittable::HitRecord;
+use crate::utils::Frame;
+use crate::{FloatTy, Ray, Vec3};
+
+use rand;
+use rand::prelude::*;
+
+/// Glass-like interface with a GGX microfacet distribution, reflecting or
+/// refracting through a sampled visible microfacet.
+#[derive(Debug, Clone)]
+pub struct RoughDielectric {
+    reflective_index: Ior,
+    distribution: Ggx,
+}
+
+impl RoughDielectric {
+    pub fn new<I: Into<Ior>>(reflective_index: I, roughness: FloatTy) -> Self {
+        RoughDielectric {
+            reflective_index: reflective_index.into(),
+            distribution: Ggx::from_roughness(roughness),
+        }
+    }
+
+    fn eta(&self, ray: &Ray, record: &HitRecord) -> FloatTy {
+        let reflective_index = self.reflective_index.at(ray.wavelength);
+        if record.front_face {
+            reflective_index
+        } else {
+            1.0 / reflective_index
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let mut rng = rand::thread_rng();
+        let frame = Frame::from_normal(record.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let eta = self.eta(ray, record);
+        let m = self
+            .distribution
+            .sample_visible_normal(wo, rng.gen(), rng.gen());
+        let fresnel = utils::fresnel_dielectric(wo.dot(&m), eta);
+
+        let (wi, is_reflection) = if rng.gen::<FloatTy>() < fresnel {
+            (utils::reflect_local(wo, m), true)
+        } else {
+            match utils::refract_local(wo, m, eta) {
+                Some(wi) => (wi, false),
+                None => (utils::reflect_local(wo, m), true),
+            }
+        };
+
+        let scattered = if (wi.z > 0.0) == is_reflection {
+            Some(Ray::new(record.p, frame.to_world(wi)))
+        } else {
+            None
+        };
+
+        let shadowing = self.distribution.g2(wo, wi) / self.distribution.g1(wo);
+
+        Some(MaterialScatter {
+            attenuation: Vec3::repeat(shadowing),
+            scattered,
+            is_specular: self.distribution.is_smooth(),
+            subsurface: None,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        let frame = Frame::from_normal(record.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        if wo.z <= 0.0 || self.distribution.is_smooth() {
+            return Vec3::zeros();
+        }
+
+        let eta = self.eta(ray, record);
+        let value = if wi.z > 0.0 {
+            let h = (wo + wi).normalize();
+            utils::fresnel_dielectric(wo.dot(&h), eta) * self.distribution.reflection(wo, wi)
+        } else {
+            let h = Ggx::refraction_half_vector(wo, wi, eta);
+            (1.0 - utils::fresnel_dielectric(wo.dot(&h), eta))
+                * self.distribution.transmission(wo, wi, eta)
+        };
+        Vec3::repeat(value)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.reflective_index.is_dispersive()
+    }
+}
@@ -0,0 +1,284 @@
+use std::sync::Arc;
+
+use super::{utils, Material, MaterialScatter};
+use crate::fconsts;
+use crate::hittable::{HitRecord, Hittable};
+use crate::medium::HomogeneousMedium;
+use crate::utils::{clamp, fmax, Frame};
+use crate::{FloatTy, Ray, Vec3, TMIN};
+
+use rand;
+use rand::prelude::*;
+
+/// Scattering events after which a random walk is abandoned.
+const MAX_WALK_EVENTS: usize = 256;
+/// Hits of a diffusion probe ray that are considered as exit points.
+const MAX_PROBE_HITS: usize = 16;
+/// Fraction of the energy of a diffusion profile within the probe radius.
+const PROFILE_COVERAGE: FloatTy = 0.999;
+
+/// How light travels between the points where it enters and leaves a
+/// `Subsurface` object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubsurfaceMethod {
+    /// Follows light through the volume of the object, which must be closed.
+    RandomWalk,
+    /// Picks the exit point near the entry one from the normalized diffusion
+    /// profile of Christensen and Burley (2015), much faster but assuming
+    /// the surface is locally flat.
+    Diffusion,
+}
+
+/// Single scattering albedo giving a multiple scattering albedo of
+/// `albedo`, from Chiang, Kutz and Burley (2016).
+fn single_scattering_albedo(albedo: FloatTy) -> FloatTy {
+    let a = clamp(albedo, 0.0, 0.999);
+    let s = 4.097_12 + 4.208_63 * a - (9.592_17 + 41.680_8 * a + 17.712_6 * a * a).sqrt();
+    1.0 - s * s
+}
+
+/// Shape parameter of the diffusion profile for a mean free path, following
+/// the fit of Christensen and Burley for a searchlight configuration.
+fn profile_width(albedo: FloatTy, mean_free_path: FloatTy) -> FloatTy {
+    let s = 1.85 - albedo + 7.0 * (albedo - 0.8).abs().powi(3);
+    mean_free_path / s
+}
+
+/// Distribution of the distance between the entry and the exit point, for
+/// a profile of width `d`.
+fn profile_pdf(r: FloatTy, d: FloatTy) -> FloatTy {
+    ((-r / d).exp() + (-r / (3.0 * d)).exp()) / (4.0 * d)
+}
+
+fn outward_normal(record: &HitRecord) -> Vec3 {
+    if record.front_face {
+        record.normal
+    } else {
+        -record.normal
+    }
+}
+
+/// Translucent material where light enters the object, scatters many times
+/// inside and leaves it somewhere else, like skin, marble, milk or wax.
+#[derive(Debug)]
+pub struct Subsurface {
+    albedo: Vec3,
+    radius: Vec3,
+    reflective_index: FloatTy,
+    method: SubsurfaceMethod,
+    medium: HomogeneousMedium,
+}
+
+impl Subsurface {
+    /// `albedo` is the color of the object once light scattered under its
+    /// surface, `radius` the mean distance light travels between scattering
+    /// events for each channel, in scene units.
+    pub fn new(albedo: Vec3, radius: Vec3, reflective_index: FloatTy) -> Self {
+        Subsurface {
+            albedo,
+            radius,
+            reflective_index,
+            method: SubsurfaceMethod::RandomWalk,
+            medium: Subsurface::medium(albedo, radius, 0.0),
+        }
+    }
+
+    /// Mean cosine of the scattering angle inside the object, used by the
+    /// random walk.
+    pub fn with_anisotropy(self, anisotropy: FloatTy) -> Self {
+        Subsurface {
+            medium: Subsurface::medium(self.albedo, self.radius, anisotropy),
+            ..self
+        }
+    }
+
+    pub fn with_method(self, method: SubsurfaceMethod) -> Self {
+        Subsurface { method, ..self }
+    }
+
+    fn medium(albedo: Vec3, radius: Vec3, anisotropy: FloatTy) -> HomogeneousMedium {
+        // forward scattering makes light go further before losing its
+        // direction, which the radius is meant to describe
+        let radius = radius / (1.0 - clamp(anisotropy, -0.9, 0.9));
+        HomogeneousMedium::from_albedo(albedo.map(single_scattering_albedo), radius, anisotropy)
+    }
+
+    /// Follows light that entered the surface at `entry` and continues along
+    /// `ray` until it leaves the object. Returns the point where it leaves,
+    /// seen from inside the object, and the throughput of the path under the
+    /// surface.
+    pub fn exit<R: Rng>(
+        &self,
+        objects: &[Box<dyn Hittable>],
+        entry: &HitRecord,
+        ray: Ray,
+        rng: &mut R,
+    ) -> Option<(HitRecord, Vec3)> {
+        match self.method {
+            SubsurfaceMethod::RandomWalk => self.random_walk(objects, ray, rng),
+            SubsurfaceMethod::Diffusion => self.diffusion(objects, entry, rng),
+        }
+    }
+
+    fn random_walk<R: Rng>(
+        &self,
+        objects: &[Box<dyn Hittable>],
+        ray: Ray,
+        rng: &mut R,
+    ) -> Option<(HitRecord, Vec3)> {
+        let mut ray = Ray {
+            direction: ray.direction.normalize(),
+            ..ray
+        };
+        let mut throughput = Vec3::repeat(1.0);
+
+        for _ in 0..MAX_WALK_EVENTS {
+            let record = objects.is_hit_by(ray, TMIN, None)?;
+            let (scattering, weight) = self.medium.sample_distance(record.t, throughput, rng);
+            throughput = throughput.component_mul(&weight);
+
+            let t = match scattering {
+                Some(t) => t,
+                None => return Some((record, throughput)),
+            };
+
+            // long walks in absorbing media carry almost nothing
+            let survival = throughput.max().min(1.0);
+            if rng.gen::<FloatTy>() >= survival {
+                return None;
+            }
+            throughput /= survival;
+
+            ray = Ray {
+                origin: ray.point_at_parameter(t),
+                direction: self.medium.sample_phase(ray.direction, rng),
+                ..ray
+            };
+        }
+
+        None
+    }
+
+    fn diffusion<R: Rng>(
+        &self,
+        objects: &[Box<dyn Hittable>],
+        entry: &HitRecord,
+        rng: &mut R,
+    ) -> Option<(HitRecord, Vec3)> {
+        let widths = self.albedo.zip_map(&self.radius, profile_width);
+
+        // the profile is a mix of two exponentials in the distance
+        let channel = rng.gen_range(0, 3);
+        let d = widths[channel];
+        let scale = if rng.gen::<FloatTy>() < 0.25 {
+            d
+        } else {
+            3.0 * d
+        };
+        let r = -(1.0 - rng.gen::<FloatTy>()).ln() * scale;
+        let r_max = -(1.0 - PROFILE_COVERAGE).ln() * 3.0 * d;
+        if r >= r_max {
+            return None;
+        }
+
+        // probe the surface around the entry point along its normal
+        let n = outward_normal(entry);
+        let frame = Frame::from_normal(n);
+        let phi = 2.0 * fconsts::PI * rng.gen::<FloatTy>();
+        let length = 2.0 * (r_max * r_max - r * r).sqrt();
+        let origin = entry.p + (frame.s * phi.cos() + frame.t * phi.sin()) * r + n * (length / 2.0);
+        let probe = Ray::new(origin, -n);
+
+        let mut hits = Vec::new();
+        let mut tmin = 0.0;
+        while hits.len() < MAX_PROBE_HITS {
+            let record = match objects.is_hit_by(probe, tmin, Some(length)) {
+                Some(record) => record,
+                None => break,
+            };
+            tmin = record.t + fconsts::EPSILON;
+            if Arc::ptr_eq(&record.material, &entry.material) {
+                hits.push(record);
+            }
+        }
+        if hits.is_empty() {
+            return None;
+        }
+
+        let count = hits.len();
+        let exit = hits.swap_remove(rng.gen_range(0, count));
+
+        let pdf = (0..3).map(|c| profile_pdf(r, widths[c])).sum::<FloatTy>() / 3.0;
+        let weight = self
+            .albedo
+            .zip_map(&widths, |albedo, d| albedo * profile_pdf(r, d))
+            * (count as FloatTy / pdf);
+
+        // the exit point is seen as if light reached it from the inside
+        let outward = outward_normal(&exit);
+        let record = HitRecord::new(
+            Ray {
+                origin: exit.p - outward,
+                direction: outward,
+                differentials: None,
+                ..entry.ray
+            },
+            1.0,
+            exit.p,
+            outward,
+            exit.u,
+            exit.v,
+            exit.material,
+        )
+        .with_tangents(exit.tangent, exit.bitangent)
+        .with_local_point(exit.local_p);
+
+        Some((record, weight))
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let mut rng = rand::thread_rng();
+
+        // light leaves diffusely, having forgotten where it came from
+        if !record.front_face {
+            let direction = utils::random_unit_sphere(&mut rng, -record.normal);
+            return Some(MaterialScatter {
+                attenuation: Vec3::repeat(1.0),
+                scattered: Some(Ray::new(record.p, direction)),
+                is_specular: false,
+                subsurface: None,
+            });
+        }
+
+        let cos_i = -ray.direction.normalize().dot(&record.normal);
+        let fresnel = utils::fresnel_dielectric(cos_i, self.reflective_index);
+        if rng.gen::<FloatTy>() < fresnel {
+            let direction = utils::reflect(ray.direction.normalize(), record.normal);
+            Some(MaterialScatter {
+                attenuation: Vec3::repeat(1.0),
+                scattered: Some(Ray::new(record.p, direction)),
+                is_specular: true,
+                subsurface: None,
+            })
+        } else {
+            let direction = utils::random_unit_sphere(&mut rng, -record.normal);
+            Some(MaterialScatter {
+                attenuation: Vec3::repeat(1.0),
+                scattered: Some(Ray::new(record.p, direction)),
+                is_specular: false,
+                subsurface: Some(self),
+            })
+        }
+    }
+
+    fn eval(&self, _ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        if record.front_face {
+            return Vec3::zeros();
+        }
+
+        let cos = fmax(-record.normal.dot(&direction), 0.0);
+        Vec3::repeat(cos / fconsts::PI)
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::ops::{Add, Div, Mul, Sub};
+use std::sync::{Arc, RwLock};
+
+use super::{utils, Material, MaterialScatter};
+use crate::fconsts;
+use crate::hittable::HitRecord;
+use crate::spectrum;
+use crate::texture::SolidTexture;
+use crate::utils::{clamp, fmax};
+use crate::{FloatTy, Ray, Texture, Vec3};
+
+use rand;
+use rand::prelude::*;
+
+const DEFAULT_SUBSTRATE_IOR: FloatTy = 1.5;
+
+/// Number of steps of the cosine of the incident angle between 0 and 1 at
+/// which the RGB reflectance of a film is computed.
+const COS_STEPS: FloatTy = 64.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Complex {
+    re: FloatTy,
+    im: FloatTy,
+}
+
+impl Complex {
+    fn new(re: FloatTy, im: FloatTy) -> Self {
+        Complex { re, im }
+    }
+
+    fn real(re: FloatTy) -> Self {
+        Complex::new(re, 0.0)
+    }
+
+    /// `e^(i phase)`
+    fn from_phase(phase: FloatTy) -> Self {
+        Complex::new(phase.cos(), phase.sin())
+    }
+
+    fn norm_sqr(self) -> FloatTy {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Principal square root.
+    fn sqrt(self) -> Self {
+        let norm = self.norm_sqr().sqrt();
+        let re = ((norm + self.re) / 2.0).max(0.0).sqrt();
+        let im = ((norm - self.re) / 2.0).max(0.0).sqrt();
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, other: Complex) -> Complex {
+        let norm = other.norm_sqr();
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / norm,
+            (self.im * other.re - self.re * other.im) / norm,
+        )
+    }
+}
+
+/// Reflectance, averaged over polarizations, of a film of index `film_ior`
+/// between the outside medium of index 1 and a substrate of complex index
+/// `substrate`, for light of `wavelength` nanometers arriving with a cosine
+/// `cos_i` to the normal. All the reflections inside the film are summed with
+/// their phase, following Airy's formula.
+fn airy_reflectance(
+    cos_i: FloatTy,
+    thickness: FloatTy,
+    film_ior: FloatTy,
+    substrate: Complex,
+    wavelength: FloatTy,
+) -> FloatTy {
+    let sin2_i = 1.0 - cos_i * cos_i;
+    let sin2_film = sin2_i / (film_ior * film_ior);
+    if sin2_film >= 1.0 {
+        return 1.0;
+    }
+    let cos_film = (1.0 - sin2_film).sqrt();
+
+    // n cos(θ) of the substrate, complex for conductors
+    let n3_cos3 = (substrate * substrate - Complex::real(sin2_i)).sqrt();
+    let cos3 = n3_cos3 / substrate;
+
+    let n2 = Complex::real(film_ior);
+    let n2_cos2 = Complex::real(film_ior * cos_film);
+    let r12_s = Complex::real((cos_i - film_ior * cos_film) / (cos_i + film_ior * cos_film));
+    let r12_p = Complex::real((film_ior * cos_i - cos_film) / (film_ior * cos_i + cos_film));
+    let r23_s = (n2_cos2 - n3_cos3) / (n2_cos2 + n3_cos3);
+    let r23_p = (substrate * Complex::real(cos_film) - n2 * cos3)
+        / (substrate * Complex::real(cos_film) + n2 * cos3);
+
+    let phase =
+        Complex::from_phase(4.0 * fconsts::PI * film_ior * thickness * cos_film / wavelength);
+    let total = |r12: Complex, r23: Complex| {
+        let r = (r12 + r23 * phase) / (Complex::real(1.0) + r12 * r23 * phase);
+        r.norm_sqr()
+    };
+    (total(r12_s, r23_s) + total(r12_p, r23_p)) / 2.0
+}
+
+/// Value of a per-channel quantity at a wavelength, interpolating between the
+/// channels as if they were measured at 650, 550 and 450 nanometers.
+fn at_wavelength(rgb: Vec3, wavelength: FloatTy) -> FloatTy {
+    if wavelength >= 550.0 {
+        let t = clamp((wavelength - 550.0) / 100.0, 0.0, 1.0);
+        rgb.y * (1.0 - t) + rgb.x * t
+    } else {
+        let t = clamp((550.0 - wavelength) / 100.0, 0.0, 1.0);
+        rgb.y * (1.0 - t) + rgb.z * t
+    }
+}
+
+fn mean(v: Vec3) -> FloatTy {
+    (v.x + v.y + v.z) / 3.0
+}
+
+/// Thin transparent layer whose reflections interfere with the ones of what
+/// it is applied on, giving the iridescent colors of soap bubbles, oil slicks
+/// or coated lenses.
+///
+/// The film changes how much the inner material reflects and transmits, but
+/// the inner material still decides where light goes.
+#[derive(Debug)]
+pub struct ThinFilm {
+    /// `None` for a film with air on both sides.
+    inner: Option<Arc<dyn Material>>,
+    thickness: Arc<dyn Texture>,
+    thickness_scale: FloatTy,
+    film_ior: FloatTy,
+    substrate_eta: Vec3,
+    substrate_k: Vec3,
+    /// RGB reflectance of the film by thickness in nanometers and step of
+    /// the incident cosine, as integrating the spectrum is slow.
+    rgb_reflectances: RwLock<HashMap<(u32, u32), Vec3>>,
+}
+
+impl ThinFilm {
+    /// Coats `inner` with a film `thickness` nanometers thick. The substrate
+    /// under the film is a glass of index 1.5 unless set with
+    /// `with_substrate`.
+    pub fn new(inner: Arc<dyn Material>, thickness: FloatTy, film_ior: FloatTy) -> Self {
+        ThinFilm {
+            inner: Some(inner),
+            thickness: Arc::new(SolidTexture::new(Vec3::repeat(1.0))),
+            thickness_scale: thickness,
+            film_ior,
+            substrate_eta: Vec3::repeat(DEFAULT_SUBSTRATE_IOR),
+            substrate_k: Vec3::zeros(),
+            rgb_reflectances: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Film with air on both sides, like a soap bubble, letting the light it
+    /// does not reflect through without deviating it.
+    pub fn bubble(thickness: FloatTy, film_ior: FloatTy) -> Self {
+        ThinFilm {
+            inner: None,
+            thickness: Arc::new(SolidTexture::new(Vec3::repeat(1.0))),
+            thickness_scale: thickness,
+            film_ior,
+            substrate_eta: Vec3::repeat(1.0),
+            substrate_k: Vec3::zeros(),
+            rgb_reflectances: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Reads the thickness from the first channel of a texture, multiplied
+    /// by `scale` to get nanometers.
+    pub fn with_thickness(self, thickness: Arc<dyn Texture>, scale: FloatTy) -> Self {
+        ThinFilm {
+            thickness,
+            thickness_scale: scale,
+            ..self
+        }
+    }
+
+    /// Complex index of refraction `eta + i * k` of the surface under the
+    /// film, which should match the inner material, like the values of a
+    /// `Conductor` for a coated metal.
+    pub fn with_substrate(self, eta: Vec3, k: Vec3) -> Self {
+        ThinFilm {
+            substrate_eta: eta,
+            substrate_k: k,
+            ..self
+        }
+    }
+
+    /// Whether the film is thick enough and optically different from air to
+    /// make reflections interfere.
+    fn has_film(&self) -> bool {
+        self.thickness_scale > 0.0 && self.film_ior != 1.0
+    }
+
+    fn substrate(&self, wavelength: FloatTy) -> Complex {
+        Complex::new(
+            at_wavelength(self.substrate_eta, wavelength),
+            at_wavelength(self.substrate_k, wavelength),
+        )
+    }
+
+    /// RGB reflectance of the film, for the closest thickness in nanometers
+    /// and cosine step already integrated over the spectrum.
+    fn rgb_reflectance(&self, cos_i: FloatTy, thickness: FloatTy) -> Vec3 {
+        let key = (thickness.round() as u32, (cos_i * COS_STEPS).round() as u32);
+        if let Some(reflectance) = self.rgb_reflectances.read().unwrap().get(&key) {
+            return *reflectance;
+        }
+
+        let thickness = key.0 as FloatTy;
+        let cos_i = key.1 as FloatTy / COS_STEPS;
+        let reflectance = spectrum::reflectance_to_rgb(|wavelength| {
+            airy_reflectance(
+                cos_i,
+                thickness,
+                self.film_ior,
+                self.substrate(wavelength),
+                wavelength,
+            )
+        })
+        .map(|c| clamp(c, 0.0, 1.0));
+        self.rgb_reflectances
+            .write()
+            .unwrap()
+            .insert(key, reflectance);
+        reflectance
+    }
+
+    /// Reflectance of the interface with and without the film.
+    fn reflectance(&self, ray: &Ray, record: &HitRecord) -> (Vec3, Vec3) {
+        let cos_i = clamp(-ray.direction.normalize().dot(&record.normal), 0.0, 1.0);
+        let thickness = fmax(
+            self.thickness.value(&record.surface_point()).x * self.thickness_scale,
+            0.0,
+        );
+
+        match ray.wavelength {
+            // without film both reflectances are the same, which spares
+            // integrating the spectrum in RGB
+            _ if thickness <= 0.0 || !self.has_film() => {
+                let bare = utils::fresnel_conductor(cos_i, self.substrate_eta, self.substrate_k);
+                (bare, bare)
+            }
+            Some(wavelength) => {
+                let substrate = self.substrate(wavelength);
+                let film = airy_reflectance(cos_i, thickness, self.film_ior, substrate, wavelength);
+                let bare = airy_reflectance(cos_i, 0.0, 1.0, substrate, wavelength);
+                (Vec3::repeat(film), Vec3::repeat(bare))
+            }
+            None => (
+                self.rgb_reflectance(cos_i, thickness),
+                utils::fresnel_conductor(cos_i, self.substrate_eta, self.substrate_k),
+            ),
+        }
+    }
+
+    /// Factor applied to what the inner material sends toward `direction`.
+    fn modulation(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        // the film is on the outside of the surface
+        if !record.front_face {
+            return Vec3::repeat(1.0);
+        }
+
+        let (film, bare) = self.reflectance(ray, record);
+        let reflected = direction.dot(&record.normal) > 0.0;
+        film.zip_map(&bare, |film, bare| {
+            if reflected && bare > fconsts::EPSILON {
+                film / bare
+            } else if !reflected && bare < 1.0 - fconsts::EPSILON {
+                (1.0 - film) / (1.0 - bare)
+            } else {
+                1.0
+            }
+        })
+    }
+}
+
+impl Material for ThinFilm {
+    fn scatter(&self, ray: &Ray, record: &HitRecord) -> Option<MaterialScatter<'_>> {
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => {
+                let mut rng = rand::thread_rng();
+                let (film, _) = self.reflectance(ray, record);
+                let probability = mean(film);
+                let scatter = if rng.gen::<FloatTy>() < probability {
+                    let reflected = utils::reflect(ray.direction.normalize(), record.normal);
+                    MaterialScatter {
+                        attenuation: film / probability,
+                        scattered: Some(Ray::new(record.p, reflected)),
+                        is_specular: true,
+                        subsurface: None,
+                    }
+                } else {
+                    MaterialScatter {
+                        attenuation: (Vec3::repeat(1.0) - film) / (1.0 - probability),
+                        scattered: Some(Ray::new(record.p, ray.direction)),
+                        is_specular: true,
+                        subsurface: None,
+                    }
+                };
+                return Some(scatter);
+            }
+        };
+
+        let mut scatter = inner.scatter(ray, record)?;
+        if let Some(scattered) = &scatter.scattered {
+            let modulation = self.modulation(ray, record, scattered.direction);
+            scatter.attenuation = scatter.attenuation.component_mul(&modulation);
+        }
+        Some(scatter)
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Vec3 {
+        match &self.inner {
+            Some(inner) => inner
+                .eval(ray, record, direction)
+                .component_mul(&self.modulation(ray, record, direction)),
+            None => Vec3::zeros(),
+        }
+    }
+
+    fn emit(&self, ray: &Ray, record: &HitRecord) -> Vec3 {
+        match &self.inner {
+            Some(inner) => inner.emit(ray, record),
+            None => Vec3::zeros(),
+        }
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.has_film() || matches!(&self.inner, Some(inner) if inner.is_dispersive())
+    }
+}
@@ -40,3 +40,61 @@ pub fn random_unit_sphere<R: Rng>(rng: &mut R, normal: Vec3) -> Vec3 {
     let v: Vec3 = v.into();
     v + normal
 }
+
+/// Exact Fresnel reflectance of a dielectric interface, `eta` being the ratio
+/// of the transmitted over the incident index of refraction.
+#[inline]
+pub fn fresnel_dielectric(cos_i: FloatTy, eta: FloatTy) -> FloatTy {
+    let cos_i = cos_i.abs().min(1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_s = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let r_p = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    (r_s * r_s + r_p * r_p) / 2.0
+}
+
+/// Fresnel reflectance of a conductor with complex index of refraction
+/// `eta + i * k`, evaluated per channel.
+#[inline]
+pub fn fresnel_conductor(cos_i: FloatTy, eta: Vec3, k: Vec3) -> Vec3 {
+    let cos_i = cos_i.abs().min(1.0);
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1.0 - cos2;
+
+    eta.zip_map(&k, |eta, k| {
+        let t0 = eta * eta - k * k - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_i * a;
+        let r_s = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let r_p = r_s * (t3 - t4) / (t3 + t4);
+        (r_s + r_p) / 2.0
+    })
+}
+
+#[inline]
+pub fn reflect_local(v: Vec3, m: Vec3) -> Vec3 {
+    -v + m * 2.0 * v.dot(&m)
+}
+
+/// Refracts `wi` (pointing away from the surface) through the microfacet `m`,
+/// `eta` being the ratio of the transmitted over the incident index.
+#[inline]
+pub fn refract_local(wi: Vec3, m: Vec3, eta: FloatTy) -> Option<Vec3> {
+    let cos_i = wi.dot(&m);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-wi / eta + m * (cos_i / eta - cos_t))
+}
@@ -0,0 +1,120 @@
+use rand::prelude::*;
+
+use crate::fconsts;
+use crate::utils::{clamp, Frame};
+use crate::{FloatTy, Vec3};
+
+/// Participating medium with the same density everywhere, scattering light
+/// with a Henyey-Greenstein phase function.
+#[derive(Debug, Clone)]
+pub struct HomogeneousMedium {
+    sigma_a: Vec3,
+    sigma_s: Vec3,
+    /// Mean cosine of the scattering angle, positive for forward scattering.
+    g: FloatTy,
+}
+
+impl HomogeneousMedium {
+    /// Absorption and scattering coefficients per channel, per scene unit.
+    pub fn new(sigma_a: Vec3, sigma_s: Vec3, g: FloatTy) -> Self {
+        HomogeneousMedium {
+            sigma_a,
+            sigma_s,
+            g: clamp(g, -0.99, 0.99),
+        }
+    }
+
+    /// Medium from the fraction of light surviving each scattering event and
+    /// the mean distance between two events, per channel.
+    pub fn from_albedo(albedo: Vec3, mean_free_path: Vec3, g: FloatTy) -> Self {
+        let sigma_t = mean_free_path.map(|d| 1.0 / d.max(fconsts::EPSILON));
+        let sigma_s = albedo.component_mul(&sigma_t);
+        HomogeneousMedium::new(sigma_t - sigma_s, sigma_s, g)
+    }
+
+    pub fn sigma_t(&self) -> Vec3 {
+        self.sigma_a + self.sigma_s
+    }
+
+    /// Samples the distance to the next scattering event along a unit ray
+    /// that leaves the medium after `tmax`, picking the channel that drives
+    /// the sampling in proportion to the current path `throughput`.
+    ///
+    /// Returns the distance if the ray scatters before leaving, and the
+    /// weight to apply to the throughput.
+    pub fn sample_distance<R: Rng>(
+        &self,
+        tmax: FloatTy,
+        throughput: Vec3,
+        rng: &mut R,
+    ) -> (Option<FloatTy>, Vec3) {
+        let sigma_t = self.sigma_t();
+        let total = throughput.x + throughput.y + throughput.z;
+        let probabilities = if total > 0.0 {
+            throughput / total
+        } else {
+            Vec3::repeat(1.0 / 3.0)
+        };
+
+        let u = rng.gen::<FloatTy>();
+        let channel = if u < probabilities.x {
+            0
+        } else if u < probabilities.x + probabilities.y {
+            1
+        } else {
+            2
+        };
+
+        let distance = if sigma_t[channel] > 0.0 {
+            -(1.0 - rng.gen::<FloatTy>()).ln() / sigma_t[channel]
+        } else {
+            FloatTy::INFINITY
+        };
+
+        let scatters = distance < tmax;
+        let t = if scatters { distance } else { tmax };
+        let transmittance = sigma_t.map(|s| (-s * t).exp());
+
+        if scatters {
+            let pdf = probabilities.dot(&sigma_t.component_mul(&transmittance));
+            let weight = if pdf > 0.0 {
+                self.sigma_s.component_mul(&transmittance) / pdf
+            } else {
+                Vec3::zeros()
+            };
+            (Some(distance), weight)
+        } else {
+            let pdf = probabilities.dot(&transmittance);
+            let weight = if pdf > 0.0 {
+                transmittance / pdf
+            } else {
+                Vec3::zeros()
+            };
+            (None, weight)
+        }
+    }
+
+    /// Samples the new direction of a ray scattering while traveling along
+    /// the unit `direction`.
+    pub fn sample_phase<R: Rng>(&self, direction: Vec3, rng: &mut R) -> Vec3 {
+        let u1 = rng.gen::<FloatTy>();
+        let u2 = rng.gen::<FloatTy>();
+        let g = self.g;
+
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u1
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u1);
+            (1.0 + g * g - s * s) / (2.0 * g)
+        };
+        let cos_theta = clamp(cos_theta, -1.0, 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * fconsts::PI * u2;
+
+        Frame::from_normal(direction).to_world(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
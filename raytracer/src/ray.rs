@@ -1,15 +1,49 @@
 use crate::FloatTy;
 use crate::{Pt3, Vec3};
 
+/// Rays going through the neighbouring pixels of a camera ray, from which
+/// the footprint of the pixel on the surfaces it hits is found.
+#[derive(Debug, Clone, Copy)]
+pub struct RayDifferentials {
+    pub x_origin: Pt3,
+    pub x_direction: Vec3,
+    pub y_origin: Pt3,
+    pub y_direction: Vec3,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
     pub origin: Pt3,
     pub direction: Vec3,
+    /// Wavelength in nanometers carried by the ray in spectral rendering.
+    pub wavelength: Option<FloatTy>,
+    /// Only known for camera rays, the footprint of scattered rays is not
+    /// tracked.
+    pub differentials: Option<RayDifferentials>,
 }
 
 impl Ray {
     pub fn new(origin: Pt3, direction: Vec3) -> Self {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            wavelength: None,
+            differentials: None,
+        }
+    }
+
+    pub fn with_wavelength(self, wavelength: FloatTy) -> Self {
+        Ray {
+            wavelength: Some(wavelength),
+            ..self
+        }
+    }
+
+    pub fn with_differentials(self, differentials: RayDifferentials) -> Self {
+        Ray {
+            differentials: Some(differentials),
+            ..self
+        }
     }
 
     pub fn point_at_parameter(&self, param: FloatTy) -> Pt3 {
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use crate::hittable::{make_box, XYRect, XZRect, YZRect};
+use crate::material::{Lambertian, Light, Metal};
+use crate::{FloatTy, Hittable, HittableExt, Mat44, Pt3, Vec3};
+
+/// Cornell box 555 units wide with a mirror box and a white one, lit by a
+/// warm area light in its ceiling. The camera looks at its open side, along
+/// +Z from `(278, 278, -800)`.
+pub fn cornell_box() -> Vec<Box<dyn Hittable>> {
+    let red = Arc::new(Lambertian::from_solid_color(Vec3::new(0.7, 0.12, 0.05)));
+    let wall = Arc::new(Lambertian::from_solid_color(Vec3::repeat(1.0)));
+    let white = Arc::new(Lambertian::from_solid_color(Vec3::new(0.95, 0.95, 0.95)));
+    let green = Arc::new(Lambertian::from_solid_color(Vec3::new(0.2, 0.4, 0.36)));
+    let light = Arc::new(Light::new(Vec3::new(1.0, 0.7, 0.38) * 30.0));
+    let mirror = Arc::new(Metal::new(Vec3::repeat(0.9), None));
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, red)),
+        Box::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, green)),
+        Box::new(XZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, wall.clone())),
+        Box::new(XYRect::new(0.0, 555.0, 0.0, 555.0, 555.0, wall.clone())),
+        Box::new(XZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, wall)),
+        Box::new(XZRect::new(213.0, 343.0, 227.0, 332.0, 554.0, light)),
+    ];
+
+    let box1 = make_box(Pt3::origin(), Pt3::new(165.0, 330.0, 165.0), mirror);
+    let transform1 = Mat44::new_translation(&Vec3::new(265.0, 0.0, 295.0))
+        * Mat44::from_axis_angle(&Vec3::y_axis(), (15.0 as FloatTy).to_radians());
+    objects.push(Box::new(box1.transform(transform1)));
+
+    let box2 = make_box(Pt3::origin(), Pt3::new(165.0, 165.0, 165.0), white);
+    let transform2 = Mat44::new_translation(&Vec3::new(130.0, 0.0, 65.0))
+        * Mat44::from_axis_angle(&Vec3::y_axis(), (-18.0 as FloatTy).to_radians());
+    objects.push(Box::new(box2.transform(transform2)));
+
+    objects
+}
@@ -0,0 +1,63 @@
+use rand::prelude::*;
+
+use crate::aov::trace_aovs;
+use crate::background::Background;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::{trace, Aovs, Carrier, Hittable, LightSource, Ray, Vec3};
+
+/// Same as `compute_color`, but following a few wavelengths along the path
+/// instead of RGB colors, so that dispersive materials split light.
+pub fn compute_spectral_color<R: Rng>(
+    objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
+    ray: Ray,
+    background: &dyn Background,
+    rng: &mut R,
+) -> Vec3 {
+    let wavelengths = SampledWavelengths::sample(rng.gen());
+    let ray = wavelengths.ray(ray);
+    let radiance = trace(&wavelengths, objects, lights, ray, background, true, rng);
+    wavelengths.to_rgb(radiance)
+}
+
+/// Same as `compute_aovs`, following wavelengths like
+/// `compute_spectral_color`.
+pub fn compute_spectral_aovs<R: Rng>(
+    objects: &[Box<dyn Hittable>],
+    lights: &[Box<dyn LightSource>],
+    ray: Ray,
+    background: &dyn Background,
+    rng: &mut R,
+) -> Aovs {
+    let wavelengths = SampledWavelengths::sample(rng.gen());
+    let ray = wavelengths.ray(ray);
+    trace_aovs(&wavelengths, objects, lights, ray, background, rng)
+}
+
+impl Carrier for SampledWavelengths {
+    type Light = SampledSpectrum;
+
+    fn black(&self) -> SampledSpectrum {
+        SampledSpectrum::zeros()
+    }
+
+    fn emitted(&self, radiance: Vec3) -> SampledSpectrum {
+        self.illuminant(radiance)
+    }
+
+    fn reflected(&self, light: SampledSpectrum, weight: Vec3) -> SampledSpectrum {
+        light.component_mul(&self.reflectance(weight))
+    }
+
+    fn ray(&self, ray: Ray) -> Ray {
+        ray.with_wavelength(self.hero())
+    }
+
+    fn dispersed(&self, light: SampledSpectrum) -> SampledSpectrum {
+        self.hero_only(light)
+    }
+
+    fn to_rgb(&self, light: SampledSpectrum) -> Vec3 {
+        SampledWavelengths::to_rgb(self, light)
+    }
+}
@@ -0,0 +1,232 @@
+use nalgebra::{Matrix3, Matrix4x3, Vector4};
+
+use crate::utils::fmax;
+use crate::{FloatTy, Vec3};
+
+/// Visible range used when integrating spectra, in nanometers.
+pub const LAMBDA_MIN: FloatTy = 360.0;
+pub const LAMBDA_MAX: FloatTy = 830.0;
+
+/// Luminous efficacy of radiation at 555nm, in lumens per watt.
+pub const LUMENS_PER_WATT: FloatTy = 683.0;
+
+const INTEGRATION_STEP: FloatTy = 5.0;
+
+/// Number of wavelengths carried together by a path in spectral rendering.
+pub const WAVELENGTH_COUNT: usize = 4;
+
+/// Values of a spectrum at the wavelengths of a `SampledWavelengths`.
+pub type SampledSpectrum = Vector4<FloatTy>;
+
+/// Color temperature of the white emitter, close to the D65 white point of
+/// sRGB, whose spectrum is given to white light.
+const WHITE_TEMPERATURE: FloatTy = 6504.0;
+const BASIS_WIDTH: FloatTy = 15.0;
+
+/// Coarser step used when converting reflectance spectra while rendering.
+const REFLECTANCE_STEP: FloatTy = 10.0;
+
+fn piecewise_gaussian(x: FloatTy, mu: FloatTy, sigma_low: FloatTy, sigma_high: FloatTy) -> FloatTy {
+    let sigma = if x < mu { sigma_low } else { sigma_high };
+    let t = (x - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+/// CIE 1931 2° color matching functions at `lambda` nanometers, using the
+/// multi-lobe fit of Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(lambda: FloatTy) -> Vec3 {
+    let x = 1.056 * piecewise_gaussian(lambda, 599.8, 37.9, 31.0)
+        + 0.362 * piecewise_gaussian(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * piecewise_gaussian(lambda, 501.1, 20.4, 26.2);
+    let y = 0.821 * piecewise_gaussian(lambda, 568.8, 46.9, 40.5)
+        + 0.286 * piecewise_gaussian(lambda, 530.9, 16.3, 31.1);
+    let z = 1.217 * piecewise_gaussian(lambda, 437.0, 11.8, 36.0)
+        + 0.681 * piecewise_gaussian(lambda, 459.0, 26.0, 13.8);
+    Vec3::new(x, y, z)
+}
+
+/// Converts CIE XYZ to linear Rec.709/sRGB primaries with a D65 white point.
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    Vec3::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+/// Relative luminance (CIE Y) of a linear RGB color.
+pub fn luminance(rgb: Vec3) -> FloatTy {
+    0.2126 * rgb.x + 0.7152 * rgb.y + 0.0722 * rgb.z
+}
+
+/// Color of the same chromaticity with a luminance of one, black for colors
+/// without luminance which have no chromaticity to keep.
+pub fn normalize_luminance(rgb: Vec3) -> Vec3 {
+    let y = luminance(rgb);
+    if y > 0.0 {
+        rgb / y
+    } else {
+        Vec3::zeros()
+    }
+}
+
+/// Spectral radiance of a black body at `kelvin`, for a wavelength in
+/// nanometers, in W / (sr m² nm).
+pub fn planck(lambda: FloatTy, kelvin: FloatTy) -> FloatTy {
+    const H: FloatTy = 6.626_070_15e-34;
+    const C: FloatTy = 299_792_458.0;
+    const K_B: FloatTy = 1.380_649e-23;
+
+    let l = lambda * 1e-9;
+    let radiance = 2.0 * H * C * C / (l.powi(5) * ((H * C / (l * K_B * kelvin)).exp() - 1.0));
+    radiance * 1e-9
+}
+
+/// Linear RGB color of a black body at `kelvin`, normalized to a luminance of
+/// one so that it only carries the chromaticity of the emitter. Black for
+/// temperatures that are not above absolute zero.
+pub fn blackbody(kelvin: FloatTy) -> Vec3 {
+    if kelvin <= 0.0 {
+        return Vec3::zeros();
+    }
+
+    let mut xyz = Vec3::zeros();
+    let mut lambda = LAMBDA_MIN;
+    while lambda <= LAMBDA_MAX {
+        xyz += cie_xyz(lambda) * planck(lambda, kelvin);
+        lambda += INTEGRATION_STEP;
+    }
+
+    // very warm colors fall outside of the sRGB gamut
+    let rgb = xyz_to_rgb(xyz).map(|c| fmax(c, 0.0));
+    normalize_luminance(rgb)
+}
+
+/// Smooth spectra going from the red to the blue end of the visible range and
+/// summing to one, on which RGB colors are upsampled.
+fn basis(lambda: FloatTy) -> Vec3 {
+    let red = 1.0 / (1.0 + ((590.0 - lambda) / BASIS_WIDTH).exp());
+    let blue = 1.0 / (1.0 + ((lambda - 490.0) / BASIS_WIDTH).exp());
+    Vec3::new(red, 1.0 - red - blue, blue)
+}
+
+fn white_emitter(lambda: FloatTy) -> FloatTy {
+    planck(lambda, WHITE_TEMPERATURE) / planck(560.0, WHITE_TEMPERATURE)
+}
+
+/// Linear RGB color of a surface reflecting the fraction `reflectance(lambda)`
+/// of white light, so that a perfect reflector stays white.
+pub fn reflectance_to_rgb<F: Fn(FloatTy) -> FloatTy>(reflectance: F) -> Vec3 {
+    let mut xyz = Vec3::zeros();
+    let mut white = Vec3::zeros();
+    let mut lambda = 380.0;
+    while lambda <= 780.0 {
+        let cie = cie_xyz(lambda) * white_emitter(lambda);
+        xyz += cie * reflectance(lambda);
+        white += cie;
+        lambda += REFLECTANCE_STEP;
+    }
+    xyz_to_rgb(xyz).component_div(&xyz_to_rgb(white))
+}
+
+struct Upsampling {
+    /// Linear RGB to the weights of the basis spectra.
+    to_basis: Matrix3<FloatTy>,
+    /// RGB of the white emitter, which is divided out so that it stays white.
+    white: Vec3,
+}
+
+impl Upsampling {
+    fn new() -> Self {
+        let mut white_xyz = Vec3::zeros();
+        let mut basis_xyz = Matrix3::zeros();
+        let mut lambda = LAMBDA_MIN;
+        while lambda <= LAMBDA_MAX {
+            let xyz = cie_xyz(lambda) * (white_emitter(lambda) * INTEGRATION_STEP);
+            white_xyz += xyz;
+            basis_xyz += xyz * basis(lambda).transpose();
+            lambda += INTEGRATION_STEP;
+        }
+
+        let white = xyz_to_rgb(white_xyz);
+        let mut basis_rgb = Matrix3::zeros();
+        for (j, xyz) in basis_xyz.column_iter().enumerate() {
+            basis_rgb.set_column(j, &xyz_to_rgb(xyz.into_owned()).component_div(&white));
+        }
+
+        Upsampling {
+            to_basis: basis_rgb.try_inverse().unwrap(),
+            white,
+        }
+    }
+}
+
+thread_local! {
+    static UPSAMPLING: Upsampling = Upsampling::new();
+}
+
+/// Wavelengths followed by a path in spectral rendering: a hero wavelength
+/// and others evenly spaced after it over the visible range.
+#[derive(Debug, Clone)]
+pub struct SampledWavelengths {
+    lambda: SampledSpectrum,
+    /// RGB to spectrum at each of the wavelengths, one row per wavelength.
+    upsampling: Matrix4x3<FloatTy>,
+    illuminant: SampledSpectrum,
+    white: Vec3,
+}
+
+impl SampledWavelengths {
+    /// Picks the hero wavelength uniformly from `u` in [0, 1).
+    pub fn sample(u: FloatTy) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let lambda = SampledSpectrum::from_fn(|i, _| {
+            LAMBDA_MIN + (u + i as FloatTy / WAVELENGTH_COUNT as FloatTy).fract() * range
+        });
+
+        let mut basis_values = Matrix4x3::zeros();
+        for i in 0..WAVELENGTH_COUNT {
+            basis_values.set_row(i, &basis(lambda[i]).transpose());
+        }
+
+        let (upsampling, white) = UPSAMPLING.with(|up| (basis_values * up.to_basis, up.white));
+
+        SampledWavelengths {
+            lambda,
+            upsampling,
+            illuminant: lambda.map(white_emitter),
+            white,
+        }
+    }
+
+    pub fn hero(&self) -> FloatTy {
+        self.lambda[0]
+    }
+
+    /// Spectrum of a reflectance or scattering weight given in linear RGB.
+    pub fn reflectance(&self, rgb: Vec3) -> SampledSpectrum {
+        (self.upsampling * rgb).map(|c| fmax(c, 0.0))
+    }
+
+    /// Spectrum of an emitted radiance given in linear RGB.
+    pub fn illuminant(&self, rgb: Vec3) -> SampledSpectrum {
+        self.reflectance(rgb).component_mul(&self.illuminant)
+    }
+
+    /// Drops the wavelengths other than the hero one, once a path went
+    /// through a dispersive interface that only the hero wavelength follows.
+    pub fn hero_only(&self, spectrum: SampledSpectrum) -> SampledSpectrum {
+        SampledSpectrum::new(spectrum[0] * WAVELENGTH_COUNT as FloatTy, 0.0, 0.0, 0.0)
+    }
+
+    /// Estimates the linear RGB color of the spectrum the samples were taken
+    /// from.
+    pub fn to_rgb(&self, spectrum: SampledSpectrum) -> Vec3 {
+        let mut xyz = Vec3::zeros();
+        for i in 0..WAVELENGTH_COUNT {
+            xyz += cie_xyz(self.lambda[i]) * spectrum[i];
+        }
+        xyz *= (LAMBDA_MAX - LAMBDA_MIN) / WAVELENGTH_COUNT as FloatTy;
+        xyz_to_rgb(xyz).component_div(&self.white)
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+
+use super::{
+    decode_image, image_size, ImageTexture, MipLevel, MipMap, SurfacePoint, Texture, TextureError,
+    TextureFilter, WrapMode,
+};
+use crate::{ColorSpace, FloatTy, Vec3};
+
+/// Number of lookups during which an image looked up stays whole, so that
+/// images used together do not keep evicting each other.
+const RECENT_LOOKUPS: u64 = 1 << 16;
+
+#[derive(Debug)]
+struct CacheState {
+    images: Mutex<HashMap<(PathBuf, ColorSpace), Arc<CachedImage>>>,
+    budget: Option<usize>,
+    /// Incremented at each lookup to find the images used least recently,
+    /// starting at 1 as 0 marks images never looked up.
+    clock: AtomicU64,
+}
+
+impl CacheState {
+    fn is_recent(&self, image: &CachedImage) -> bool {
+        let last_use = image.last_use.load(Ordering::Relaxed);
+        let now = self.clock.load(Ordering::Relaxed);
+        last_use != 0 && now.saturating_sub(last_use) < RECENT_LOOKUPS
+    }
+
+    /// Drops the finest levels of the images used least recently until the
+    /// memory used fits in the budget, never touching `keep` nor the images
+    /// looked up recently.
+    fn enforce_budget(&self, keep: &CachedImage) {
+        let budget = match self.budget {
+            Some(budget) => budget,
+            None => return,
+        };
+
+        let mut images: Vec<Arc<CachedImage>> =
+            self.images.lock().unwrap().values().cloned().collect();
+        let mut total: usize = images.iter().map(|image| image.memory_size()).sum();
+        images.sort_by_key(|image| image.last_use.load(Ordering::Relaxed));
+
+        for image in images {
+            if total <= budget {
+                break;
+            }
+            if std::ptr::eq(image.as_ref(), keep) || self.is_recent(&image) {
+                continue;
+            }
+            while total > budget {
+                match image.drop_finest_level() {
+                    Some(freed) => total -= freed,
+                    None => break,
+                }
+            }
+        }
+    }
+}
+
+/// Shared set of images, each one loaded once for every texture using it.
+///
+/// Images are decoded on their first lookup, only their header being read
+/// when requested to report missing files before rendering. When a memory
+/// budget is set, the finest levels of the mip pyramids used least recently
+/// are dropped to stay under it, and loaded again when a lookup needs them.
+/// The budget is not a hard limit: images looked up recently are kept whole.
+#[derive(Debug, Clone)]
+pub struct TextureCache {
+    state: Arc<CacheState>,
+}
+
+impl TextureCache {
+    fn with_state(budget: Option<usize>) -> Self {
+        TextureCache {
+            state: Arc::new(CacheState {
+                images: Mutex::new(HashMap::new()),
+                budget,
+                clock: AtomicU64::new(1),
+            }),
+        }
+    }
+
+    /// Cache keeping every image it loads.
+    pub fn new() -> Self {
+        TextureCache::with_state(None)
+    }
+
+    /// Cache with a memory budget in bytes for the texels of all images.
+    pub fn bounded(budget: usize) -> Self {
+        TextureCache::with_state(Some(budget))
+    }
+
+    /// Image at a path decoded in a color space, shared with every previous
+    /// request for the same path and color space.
+    pub fn image<P: AsRef<Path>>(
+        &self,
+        path: P,
+        color_space: ColorSpace,
+    ) -> Result<Arc<CachedImage>, TextureError> {
+        let path = fs::canonicalize(path)?;
+        let key = (path.clone(), color_space);
+        if let Some(image) = self.state.images.lock().unwrap().get(&key) {
+            return Ok(image.clone());
+        }
+
+        // read without holding the lock, as another request for the same
+        // image in the meantime only reads the header again
+        image_size(&path)?;
+        let image = self
+            .state
+            .images
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(CachedImage {
+                    path,
+                    color_space,
+                    mipmap: RwLock::new(None),
+                    loading: Mutex::new(()),
+                    load_failed: AtomicBool::new(false),
+                    last_use: AtomicU64::new(0),
+                    cache: Arc::downgrade(&self.state),
+                })
+            })
+            .clone();
+        Ok(image)
+    }
+
+    /// Texture sampling the shared image at a path.
+    pub fn texture<P: AsRef<Path>>(
+        &self,
+        path: P,
+        color_space: ColorSpace,
+    ) -> Result<CachedTexture, TextureError> {
+        Ok(CachedTexture::new(self.image(path, color_space)?))
+    }
+
+    /// Memory used by the texels of the images loaded.
+    pub fn memory_size(&self) -> usize {
+        self.state
+            .images
+            .lock()
+            .unwrap()
+            .values()
+            .map(|image| image.memory_size())
+            .sum()
+    }
+}
+
+impl Default for TextureCache {
+    fn default() -> Self {
+        TextureCache::new()
+    }
+}
+
+fn load_mipmap(path: &Path, color_space: ColorSpace) -> Result<MipMap, TextureError> {
+    let image = decode_image(path)?;
+    Ok(ImageTexture::from_decoded(image, color_space).mipmap)
+}
+
+/// Image of a `TextureCache`, loaded on demand.
+#[derive(Debug)]
+pub struct CachedImage {
+    path: PathBuf,
+    color_space: ColorSpace,
+    mipmap: RwLock<Option<Arc<MipMap>>>,
+    /// Held while the image is decoded, lookups served by the levels in
+    /// memory going on meanwhile.
+    loading: Mutex<()>,
+    /// Set when the file could not be decoded, the levels in memory being
+    /// used from then on.
+    load_failed: AtomicBool,
+    last_use: AtomicU64,
+    cache: Weak<CacheState>,
+}
+
+impl CachedImage {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Memory used by the texels of the levels in memory.
+    pub fn memory_size(&self) -> usize {
+        self.mipmap
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|mipmap| mipmap.memory_size())
+            .unwrap_or(0)
+    }
+
+    /// Returns the memory freed, if there was a level to drop.
+    fn drop_finest_level(&self) -> Option<usize> {
+        let mut mipmap = self.mipmap.write().unwrap();
+        let current = mipmap.as_ref()?;
+        let smaller = current.without_finest_level()?;
+        let freed = current.memory_size() - smaller.memory_size();
+        *mipmap = Some(Arc::new(smaller));
+        Some(freed)
+    }
+
+    /// Pyramid holding the levels needed by a lookup, loading the image
+    /// when it is not in memory or misses these levels.
+    ///
+    /// When the file cannot be decoded, the coarser levels still in memory
+    /// are used instead, or a black texel when there are none.
+    pub fn mipmap(&self, point: &SurfacePoint, filter: TextureFilter) -> Arc<MipMap> {
+        if let Some(cache) = self.cache.upgrade() {
+            let now = cache.clock.fetch_add(1, Ordering::Relaxed);
+            self.last_use.store(now, Ordering::Relaxed);
+        }
+
+        let current = || self.mipmap.read().unwrap().clone();
+        let usable = |mipmap: &Option<Arc<MipMap>>| {
+            mipmap
+                .as_ref()
+                .filter(|mipmap| {
+                    self.load_failed.load(Ordering::Relaxed)
+                        || mipmap.dropped_levels() <= mipmap.finest_level_needed(point, filter)
+                })
+                .cloned()
+        };
+        if let Some(mipmap) = usable(&current()) {
+            return mipmap;
+        }
+
+        let _loading = self.loading.lock().unwrap();
+        // another thread may have loaded it meanwhile
+        let mipmap = current();
+        if let Some(mipmap) = usable(&mipmap) {
+            return mipmap;
+        }
+        let loaded = match load_mipmap(&self.path, self.color_space) {
+            Ok(loaded) => Arc::new(loaded),
+            Err(_) => {
+                self.load_failed.store(true, Ordering::Relaxed);
+                let fallback = mipmap.unwrap_or_else(|| {
+                    let black = MipLevel::new(1, 1, vec![[0.0, 0.0, 0.0, 1.0]]);
+                    Arc::new(MipMap::new(black))
+                });
+                *self.mipmap.write().unwrap() = Some(fallback.clone());
+                return fallback;
+            }
+        };
+        *self.mipmap.write().unwrap() = Some(loaded.clone());
+
+        if let Some(cache) = self.cache.upgrade() {
+            cache.enforce_budget(self);
+        }
+        loaded
+    }
+}
+
+/// Texture sampling an image of a `TextureCache`.
+#[derive(Debug, Clone)]
+pub struct CachedTexture {
+    image: Arc<CachedImage>,
+    filter: TextureFilter,
+    wrap: WrapMode,
+}
+
+impl CachedTexture {
+    pub fn new(image: Arc<CachedImage>) -> Self {
+        CachedTexture {
+            image,
+            filter: TextureFilter::Ewa,
+            wrap: WrapMode::Repeat,
+        }
+    }
+
+    pub fn with_filter(self, filter: TextureFilter) -> Self {
+        CachedTexture { filter, ..self }
+    }
+
+    pub fn with_wrap(self, wrap: WrapMode) -> Self {
+        CachedTexture { wrap, ..self }
+    }
+
+    fn lookup(&self, point: &SurfacePoint) -> [FloatTy; 4] {
+        self.image
+            .mipmap(point, self.filter)
+            .lookup(point, self.filter, self.wrap)
+    }
+}
+
+impl Texture for CachedTexture {
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        let texel = self.lookup(point);
+        Vec3::new(texel[0], texel[1], texel[2])
+    }
+
+    fn alpha(&self, point: &SurfacePoint) -> FloatTy {
+        self.lookup(point)[3]
+    }
+}
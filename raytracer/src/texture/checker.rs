@@ -1,5 +1,5 @@
-use super::Texture;
-use crate::{FloatTy, Vec3};
+use super::{SurfacePoint, Texture, TextureSpace};
+use crate::{fconsts, FloatTy, Vec3};
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -7,21 +7,88 @@ pub struct CheckerTexture {
     pub coeff: FloatTy,
     pub even: Arc<dyn Texture>,
     pub odd: Arc<dyn Texture>,
+    pub space: TextureSpace,
 }
 
 impl CheckerTexture {
     pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, coeff: FloatTy) -> Self {
-        CheckerTexture { even, odd, coeff }
+        CheckerTexture {
+            even,
+            odd,
+            coeff,
+            space: TextureSpace::Uv,
+        }
+    }
+
+    /// Checkers of the texture coordinates by default, or cubes of 3D space
+    /// otherwise.
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        CheckerTexture { space, ..self }
     }
 }
 
-impl Texture for CheckerTexture {
-    fn value(&self, u: FloatTy, v: FloatTy) -> Vec3 {
-        let coeff = (self.coeff * u).sin() * (self.coeff * v).sin();
+impl CheckerTexture {
+    fn select(&self, point: &SurfacePoint) -> &dyn Texture {
+        let p = point.position(self.space) * self.coeff;
+        let mut coeff = p.x.sin() * p.y.sin();
+        if self.space != TextureSpace::Uv {
+            coeff *= p.z.sin();
+        }
         if coeff < 0.0 {
-            self.odd.value(u, v)
+            self.odd.as_ref()
         } else {
-            self.even.value(u, v)
+            self.even.as_ref()
         }
     }
 }
+
+/// Integral from 0 of a square wave equal to 1 when the integer part of `x`
+/// is odd and 0 otherwise.
+fn odd_integral(x: FloatTy) -> FloatTy {
+    let half = x / 2.0;
+    half.floor() + 2.0 * (half - half.floor() - 0.5).max(0.0)
+}
+
+impl CheckerTexture {
+    /// Fraction of odd squares in the footprint of the pixel, found by
+    /// integrating the checkers over a box around the point.
+    fn odd_fraction(&self, point: &SurfacePoint) -> FloatTy {
+        // one unit per square
+        let scale = self.coeff / fconsts::PI;
+        let s = point.u * scale;
+        let t = point.v * scale;
+        let ds = point.dudx.abs().max(point.dudy.abs()) * scale;
+        let dt = point.dvdx.abs().max(point.dvdy.abs()) * scale;
+        if ds > 1.0 || dt > 1.0 {
+            return 0.5;
+        }
+
+        let average = |x: FloatTy, dx: FloatTy| {
+            if dx > 0.0 {
+                (odd_integral(x + dx) - odd_integral(x - dx)) / (2.0 * dx)
+            } else {
+                (x.floor().rem_euclid(2.0) != 0.0) as u8 as FloatTy
+            }
+        };
+        let odd_s = average(s, ds);
+        let odd_t = average(t, dt);
+        // a square is odd when exactly one of its coordinates is
+        odd_s + odd_t - 2.0 * odd_s * odd_t
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        // checkers of texture coordinates are antialiased, the pattern
+        // aliasing badly on distant floors
+        if self.space == TextureSpace::Uv && point.has_differentials() {
+            let odd = self.odd_fraction(point);
+            return self.even.value(point) * (1.0 - odd) + self.odd.value(point) * odd;
+        }
+        self.select(point).value(point)
+    }
+
+    fn alpha(&self, point: &SurfacePoint) -> FloatTy {
+        self.select(point).alpha(point)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{FloatTy, Pt3};
+
+use noise::{NoiseFn, Perlin, Seedable};
+
+/// Perlin noise summed over several octaves, each one `lacunarity` times
+/// finer and `gain` times weaker than the previous one.
+#[derive(Debug, Clone)]
+pub struct FractalNoise {
+    perlin: Perlin,
+    octaves: u32,
+    lacunarity: FloatTy,
+    gain: FloatTy,
+}
+
+impl FractalNoise {
+    /// A single octave of the noise generated from `seed`.
+    pub fn new(seed: u32) -> Self {
+        FractalNoise {
+            perlin: Perlin::new().set_seed(seed),
+            octaves: 1,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub fn with_octaves(self, octaves: u32) -> Self {
+        FractalNoise {
+            octaves: octaves.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_lacunarity(self, lacunarity: FloatTy) -> Self {
+        FractalNoise { lacunarity, ..self }
+    }
+
+    pub fn with_gain(self, gain: FloatTy) -> Self {
+        FractalNoise { gain, ..self }
+    }
+
+    fn sum(&self, p: Pt3, octave: impl Fn(FloatTy) -> FloatTy) -> FloatTy {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        let mut frequency = 1.0;
+        for _ in 0..self.octaves {
+            let q = p * frequency;
+            total += octave(self.perlin.get([q.x, q.y, q.z])) * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+        if total_amplitude > 0.0 {
+            total / total_amplitude
+        } else {
+            0.0
+        }
+    }
+
+    /// Fractal Brownian motion, between -1 and 1.
+    pub fn fbm(&self, p: Pt3) -> FloatTy {
+        self.sum(p, |n| n)
+    }
+
+    /// Sum of the absolute value of the octaves, between 0 and 1, whose
+    /// creases look like the veins of marble or flames.
+    pub fn turbulence(&self, p: Pt3) -> FloatTy {
+        self.sum(p, FloatTy::abs)
+    }
+}
+
+impl Default for FractalNoise {
+    fn default() -> Self {
+        FractalNoise::new(0)
+    }
+}
@@ -1,42 +1,336 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
 use std::path::Path;
 
-use image::error::ImageResult;
-use image::RgbImage;
+use image::error::ImageError;
+use image::hdr::HdrDecoder;
+use image::DynamicImage;
 
-use super::Texture;
-use crate::{FloatTy, Vec3};
+use super::{MipLevel, MipMap, SurfacePoint, Texture, TextureFilter, WrapMode};
+use crate::{ColorSpace, FloatTy, Vec3};
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(std::io::Error),
+    Image(ImageError),
+    Parse(String),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::Io(err) => write!(f, "cannot read texture: {}", err),
+            TextureError::Image(err) => write!(f, "cannot decode texture: {}", err),
+            TextureError::Parse(msg) => write!(f, "invalid texture: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+impl From<std::io::Error> for TextureError {
+    fn from(err: std::io::Error) -> Self {
+        TextureError::Io(err)
+    }
+}
+
+impl From<ImageError> for TextureError {
+    fn from(err: ImageError) -> Self {
+        TextureError::Image(err)
+    }
+}
+
+/// Texels decoded from an image file, from its top row to its bottom one.
+#[derive(Debug)]
+pub struct DecodedImage {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<[f32; 4]>,
+    /// Whether the file stores floating point values, which are linear.
+    pub is_float: bool,
+}
+
+fn extension_is<P: AsRef<Path>>(path: P, extension: &str) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case(extension))
+        .unwrap_or(false)
+}
+
+/// Reads an image without quantizing it: 8 and 16-bit images keep their
+/// precision, while Radiance `.hdr` and `.pfm` files are read as floats.
+pub fn decode_image<P: AsRef<Path>>(path: P) -> Result<DecodedImage, TextureError> {
+    let path = path.as_ref();
+    if extension_is(path, "hdr") {
+        let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+        let metadata = decoder.metadata();
+        let texels = decoder
+            .read_image_hdr()?
+            .into_iter()
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], 1.0])
+            .collect();
+        return Ok(DecodedImage {
+            width: metadata.width as usize,
+            height: metadata.height as usize,
+            texels,
+            is_float: true,
+        });
+    }
+    if extension_is(path, "pfm") {
+        return parse_pfm(&fs::read(path)?);
+    }
+
+    let image = image::open(path)?;
+    let is_16_bit = matches!(
+        image,
+        DynamicImage::ImageLuma16(_)
+            | DynamicImage::ImageLumaA16(_)
+            | DynamicImage::ImageRgb16(_)
+            | DynamicImage::ImageRgba16(_)
+    );
+    let (width, height, texels) = if is_16_bit {
+        let buffer = image.to_rgba16();
+        let c = |v: u16| f32::from(v) / 65535.0;
+        let texels: Vec<_> = buffer
+            .pixels()
+            .map(|p| [c(p[0]), c(p[1]), c(p[2]), c(p[3])])
+            .collect();
+        (buffer.width(), buffer.height(), texels)
+    } else {
+        let buffer = image.to_rgba8();
+        let c = |v: u8| f32::from(v) / 255.0;
+        let texels: Vec<_> = buffer
+            .pixels()
+            .map(|p| [c(p[0]), c(p[1]), c(p[2]), c(p[3])])
+            .collect();
+        (buffer.width(), buffer.height(), texels)
+    };
+    Ok(DecodedImage {
+        width: width as usize,
+        height: height as usize,
+        texels,
+        is_float: false,
+    })
+}
+
+/// Longest PFM header read to find the size of an image.
+const MAX_PFM_HEADER: u64 = 4096;
+
+/// Size of an image, reading only the header of the file.
+pub fn image_size<P: AsRef<Path>>(path: P) -> Result<(usize, usize), TextureError> {
+    let path = path.as_ref();
+    if extension_is(path, "hdr") {
+        let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+        let metadata = decoder.metadata();
+        return Ok((metadata.width as usize, metadata.height as usize));
+    }
+    if extension_is(path, "pfm") {
+        let mut header = Vec::new();
+        File::open(path)?
+            .take(MAX_PFM_HEADER)
+            .read_to_end(&mut header)?;
+        let header = parse_pfm_header(&header)?;
+        return Ok((header.width, header.height));
+    }
+
+    let (width, height) = image::image_dimensions(path)?;
+    Ok((width as usize, height as usize))
+}
+
+#[derive(Debug)]
+struct PfmHeader {
+    channels: usize,
+    width: usize,
+    height: usize,
+    little_endian: bool,
+    /// Position of the first value in the file.
+    data_start: usize,
+}
+
+fn parse_pfm_header(content: &[u8]) -> Result<PfmHeader, TextureError> {
+    // the header is made of three whitespace separated tokens after the
+    // magic number, followed by a single whitespace character
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while tokens.len() < 4 {
+        while position < content.len() && content[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        let start = position;
+        while position < content.len() && !content[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err(TextureError::Parse("truncated PFM header".to_owned()));
+        }
+        tokens.push(String::from_utf8_lossy(&content[start..position]).into_owned());
+    }
+    position += 1;
+
+    let channels = match tokens[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        other => {
+            return Err(TextureError::Parse(format!(
+                "unknown PFM magic number `{}`",
+                other
+            )))
+        }
+    };
+    let parse_size = |token: &str| {
+        token
+            .parse::<usize>()
+            .map_err(|_| TextureError::Parse(format!("invalid PFM size `{}`", token)))
+    };
+    let width = parse_size(&tokens[1])?;
+    let height = parse_size(&tokens[2])?;
+    if width == 0 || height == 0 {
+        return Err(TextureError::Parse(format!(
+            "empty {}x{} PFM image",
+            width, height
+        )));
+    }
+    let scale: f32 = tokens[3]
+        .parse()
+        .map_err(|_| TextureError::Parse(format!("invalid PFM scale `{}`", tokens[3])))?;
+    // a negative scale marks little endian values
+    let little_endian = scale < 0.0;
+
+    Ok(PfmHeader {
+        channels,
+        width,
+        height,
+        little_endian,
+        data_start: position,
+    })
+}
+
+/// Parses a Portable Float Map, `PF` for color and `Pf` for grayscale
+/// images, whose rows go from the bottom of the image to its top.
+pub fn parse_pfm(content: &[u8]) -> Result<DecodedImage, TextureError> {
+    let PfmHeader {
+        channels,
+        width,
+        height,
+        little_endian,
+        data_start,
+    } = parse_pfm_header(content)?;
+
+    let size = width
+        .checked_mul(height)
+        .and_then(|texels| texels.checked_mul(channels * 4))
+        .ok_or_else(|| TextureError::Parse(format!("PFM size {}x{} too large", width, height)))?;
+    let data = content.get(data_start..).unwrap_or(&[]);
+    if data.len() < size {
+        return Err(TextureError::Parse("truncated PFM data".to_owned()));
+    }
+    let value = |index: usize| {
+        let bytes = [
+            data[index * 4],
+            data[index * 4 + 1],
+            data[index * 4 + 2],
+            data[index * 4 + 3],
+        ];
+        if little_endian {
+            f32::from_le_bytes(bytes)
+        } else {
+            f32::from_be_bytes(bytes)
+        }
+    };
+
+    let mut texels = Vec::with_capacity(width * height);
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let index = (y * width + x) * channels;
+            texels.push(if channels == 3 {
+                [value(index), value(index + 1), value(index + 2), 1.0]
+            } else {
+                let v = value(index);
+                [v, v, v, 1.0]
+            });
+        }
+    }
+    Ok(DecodedImage {
+        width,
+        height,
+        texels,
+        is_float: true,
+    })
+}
 
 #[derive(Debug)]
 pub struct ImageTexture {
-    pub buffer: RgbImage,
+    pub mipmap: MipMap,
+    pub filter: TextureFilter,
+    pub wrap: WrapMode,
+    pub color_space: ColorSpace,
     pub width: u32,
     pub height: u32,
 }
 
 impl ImageTexture {
-    pub fn open<P: AsRef<Path>>(path: P) -> ImageResult<Self> {
-        let buffer = image::open(path)?;
-        let buffer = buffer.to_rgb();
-        let (width, height) = buffer.dimensions();
-        Ok(ImageTexture {
-            buffer,
-            width,
-            height,
-        })
+    /// Opens a color image: 8 and 16-bit images are decoded from sRGB,
+    /// while floating point images are already linear.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, TextureError> {
+        let image = decode_image(path)?;
+        let color_space = if image.is_float {
+            ColorSpace::Linear
+        } else {
+            ColorSpace::Srgb
+        };
+        Ok(ImageTexture::from_decoded(image, color_space))
+    }
+
+    /// Opens an image whose values are in the given color space, linear for
+    /// data such as roughness or normal maps.
+    pub fn open_with_color_space<P: AsRef<Path>>(
+        path: P,
+        color_space: ColorSpace,
+    ) -> Result<Self, TextureError> {
+        let image = decode_image(path)?;
+        Ok(ImageTexture::from_decoded(image, color_space))
+    }
+
+    /// The mip pyramid is built from linear values so that its levels
+    /// average light and not encoded values. Alpha is always linear.
+    pub fn from_decoded(image: DecodedImage, color_space: ColorSpace) -> Self {
+        let texels = image
+            .texels
+            .into_iter()
+            .map(|texel| {
+                let c = |v: f32| color_space.to_linear(FloatTy::from(v)) as f32;
+                [c(texel[0]), c(texel[1]), c(texel[2]), texel[3]]
+            })
+            .collect();
+        let mipmap = MipMap::new(MipLevel::new(image.width, image.height, texels));
+        ImageTexture {
+            mipmap,
+            filter: TextureFilter::Ewa,
+            wrap: WrapMode::Repeat,
+            color_space,
+            width: image.width as u32,
+            height: image.height as u32,
+        }
+    }
+
+    pub fn with_filter(self, filter: TextureFilter) -> Self {
+        ImageTexture { filter, ..self }
+    }
+
+    pub fn with_wrap(self, wrap: WrapMode) -> Self {
+        ImageTexture { wrap, ..self }
     }
 }
 
 impl Texture for ImageTexture {
-    fn value(&self, u: FloatTy, v: FloatTy) -> Vec3 {
-        let u = u.fract();
-        let v = 1.0 - v.fract();
-        let x = (u * self.width as FloatTy) as u32;
-        let y = (v * self.height as FloatTy) as u32;
-        let pixel = self.buffer.get_pixel(x, y);
-        let r = pixel[0] as FloatTy / 255.0;
-        let g = pixel[1] as FloatTy / 255.0;
-        let b = pixel[2] as FloatTy / 255.0;
-
-        Vec3::new(r, g, b)
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        let texel = self.mipmap.lookup(point, self.filter, self.wrap);
+        Vec3::new(texel[0], texel[1], texel[2])
+    }
+
+    fn alpha(&self, point: &SurfacePoint) -> FloatTy {
+        self.mipmap.lookup(point, self.filter, self.wrap)[3]
     }
 }
@@ -0,0 +1,50 @@
+use super::{FractalNoise, SurfacePoint, Texture, TextureSpace};
+use crate::{FloatTy, Vec3};
+
+/// Stripes along a direction bent by turbulence, between 0 and 1, to be
+/// colored by a `ColorRamp`.
+#[derive(Debug)]
+pub struct MarbleTexture {
+    noise: FractalNoise,
+    freq: FloatTy,
+    direction: Vec3,
+    distortion: FloatTy,
+    space: TextureSpace,
+}
+
+impl MarbleTexture {
+    pub fn new(freq: FloatTy, noise: FractalNoise) -> Self {
+        MarbleTexture {
+            noise,
+            freq,
+            direction: Vec3::x(),
+            distortion: 5.0,
+            space: TextureSpace::Object,
+        }
+    }
+
+    /// Direction across the stripes, `x` by default.
+    pub fn with_direction(self, direction: Vec3) -> Self {
+        MarbleTexture {
+            direction: direction.normalize(),
+            ..self
+        }
+    }
+
+    /// How far the veins wander from straight stripes.
+    pub fn with_distortion(self, distortion: FloatTy) -> Self {
+        MarbleTexture { distortion, ..self }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        MarbleTexture { space, ..self }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        let p = point.position(self.space) * self.freq;
+        let phase = p.coords.dot(&self.direction) + self.distortion * self.noise.turbulence(p);
+        Vec3::repeat((1.0 + phase.sin()) / 2.0)
+    }
+}
@@ -0,0 +1,459 @@
+use super::SurfacePoint;
+use crate::FloatTy;
+
+/// Longest axis of the footprint ellipse allowed for EWA, in number of its
+/// shortest axis, to bound the number of texels read by a lookup.
+const MAX_ANISOTROPY: FloatTy = 8.0;
+
+/// How an image texture is sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// Closest texel, without any filtering.
+    Nearest,
+    /// Interpolation of the four closest texels of the full resolution image.
+    Bilinear,
+    /// Bilinear lookups in the two levels of the mip pyramid closest to the
+    /// size of the pixel footprint.
+    Trilinear,
+    /// Elliptically weighted average over the footprint of the pixel,
+    /// sharp along the footprint when it is stretched at grazing angles.
+    Ewa,
+}
+
+/// How texels are found outside of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+    /// The image is tiled.
+    Repeat,
+    /// The texels of the border are extended.
+    Clamp,
+    /// The image is tiled, flipping every other tile so that they join
+    /// without seams.
+    Mirror,
+}
+
+fn wrap_coordinate(x: i64, size: usize, wrap: WrapMode) -> usize {
+    let size = size as i64;
+    let x = match wrap {
+        WrapMode::Repeat => x.rem_euclid(size),
+        WrapMode::Clamp => x.max(0).min(size - 1),
+        WrapMode::Mirror => {
+            let x = x.rem_euclid(2 * size);
+            if x >= size {
+                2 * size - 1 - x
+            } else {
+                x
+            }
+        }
+    };
+    x as usize
+}
+
+/// Image of RGBA texels at one resolution.
+#[derive(Debug, Clone)]
+pub struct MipLevel {
+    width: usize,
+    height: usize,
+    texels: Vec<[f32; 4]>,
+}
+
+impl MipLevel {
+    pub fn new(width: usize, height: usize, texels: Vec<[f32; 4]>) -> Self {
+        assert_eq!(texels.len(), width * height);
+        MipLevel {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Texel at integer coordinates, possibly outside of the image.
+    pub fn texel(&self, x: i64, y: i64, wrap: WrapMode) -> [FloatTy; 4] {
+        let x = wrap_coordinate(x, self.width, wrap);
+        let y = wrap_coordinate(y, self.height, wrap);
+        let texel = self.texels[y * self.width + x];
+        [
+            FloatTy::from(texel[0]),
+            FloatTy::from(texel[1]),
+            FloatTy::from(texel[2]),
+            FloatTy::from(texel[3]),
+        ]
+    }
+
+    /// Half resolution image, each texel averaging up to four texels of
+    /// this one.
+    fn downsample(&self) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut texels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.0; 4];
+                let mut count = 0.0;
+                for sy in (2 * y)..(2 * y + 2).min(self.height) {
+                    for sx in (2 * x)..(2 * x + 2).min(self.width) {
+                        let texel = self.texels[sy * self.width + sx];
+                        for c in 0..4 {
+                            sum[c] += texel[c];
+                        }
+                        count += 1.0;
+                    }
+                }
+                texels.push([
+                    sum[0] / count,
+                    sum[1] / count,
+                    sum[2] / count,
+                    sum[3] / count,
+                ]);
+            }
+        }
+        MipLevel::new(width, height, texels)
+    }
+
+    /// Texel value at continuous coordinates, in texels, interpolated from
+    /// the four closest texels.
+    fn bilinear(&self, s: FloatTy, t: FloatTy, wrap: WrapMode) -> [FloatTy; 4] {
+        let s = s - 0.5;
+        let t = t - 0.5;
+        let x = s.floor();
+        let y = t.floor();
+        let fx = s - x;
+        let fy = t - y;
+        let (x, y) = (x as i64, y as i64);
+
+        let mut result = [0.0; 4];
+        let corners = [
+            (x, y, (1.0 - fx) * (1.0 - fy)),
+            (x + 1, y, fx * (1.0 - fy)),
+            (x, y + 1, (1.0 - fx) * fy),
+            (x + 1, y + 1, fx * fy),
+        ];
+        for &(x, y, weight) in &corners {
+            let texel = self.texel(x, y, wrap);
+            for c in 0..4 {
+                result[c] += texel[c] * weight;
+            }
+        }
+        result
+    }
+
+    /// Gaussian weighted average of the texels inside the ellipse whose
+    /// axes are given in texels around coordinates in texels.
+    fn ewa(
+        &self,
+        s: FloatTy,
+        t: FloatTy,
+        axis0: (FloatTy, FloatTy),
+        axis1: (FloatTy, FloatTy),
+        wrap: WrapMode,
+    ) -> [FloatTy; 4] {
+        let s = s - 0.5;
+        let t = t - 0.5;
+
+        // implicit equation of the ellipse, one pixel larger so that it
+        // covers at least a texel
+        let mut a = axis0.1 * axis0.1 + axis1.1 * axis1.1 + 1.0;
+        let mut b = -2.0 * (axis0.0 * axis0.1 + axis1.0 * axis1.1);
+        let mut c = axis0.0 * axis0.0 + axis1.0 * axis1.0 + 1.0;
+        let inverse_f = 1.0 / (a * c - b * b * 0.25);
+        a *= inverse_f;
+        b *= inverse_f;
+        c *= inverse_f;
+
+        let determinant = -b * b + 4.0 * a * c;
+        let inverse_determinant = 1.0 / determinant;
+        let s_extent = 2.0 * inverse_determinant * (determinant * c).sqrt();
+        let t_extent = 2.0 * inverse_determinant * (determinant * a).sqrt();
+        let s0 = (s - s_extent).ceil() as i64;
+        let s1 = (s + s_extent).floor() as i64;
+        let t0 = (t - t_extent).ceil() as i64;
+        let t1 = (t + t_extent).floor() as i64;
+
+        let mut sum = [0.0; 4];
+        let mut total_weight = 0.0;
+        for it in t0..=t1 {
+            let tt = it as FloatTy - t;
+            for is in s0..=s1 {
+                let ss = is as FloatTy - s;
+                let r2 = a * ss * ss + b * ss * tt + c * tt * tt;
+                if r2 < 1.0 {
+                    let weight = (-2.0 * r2).exp() - (-2.0 as FloatTy).exp();
+                    let texel = self.texel(is, it, wrap);
+                    for channel in 0..4 {
+                        sum[channel] += texel[channel] * weight;
+                    }
+                    total_weight += weight;
+                }
+            }
+        }
+
+        if total_weight <= 0.0 {
+            return self.bilinear(s + 0.5, t + 0.5, wrap);
+        }
+        [
+            sum[0] / total_weight,
+            sum[1] / total_weight,
+            sum[2] / total_weight,
+            sum[3] / total_weight,
+        ]
+    }
+}
+
+fn lerp_texels(t: FloatTy, a: [FloatTy; 4], b: [FloatTy; 4]) -> [FloatTy; 4] {
+    [
+        a[0] * (1.0 - t) + b[0] * t,
+        a[1] * (1.0 - t) + b[1] * t,
+        a[2] * (1.0 - t) + b[2] * t,
+        a[3] * (1.0 - t) + b[3] * t,
+    ]
+}
+
+/// Pyramid of images, each one half the resolution of the previous one down
+/// to a single texel, to sample textures over the footprint of a pixel.
+///
+/// The `v` texture coordinate goes up from the last row of the image.
+///
+/// The finest levels can be dropped to save memory, lookups then using the
+/// finest level left.
+#[derive(Debug, Clone)]
+pub struct MipMap {
+    levels: Vec<MipLevel>,
+    /// Number of finest levels dropped.
+    dropped: usize,
+    width: usize,
+    height: usize,
+}
+
+impl MipMap {
+    pub fn new(image: MipLevel) -> Self {
+        let mut levels = vec![image];
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = last.downsample();
+            levels.push(next);
+        }
+        let (width, height) = (levels[0].width, levels[0].height);
+        MipMap {
+            levels,
+            dropped: 0,
+            width,
+            height,
+        }
+    }
+
+    /// Width of the full resolution image, even when it was dropped.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height of the full resolution image, even when it was dropped.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Levels kept in memory, from the finest one.
+    pub fn levels(&self) -> &[MipLevel] {
+        &self.levels
+    }
+
+    /// Number of levels of the full pyramid.
+    pub fn level_count(&self) -> usize {
+        self.dropped + self.levels.len()
+    }
+
+    pub fn dropped_levels(&self) -> usize {
+        self.dropped
+    }
+
+    /// Memory used by the texels of the levels kept.
+    pub fn memory_size(&self) -> usize {
+        self.levels
+            .iter()
+            .map(|level| level.texels.len() * std::mem::size_of::<[f32; 4]>())
+            .sum()
+    }
+
+    /// Copy of the pyramid without its finest level, unless it has a single
+    /// one left.
+    pub fn without_finest_level(&self) -> Option<MipMap> {
+        if self.levels.len() <= 1 {
+            return None;
+        }
+        Some(MipMap {
+            levels: self.levels[1..].to_vec(),
+            dropped: self.dropped + 1,
+            ..*self
+        })
+    }
+
+    /// Level of the pyramid, the finest one kept for dropped levels.
+    fn level(&self, index: usize) -> &MipLevel {
+        &self.levels[index.max(self.dropped) - self.dropped]
+    }
+
+    /// Coordinates in texels of the full resolution image.
+    fn texel_coordinates(&self, u: FloatTy, v: FloatTy) -> (FloatTy, FloatTy) {
+        (
+            u * self.width() as FloatTy,
+            (1.0 - v) * self.height() as FloatTy,
+        )
+    }
+
+    /// Footprint of the pixel along the `x` and `y` axes of the image, in
+    /// texels of the full resolution image.
+    fn footprint(&self, point: &SurfacePoint) -> ((FloatTy, FloatTy), (FloatTy, FloatTy)) {
+        let width = self.width() as FloatTy;
+        let height = self.height() as FloatTy;
+        (
+            (point.dudx * width, -point.dvdx * height),
+            (point.dudy * width, -point.dvdy * height),
+        )
+    }
+
+    /// Level of the pyramid, possibly fractional, whose texels have the
+    /// given size in texels of the full resolution image.
+    fn level_for(&self, size: FloatTy) -> FloatTy {
+        let last = (self.level_count() - 1) as FloatTy;
+        size.max(1e-8).log2().max(self.dropped as FloatTy).min(last)
+    }
+
+    /// Coordinates of a point of the full resolution image in another level.
+    fn scale_to(&self, level: usize, (s, t): (FloatTy, FloatTy)) -> (FloatTy, FloatTy) {
+        let level = self.level(level);
+        (
+            s * level.width as FloatTy / self.width() as FloatTy,
+            t * level.height as FloatTy / self.height() as FloatTy,
+        )
+    }
+
+    fn trilinear(&self, (s, t): (FloatTy, FloatTy), size: FloatTy, wrap: WrapMode) -> [FloatTy; 4] {
+        let level = self.level_for(size);
+        let lower = level.floor() as usize;
+        let (s0, t0) = self.scale_to(lower, (s, t));
+        let fine = self.level(lower).bilinear(s0, t0, wrap);
+        if lower + 1 >= self.level_count() || level == lower as FloatTy {
+            return fine;
+        }
+        let (s1, t1) = self.scale_to(lower + 1, (s, t));
+        let coarse = self.level(lower + 1).bilinear(s1, t1, wrap);
+        lerp_texels(level - lower as FloatTy, fine, coarse)
+    }
+
+    fn ewa(
+        &self,
+        (s, t): (FloatTy, FloatTy),
+        axis0: (FloatTy, FloatTy),
+        axis1: (FloatTy, FloatTy),
+        wrap: WrapMode,
+    ) -> [FloatTy; 4] {
+        let length = |axis: (FloatTy, FloatTy)| (axis.0 * axis.0 + axis.1 * axis.1).sqrt();
+        let (major, mut minor) = if length(axis0) >= length(axis1) {
+            (axis0, axis1)
+        } else {
+            (axis1, axis0)
+        };
+        let major_length = length(major);
+        let mut minor_length = length(minor);
+
+        // very thin ellipses are widened, blurring the texture a bit more
+        // instead of reading a whole row of texels
+        if minor_length * MAX_ANISOTROPY < major_length && minor_length > 0.0 {
+            let scale = major_length / (minor_length * MAX_ANISOTROPY);
+            minor = (minor.0 * scale, minor.1 * scale);
+            minor_length *= scale;
+        }
+        if minor_length == 0.0 {
+            return self.trilinear((s, t), major_length, wrap);
+        }
+
+        let level = self.level_for(minor_length);
+        let lower = level.floor() as usize;
+        if lower + 1 >= self.level_count() {
+            // the footprint covers the whole texture
+            return self.level(lower).texel(0, 0, wrap);
+        }
+        let at_level = |index: usize| {
+            let (ls, lt) = self.scale_to(index, (s, t));
+            let (sx, sy) = self.scale_to(index, (1.0, 1.0));
+            self.level(index).ewa(
+                ls,
+                lt,
+                (major.0 * sx, major.1 * sy),
+                (minor.0 * sx, minor.1 * sy),
+                wrap,
+            )
+        };
+        let fine = at_level(lower);
+        if level == lower as FloatTy {
+            return fine;
+        }
+        lerp_texels(level - lower as FloatTy, fine, at_level(lower + 1))
+    }
+
+    /// RGBA value of the texture over the footprint of the pixel at a point,
+    /// falling back to bilinear filtering when the footprint is unknown.
+    pub fn lookup(
+        &self,
+        point: &SurfacePoint,
+        filter: TextureFilter,
+        wrap: WrapMode,
+    ) -> [FloatTy; 4] {
+        let (s, t) = self.texel_coordinates(point.u, point.v);
+        if filter == TextureFilter::Nearest
+            || filter == TextureFilter::Bilinear
+            || !point.has_differentials()
+        {
+            let (ls, lt) = self.scale_to(self.dropped, (s, t));
+            let level = self.level(self.dropped);
+            if filter == TextureFilter::Nearest {
+                return level.texel(ls.floor() as i64, lt.floor() as i64, wrap);
+            }
+            return level.bilinear(ls, lt, wrap);
+        }
+
+        let (axis0, axis1) = self.footprint(point);
+        match filter {
+            TextureFilter::Ewa => self.ewa((s, t), axis0, axis1, wrap),
+            _ => {
+                // the footprint is taken as a square as large as its
+                // longest side
+                let size = (axis0.0 * axis0.0 + axis0.1 * axis0.1)
+                    .max(axis1.0 * axis1.0 + axis1.1 * axis1.1)
+                    .sqrt();
+                self.trilinear((s, t), size, wrap)
+            }
+        }
+    }
+
+    /// Finest level a lookup would read if every level was kept.
+    pub fn finest_level_needed(&self, point: &SurfacePoint, filter: TextureFilter) -> usize {
+        if filter == TextureFilter::Nearest
+            || filter == TextureFilter::Bilinear
+            || !point.has_differentials()
+        {
+            return 0;
+        }
+
+        let (axis0, axis1) = self.footprint(point);
+        let length0 = (axis0.0 * axis0.0 + axis0.1 * axis0.1).sqrt();
+        let length1 = (axis1.0 * axis1.0 + axis1.1 * axis1.1).sqrt();
+        let (major, minor) = (length0.max(length1), length0.min(length1));
+        let size = match filter {
+            TextureFilter::Ewa if minor > 0.0 => minor.max(major / MAX_ANISOTROPY),
+            _ => major,
+        };
+        let last = (self.level_count() - 1) as FloatTy;
+        size.max(1e-8).log2().max(0.0).min(last) as usize
+    }
+}
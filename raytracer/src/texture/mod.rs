@@ -1,22 +1,111 @@
 use std::sync::Arc;
 
-use crate::{FloatTy, Vec3};
+use crate::{FloatTy, Pt3, Vec3};
 
+mod cache;
 mod checker;
+mod fractal;
 mod image;
+mod marble;
+mod mipmap;
 mod perlin;
+mod ramp;
 mod solid;
+mod udim;
+mod uv;
+mod voronoi;
+mod wood;
 pub use self::image::*;
+pub use cache::*;
 pub use checker::*;
+pub use fractal::*;
+pub use marble::*;
+pub use mipmap::*;
 pub use perlin::*;
+pub use ramp::*;
 pub use solid::*;
+pub use udim::*;
+pub use uv::*;
+pub use voronoi::*;
+pub use wood::*;
+
+/// Point of a surface where a texture is looked up.
+#[derive(Debug, Clone, Copy)]
+pub struct SurfacePoint {
+    pub u: FloatTy,
+    pub v: FloatTy,
+    /// Hit point in the space of the object, before its transforms.
+    pub local_p: Pt3,
+    /// Hit point in world space.
+    pub p: Pt3,
+    pub normal: Vec3,
+    /// Change of the texture coordinates from one pixel to the next along
+    /// the `x` and `y` axes of the image, zero when unknown.
+    pub dudx: FloatTy,
+    pub dvdx: FloatTy,
+    pub dudy: FloatTy,
+    pub dvdy: FloatTy,
+}
+
+impl SurfacePoint {
+    /// Point known only by its texture coordinates, for lookups outside of
+    /// any surface.
+    pub fn from_uv(u: FloatTy, v: FloatTy) -> Self {
+        SurfacePoint {
+            u,
+            v,
+            local_p: Pt3::origin(),
+            p: Pt3::origin(),
+            normal: Vec3::zeros(),
+            dudx: 0.0,
+            dvdx: 0.0,
+            dudy: 0.0,
+            dvdy: 0.0,
+        }
+    }
+
+    /// Whether the footprint of the pixel on the surface is known.
+    pub fn has_differentials(&self) -> bool {
+        self.dudx != 0.0 || self.dvdx != 0.0 || self.dudy != 0.0 || self.dvdy != 0.0
+    }
+
+    pub fn position(&self, space: TextureSpace) -> Pt3 {
+        match space {
+            TextureSpace::Uv => Pt3::new(self.u, self.v, 0.0),
+            TextureSpace::Object => self.local_p,
+            TextureSpace::World => self.p,
+        }
+    }
+}
+
+/// Coordinates a procedural texture is evaluated at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureSpace {
+    /// Texture coordinates of the surface, as the `x` and `y` of a point.
+    Uv,
+    /// Hit point in the space of the object, so that the texture moves with
+    /// it.
+    Object,
+    /// Hit point in world space, so that objects look carved out of the
+    /// same block.
+    World,
+}
 
 pub trait Texture: Send + Sync + std::fmt::Debug {
-    fn value(&self, u: FloatTy, v: FloatTy) -> Vec3;
+    fn value(&self, point: &SurfacePoint) -> Vec3;
+
+    /// Opacity of the texture, from 0 (fully transparent) to 1 (opaque).
+    fn alpha(&self, _point: &SurfacePoint) -> FloatTy {
+        1.0
+    }
 }
 
 impl<T: Texture> Texture for Arc<T> {
-    fn value(&self, u: FloatTy, v: FloatTy) -> Vec3 {
-        self.as_ref().value(u, v)
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        self.as_ref().value(point)
+    }
+
+    fn alpha(&self, point: &SurfacePoint) -> FloatTy {
+        self.as_ref().alpha(point)
     }
 }
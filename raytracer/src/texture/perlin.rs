@@ -1,30 +1,53 @@
-use super::Texture;
+use super::{FractalNoise, SurfacePoint, Texture, TextureSpace};
 use crate::{FloatTy, Vec3};
 
-use noise::NoiseFn;
-use noise::Perlin;
-
+/// Perlin noise, evaluated by default in the space of the object so that it
+/// has no seams where the texture coordinates wrap.
 #[derive(Debug)]
 pub struct PerlinTexture {
-    inner: Perlin,
+    noise: FractalNoise,
     freq: FloatTy,
+    space: TextureSpace,
+    turbulent: bool,
 }
 
 impl PerlinTexture {
     pub fn new(freq: FloatTy) -> Self {
         PerlinTexture {
-            inner: Perlin::new(),
+            noise: FractalNoise::default(),
             freq,
+            space: TextureSpace::Object,
+            turbulent: false,
+        }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        PerlinTexture { space, ..self }
+    }
+
+    /// Seed and octaves of the noise, a single octave seeded with 0 by
+    /// default.
+    pub fn with_noise(self, noise: FractalNoise) -> Self {
+        PerlinTexture { noise, ..self }
+    }
+
+    /// Uses the turbulence of the noise instead of its fBm.
+    pub fn turbulent(self) -> Self {
+        PerlinTexture {
+            turbulent: true,
+            ..self
         }
     }
 }
 
 impl Texture for PerlinTexture {
-    fn value(&self, u: FloatTy, v: FloatTy) -> Vec3 {
-        let coeff = self
-            .inner
-            .get([self.freq * u as FloatTy, self.freq * v as FloatTy]);
-        let coeff = (coeff + 1.0) / 2.0;
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        let p = point.position(self.space) * self.freq;
+        let coeff = if self.turbulent {
+            self.noise.turbulence(p)
+        } else {
+            (self.noise.fbm(p) + 1.0) / 2.0
+        };
         Vec3::repeat(1.0) * coeff
     }
 }
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use super::{SurfacePoint, Texture};
+use crate::{FloatTy, Vec3};
+
+/// Maps the first channel of another texture to a gradient of colors, for
+/// instance to color marble veins or wood rings.
+#[derive(Debug)]
+pub struct ColorRamp {
+    input: Arc<dyn Texture>,
+    stops: Vec<(FloatTy, Vec3)>,
+}
+
+impl ColorRamp {
+    /// The stops are the colors taken at given values of the input, colors
+    /// in between being interpolated linearly and the ones outside clamped
+    /// to the first and last stops.
+    pub fn new(input: Arc<dyn Texture>, stops: Vec<(FloatTy, Vec3)>) -> Self {
+        assert!(!stops.is_empty(), "a color ramp needs at least one stop");
+        let mut stops = stops;
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        ColorRamp { input, stops }
+    }
+
+    pub fn color_at(&self, t: FloatTy) -> Vec3 {
+        let after = self.stops.iter().position(|stop| stop.0 > t);
+        match after {
+            Some(0) => self.stops[0].1,
+            Some(i) => {
+                let (t0, c0) = self.stops[i - 1];
+                let (t1, c1) = self.stops[i];
+                c0.lerp(&c1, (t - t0) / (t1 - t0))
+            }
+            None => self.stops[self.stops.len() - 1].1,
+        }
+    }
+}
+
+impl Texture for ColorRamp {
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        self.color_at(self.input.value(point).x)
+    }
+
+    fn alpha(&self, point: &SurfacePoint) -> FloatTy {
+        self.input.alpha(point)
+    }
+}
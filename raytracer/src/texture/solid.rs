@@ -1,5 +1,5 @@
-use super::Texture;
-use crate::{FloatTy, Vec3};
+use super::{SurfacePoint, Texture};
+use crate::Vec3;
 
 #[derive(Debug)]
 pub struct SolidTexture {
@@ -13,7 +13,7 @@ impl SolidTexture {
 }
 
 impl Texture for SolidTexture {
-    fn value(&self, _: FloatTy, _: FloatTy) -> Vec3 {
+    fn value(&self, _: &SurfacePoint) -> Vec3 {
         self.color
     }
 }
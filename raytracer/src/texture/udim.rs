@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{ImageTexture, SurfacePoint, Texture, TextureError, TextureFilter, WrapMode};
+use crate::{ColorSpace, FloatTy, Vec3};
+
+/// Marker replaced by the tile number in the paths of UDIM textures.
+pub const UDIM_MARKER: &str = "<UDIM>";
+
+/// Texture split into tiles covering the unit squares of texture space, as
+/// painted for meshes whose UVs go outside of 0..1. Tile 1001 covers
+/// `0 <= u, v < 1`, and the number goes up by one for each unit of `u` up
+/// to 10, then by 10 for each unit of `v`.
+#[derive(Debug)]
+pub struct UdimTexture {
+    tiles: HashMap<(i64, i64), ImageTexture>,
+}
+
+impl UdimTexture {
+    /// Opens every tile matching a path such as `albedo.<UDIM>.png`, with
+    /// the color space chosen as in `ImageTexture::open`.
+    pub fn open(pattern: &str) -> Result<Self, TextureError> {
+        UdimTexture::open_tiles(pattern, |path| ImageTexture::open(path))
+    }
+
+    pub fn open_with_color_space(
+        pattern: &str,
+        color_space: ColorSpace,
+    ) -> Result<Self, TextureError> {
+        UdimTexture::open_tiles(pattern, |path| {
+            ImageTexture::open_with_color_space(path, color_space)
+        })
+    }
+
+    fn open_tiles<F>(pattern: &str, open: F) -> Result<Self, TextureError>
+    where
+        F: Fn(&Path) -> Result<ImageTexture, TextureError>,
+    {
+        let pattern_path = Path::new(pattern);
+        let file_pattern = pattern_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        let marker = file_pattern.find(UDIM_MARKER).ok_or_else(|| {
+            TextureError::Parse(format!("`{}` does not contain {}", pattern, UDIM_MARKER))
+        })?;
+        let prefix = &file_pattern[..marker];
+        let suffix = &file_pattern[marker + UDIM_MARKER.len()..];
+        let directory = match pattern_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut tiles = HashMap::new();
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+            if name.len() != prefix.len() + 4 + suffix.len()
+                || !name.starts_with(prefix)
+                || !name.ends_with(suffix)
+            {
+                continue;
+            }
+            let tile = match name[prefix.len()..prefix.len() + 4].parse::<i64>() {
+                Ok(tile) if tile >= 1001 => tile - 1001,
+                _ => continue,
+            };
+            // tiles are filtered up to their borders without bleeding into
+            // their neighbours
+            let texture = open(&path)?.with_wrap(WrapMode::Clamp);
+            tiles.insert((tile % 10, tile / 10), texture);
+        }
+
+        if tiles.is_empty() {
+            return Err(TextureError::Parse(format!(
+                "no tile found for `{}`",
+                pattern
+            )));
+        }
+        Ok(UdimTexture { tiles })
+    }
+
+    pub fn with_filter(self, filter: TextureFilter) -> Self {
+        let tiles = self
+            .tiles
+            .into_iter()
+            .map(|(key, tile)| (key, tile.with_filter(filter)))
+            .collect();
+        UdimTexture { tiles }
+    }
+
+    /// Tile containing a point and the coordinates of the point in it.
+    fn tile(&self, point: &SurfacePoint) -> Option<(&ImageTexture, SurfacePoint)> {
+        let u_tile = point.u.floor();
+        let v_tile = point.v.floor();
+        let tile = self.tiles.get(&(u_tile as i64, v_tile as i64))?;
+        Some((
+            tile,
+            SurfacePoint {
+                u: point.u - u_tile,
+                v: point.v - v_tile,
+                ..*point
+            },
+        ))
+    }
+}
+
+impl Texture for UdimTexture {
+    /// Black where there is no tile.
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        self.tile(point)
+            .map(|(tile, local)| tile.value(&local))
+            .unwrap_or_else(Vec3::zeros)
+    }
+
+    /// Transparent where there is no tile.
+    fn alpha(&self, point: &SurfacePoint) -> FloatTy {
+        self.tile(point)
+            .map(|(tile, local)| tile.alpha(&local))
+            .unwrap_or(0.0)
+    }
+}
@@ -0,0 +1,104 @@
+use super::{SurfacePoint, Texture};
+use crate::{FloatTy, Vec3};
+
+/// Change of the texture coordinates before a lookup: they are scaled, then
+/// rotated around the origin and finally offset.
+#[derive(Debug, Clone, Copy)]
+pub struct UvTransform {
+    pub offset: (FloatTy, FloatTy),
+    pub scale: (FloatTy, FloatTy),
+    /// Counterclockwise rotation in degrees.
+    pub rotation: FloatTy,
+}
+
+impl UvTransform {
+    pub fn identity() -> Self {
+        UvTransform {
+            offset: (0.0, 0.0),
+            scale: (1.0, 1.0),
+            rotation: 0.0,
+        }
+    }
+
+    pub fn with_offset(self, u: FloatTy, v: FloatTy) -> Self {
+        UvTransform {
+            offset: (u, v),
+            ..self
+        }
+    }
+
+    pub fn with_scale(self, u: FloatTy, v: FloatTy) -> Self {
+        UvTransform {
+            scale: (u, v),
+            ..self
+        }
+    }
+
+    pub fn with_rotation(self, degrees: FloatTy) -> Self {
+        UvTransform {
+            rotation: degrees,
+            ..self
+        }
+    }
+
+    /// Linear part of the transform, without the offset.
+    fn linear(&self, u: FloatTy, v: FloatTy) -> (FloatTy, FloatTy) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let u = u * self.scale.0;
+        let v = v * self.scale.1;
+        (u * cos - v * sin, u * sin + v * cos)
+    }
+
+    /// The point with transformed texture coordinates and footprint.
+    pub fn apply(&self, point: &SurfacePoint) -> SurfacePoint {
+        let (u, v) = self.linear(point.u, point.v);
+        let (dudx, dvdx) = self.linear(point.dudx, point.dvdx);
+        let (dudy, dvdy) = self.linear(point.dudy, point.dvdy);
+        SurfacePoint {
+            u: u + self.offset.0,
+            v: v + self.offset.1,
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+            ..*point
+        }
+    }
+}
+
+impl Default for UvTransform {
+    fn default() -> Self {
+        UvTransform::identity()
+    }
+}
+
+/// Texture looked up with transformed texture coordinates.
+#[derive(Debug)]
+pub struct UvTransformed<T: Texture> {
+    inner: T,
+    transform: UvTransform,
+}
+
+impl<T: Texture> UvTransformed<T> {
+    pub fn new(inner: T, transform: UvTransform) -> Self {
+        UvTransformed { inner, transform }
+    }
+}
+
+impl<T: Texture> Texture for UvTransformed<T> {
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        self.inner.value(&self.transform.apply(point))
+    }
+
+    fn alpha(&self, point: &SurfacePoint) -> FloatTy {
+        self.inner.alpha(&self.transform.apply(point))
+    }
+}
+
+pub trait TextureExt: Texture + Sized {
+    fn transform_uv(self, transform: UvTransform) -> UvTransformed<Self> {
+        UvTransformed::new(self, transform)
+    }
+}
+
+impl<T: Sized + Texture> TextureExt for T {}
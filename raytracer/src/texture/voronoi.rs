@@ -0,0 +1,111 @@
+use super::{SurfacePoint, Texture, TextureSpace};
+use crate::{FloatTy, Pt3, Vec3};
+
+/// Value computed from the cells of a `VoronoiTexture`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoronoiFeature {
+    /// Distance to the closest feature point.
+    F1,
+    /// Distance to the second closest feature point.
+    F2,
+    /// Difference between the two, zero on the edges between cells.
+    Edge,
+    /// Random value of the closest cell, constant over each cell.
+    Cell,
+}
+
+/// Hash of a cell and a seed, the finalizer of SplitMix64.
+fn hash(cell: [i64; 3], seed: u32, salt: u64) -> u64 {
+    let mut h = u64::from(seed) ^ salt.wrapping_mul(0xd6e8_feb8_6659_fd93);
+    for &c in &cell {
+        h = (h ^ c as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+    }
+    h
+}
+
+fn hash_unit(cell: [i64; 3], seed: u32, salt: u64) -> FloatTy {
+    (hash(cell, seed, salt) >> 11) as FloatTy / (1u64 << 53) as FloatTy
+}
+
+/// Worley noise: one random feature point per cell of a grid, the texture
+/// being a function of the distances to the closest ones.
+#[derive(Debug)]
+pub struct VoronoiTexture {
+    seed: u32,
+    freq: FloatTy,
+    feature: VoronoiFeature,
+    jitter: FloatTy,
+    space: TextureSpace,
+}
+
+impl VoronoiTexture {
+    pub fn new(freq: FloatTy, seed: u32) -> Self {
+        VoronoiTexture {
+            seed,
+            freq,
+            feature: VoronoiFeature::F1,
+            jitter: 1.0,
+            space: TextureSpace::Object,
+        }
+    }
+
+    pub fn with_feature(self, feature: VoronoiFeature) -> Self {
+        VoronoiTexture { feature, ..self }
+    }
+
+    /// How far the feature points move from the center of their cell, from
+    /// 0 for a regular grid to 1.
+    pub fn with_jitter(self, jitter: FloatTy) -> Self {
+        VoronoiTexture { jitter, ..self }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        VoronoiTexture { space, ..self }
+    }
+
+    fn feature_point(&self, cell: [i64; 3]) -> Pt3 {
+        let offset = |salt| 0.5 + (hash_unit(cell, self.seed, salt) - 0.5) * self.jitter;
+        Pt3::new(
+            cell[0] as FloatTy + offset(1),
+            cell[1] as FloatTy + offset(2),
+            cell[2] as FloatTy + offset(3),
+        )
+    }
+}
+
+impl Texture for VoronoiTexture {
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        let p = point.position(self.space) * self.freq;
+        let base = [p.x.floor() as i64, p.y.floor() as i64, p.z.floor() as i64];
+
+        let mut f1 = FloatTy::INFINITY;
+        let mut f2 = FloatTy::INFINITY;
+        let mut closest = base;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let cell = [base[0] + dx, base[1] + dy, base[2] + dz];
+                    let distance = (self.feature_point(cell) - p).norm();
+                    if distance < f1 {
+                        f2 = f1;
+                        f1 = distance;
+                        closest = cell;
+                    } else if distance < f2 {
+                        f2 = distance;
+                    }
+                }
+            }
+        }
+
+        let value = match self.feature {
+            VoronoiFeature::F1 => f1,
+            VoronoiFeature::F2 => f2,
+            VoronoiFeature::Edge => f2 - f1,
+            VoronoiFeature::Cell => hash_unit(closest, self.seed, 0),
+        };
+        Vec3::repeat(value)
+    }
+}
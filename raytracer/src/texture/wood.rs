@@ -0,0 +1,45 @@
+use super::{FractalNoise, SurfacePoint, Texture, TextureSpace};
+use crate::{FloatTy, Vec3};
+
+/// Growth rings around the `y` axis, going from 0 to 1 across each ring,
+/// to be colored by a `ColorRamp`.
+#[derive(Debug)]
+pub struct WoodTexture {
+    noise: FractalNoise,
+    rings: FloatTy,
+    freq: FloatTy,
+    distortion: FloatTy,
+    space: TextureSpace,
+}
+
+impl WoodTexture {
+    /// `rings` is the number of rings per unit of distance to the axis and
+    /// `freq` the frequency of the noise bending them.
+    pub fn new(rings: FloatTy, freq: FloatTy, noise: FractalNoise) -> Self {
+        WoodTexture {
+            noise,
+            rings,
+            freq,
+            distortion: 0.3,
+            space: TextureSpace::Object,
+        }
+    }
+
+    /// How much the rings are bent, as a fraction of their width.
+    pub fn with_distortion(self, distortion: FloatTy) -> Self {
+        WoodTexture { distortion, ..self }
+    }
+
+    pub fn with_space(self, space: TextureSpace) -> Self {
+        WoodTexture { space, ..self }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, point: &SurfacePoint) -> Vec3 {
+        let p = point.position(self.space);
+        let radius = (p.x * p.x + p.z * p.z).sqrt();
+        let ring = radius * self.rings + self.distortion * self.noise.fbm(p * self.freq);
+        Vec3::repeat(ring - ring.floor())
+    }
+}
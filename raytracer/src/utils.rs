@@ -1,4 +1,5 @@
-use crate::FloatTy;
+use crate::fconsts;
+use crate::{FloatTy, Vec3};
 
 #[inline]
 pub(crate) fn is_in_range(t: FloatTy, tmin: FloatTy, tmax: Option<FloatTy>) -> bool {
@@ -41,3 +42,49 @@ pub fn clamp(v: FloatTy, min: FloatTy, max: FloatTy) -> FloatTy {
         v
     }
 }
+
+/// Orthonormal basis around a shading normal, used to move directions in and
+/// out of the local frame where the normal is +Z.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Frame {
+    pub s: Vec3,
+    pub t: Vec3,
+    pub n: Vec3,
+}
+
+impl Frame {
+    pub fn from_normal(n: Vec3) -> Self {
+        // Duff et al. 2017, "Building an Orthonormal Basis, Revisited"
+        let sign = if n.z >= 0.0 { 1.0 } else { -1.0 };
+        let a = -1.0 / (sign + n.z);
+        let b = n.x * n.y * a;
+        let s = Vec3::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x);
+        let t = Vec3::new(b, sign + n.y * n.y * a, -n.y);
+        Frame { s, t, n }
+    }
+
+    /// Frame around `n` whose first axis follows the projection of `tangent`,
+    /// the second one being on the side of `bitangent`. Falls back to an
+    /// arbitrary frame when there is no usable tangent.
+    pub fn from_tangents(n: Vec3, tangent: Vec3, bitangent: Vec3) -> Self {
+        let tangent = tangent - n * n.dot(&tangent);
+        if tangent.norm_squared() <= fconsts::EPSILON {
+            return Frame::from_normal(n);
+        }
+
+        let s = tangent.normalize();
+        let t = n.cross(&s);
+        let t = if t.dot(&bitangent) < 0.0 { -t } else { t };
+        Frame { s, t, n }
+    }
+
+    #[inline]
+    pub fn to_local(self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.s), v.dot(&self.t), v.dot(&self.n))
+    }
+
+    #[inline]
+    pub fn to_world(self, v: Vec3) -> Vec3 {
+        self.s * v.x + self.t * v.y + self.n * v.z
+    }
+}
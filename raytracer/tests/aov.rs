@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use rand::prelude::*;
+
+use raytracer::background::Background;
+use raytracer::hittable::XYRect;
+use raytracer::light::PointLight;
+use raytracer::material::{Lambertian, Light};
+use raytracer::{compute_aovs, Aovs, FloatTy, Hittable, HittableExt, LightSource, Pt3, Ray, Vec3};
+
+const SAMPLE_COUNT: usize = 1000;
+
+type Scene = (Vec<Box<dyn Hittable>>, Vec<Box<dyn LightSource>>);
+
+/// A floor and a ceiling lit by a point light between them, with a small
+/// emitter facing the ceiling.
+fn scene() -> Scene {
+    let gray = Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.5)));
+    let objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(XYRect::new(-1.0, 1.0, -1.0, 1.0, 0.0, gray.clone()).with_id(1)),
+        Box::new(XYRect::new(-1.0, 1.0, -1.0, 1.0, 3.0, gray).with_id(2)),
+        Box::new(
+            XYRect::new(
+                0.6,
+                0.8,
+                -0.8,
+                -0.6,
+                1.5,
+                Arc::new(Light::new(Vec3::repeat(4.0))),
+            )
+            .with_id(3),
+        ),
+    ];
+    let lights: Vec<Box<dyn LightSource>> = vec![Box::new(PointLight::new(
+        Pt3::new(0.0, 0.0, 2.0),
+        Vec3::repeat(2.0),
+    ))];
+    (objects, lights)
+}
+
+fn trace(ray: Ray, rng: &mut StdRng) -> Aovs {
+    let (objects, lights) = scene();
+    let background: Arc<dyn Background> = Arc::new(Vec3::new(0.1, 0.2, 0.3));
+    compute_aovs(&objects, &lights, ray, &*background, rng)
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).amax() < 1e-9, "{} != {}", a, b);
+}
+
+#[test]
+fn first_hit_passes_describe_the_geometry() {
+    let mut rng = StdRng::seed_from_u64(1);
+    // the direction is not normalized, the depth being a distance
+    let aovs = trace(
+        Ray::new(Pt3::new(0.2, 0.4, 1.0), Vec3::new(0.0, 0.0, -2.0)),
+        &mut rng,
+    );
+    assert!((aovs.depth - 1.0).abs() < 1e-9);
+    assert_close(aovs.position, Vec3::new(0.2, 0.4, 0.0));
+    assert_close(aovs.normal, Vec3::z());
+    assert!((aovs.uv[0] - 0.6).abs() < 1e-9 && (aovs.uv[1] - 0.7).abs() < 1e-9);
+    assert_eq!(aovs.object_id, 1);
+    assert_close(aovs.albedo, Vec3::repeat(0.5));
+
+    // the ceiling is seen from below
+    let aovs = trace(Ray::new(Pt3::new(-0.5, 0.0, 1.0), Vec3::z()), &mut rng);
+    assert!((aovs.depth - 2.0).abs() < 1e-9);
+    assert_close(aovs.normal, -Vec3::z());
+    assert_eq!(aovs.object_id, 2);
+
+    let miss = trace(Ray::new(Pt3::new(2.0, 0.0, 1.0), Vec3::x()), &mut rng);
+    assert_eq!(miss.depth, FloatTy::INFINITY);
+    assert_eq!(miss.object_id, 0);
+    assert_eq!(miss.material_id, 0);
+    assert_eq!(miss.normal, Vec3::zeros());
+    assert_close(miss.color, Vec3::new(0.1, 0.2, 0.3));
+    assert_close(miss.emission, miss.color);
+}
+
+#[test]
+fn lighting_passes_sum_to_the_image() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let rays = [
+        Ray::new(Pt3::new(0.2, 0.4, 1.0), -Vec3::z()),
+        Ray::new(Pt3::new(0.7, -0.7, 2.5), -Vec3::z()),
+    ];
+    let mut totals = Vec::new();
+    for &ray in &rays {
+        let mut total = [Vec3::zeros(); 3];
+        for _ in 0..SAMPLE_COUNT {
+            let aovs = trace(ray, &mut rng);
+            assert_close(aovs.emission + aovs.direct + aovs.indirect, aovs.color);
+            total[0] += aovs.emission;
+            total[1] += aovs.direct;
+            total[2] += aovs.indirect;
+        }
+        totals.push(total);
+    }
+
+    // the floor is lit by the point light and by the ceiling
+    assert_eq!(totals[0][0], Vec3::zeros());
+    assert!(totals[0][1].min() > 0.0);
+    assert!(totals[0][2].min() > 0.0);
+    // while the emitter only emits
+    assert_close(totals[1][0] / SAMPLE_COUNT as FloatTy, Vec3::repeat(4.0));
+    assert_eq!(totals[1][1] + totals[1][2], Vec3::zeros());
+}
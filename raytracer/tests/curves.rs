@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use raytracer::hittable::{
+    parse_polylines, parse_strands, Curve, CurveType, Hittable, StrandError,
+};
+use raytracer::material::{Lambertian, Material};
+use raytracer::{FloatTy, Pt3, Ray, Vec3};
+
+const VERTICES: &str = "v 0 0 0\nv 1 0 0\nv 2 0 0\nv 3 0 0\n";
+
+fn material() -> Arc<dyn Material> {
+    Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.5)))
+}
+
+fn polylines(content: &str) -> Result<Vec<Box<dyn Hittable>>, StrandError> {
+    parse_polylines(content, 0.0, 0.0, CurveType::Flat, material())
+}
+
+fn parse_error(result: Result<Vec<Box<dyn Hittable>>, StrandError>) -> String {
+    match result {
+        Ok(_) => panic!("invalid file was parsed"),
+        Err(StrandError::Parse(message)) => message,
+        Err(err) => panic!("unexpected error: {}", err),
+    }
+}
+
+/// Range along x spanned by a curve, whose width is zero.
+fn x_range(curve: &dyn Hittable) -> (FloatTy, FloatTy) {
+    let bounds = curve.bounding_box().unwrap();
+    (bounds.min.x, bounds.max.x)
+}
+
+#[test]
+fn tangent_follows_the_texture_coordinate() {
+    let control_points = [
+        Pt3::new(0.0, 0.0, 0.0),
+        Pt3::new(1.0, 0.0, 0.0),
+        Pt3::new(2.0, 0.0, 0.0),
+        Pt3::new(3.0, 0.0, 0.0),
+    ];
+    let curve =
+        Curve::new(control_points, 0.2, 0.2, CurveType::Flat, material()).with_u_range(0.25, 0.5);
+    let ray = Ray::new(Pt3::new(1.5, 0.0, 5.0), -Vec3::z());
+    let record = curve.is_hit_by(ray, 0.01, None).unwrap();
+
+    assert!((record.u - 0.375).abs() < 1e-6);
+    // 3 units of length over a quarter of the texture
+    assert!((record.tangent - Vec3::new(12.0, 0.0, 0.0)).norm() < 1e-6);
+    assert!((record.bitangent.norm() - 0.2).abs() < 1e-6);
+}
+
+#[test]
+#[should_panic(expected = "cannot be empty")]
+fn empty_u_range_is_rejected() {
+    let control_points = [Pt3::origin(); 4];
+    Curve::new(control_points, 0.1, 0.1, CurveType::Flat, material()).with_u_range(0.5, 0.5);
+}
+
+#[test]
+fn strands_are_split_in_segments() {
+    let content = "# two strands\n\n0 0 0 0.1 1 0 0 0.1 2 0 0 0.05\n0 1 0 0.1 0 2 0 0.1\n";
+    let curves = parse_strands(content, CurveType::Round, material()).unwrap();
+    assert_eq!(curves.len(), 3);
+    assert_eq!(x_range(&*curves[1]), (1.0 - 0.05, 2.0 + 0.05));
+}
+
+#[test]
+fn malformed_strands_are_rejected() {
+    let strands = |content| parse_strands(content, CurveType::Flat, material());
+    assert!(parse_error(strands("0 0 0 0.1 1 0 0")).contains("line 1"));
+    assert!(parse_error(strands("\n0 0 0 0.1")).contains("line 2"));
+    assert!(parse_error(strands("0 0 0 0.1 1 0 zero 0.1")).contains("`zero`"));
+}
+
+#[test]
+fn polyline_indices_are_resolved() {
+    let curves = polylines(&format!("{}l 1 3\n", VERTICES)).unwrap();
+    assert_eq!(curves.len(), 1);
+    assert_eq!(x_range(&*curves[0]), (0.0, 2.0));
+
+    // texture coordinates referenced by the elements are ignored
+    let curves = polylines(&format!("{}l 2/1 4/2\n", VERTICES)).unwrap();
+    assert_eq!(x_range(&*curves[0]), (1.0, 3.0));
+
+    // negative indices count back from the last vertex read so far
+    let curves = polylines(&format!("{}l -1 -4\nv 9 0 0\nl 1 -1\n", VERTICES)).unwrap();
+    assert_eq!(curves.len(), 2);
+    assert_eq!(x_range(&*curves[0]), (0.0, 3.0));
+    assert_eq!(x_range(&*curves[1]), (0.0, 9.0));
+}
+
+#[test]
+fn out_of_range_polyline_indices_are_rejected() {
+    for line in &["l 1 0", "l 1 5", "l 1 -5", "l 1 two"] {
+        let message = parse_error(polylines(&format!("{}{}\n", VERTICES, line)));
+        let index = line.split_whitespace().last().unwrap();
+        assert!(message.contains(index), "{}: {}", line, message);
+    }
+    // vertices are only known once read
+    assert!(parse_error(polylines("v 0 0 0\nl 1 2\nv 1 0 0\n")).contains("out of range"));
+}
+
+#[test]
+fn malformed_vertices_are_rejected() {
+    assert!(parse_error(polylines("v 0 0\n")).contains("3 coordinates"));
+    assert!(parse_error(polylines("v 0 0 x\n")).contains("`x`"));
+}
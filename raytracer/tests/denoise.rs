@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use rand::prelude::*;
+
+use raytracer::background::Background;
+use raytracer::hittable;
+use raytracer::scenes;
+use raytracer::{Camera, Denoiser, FloatTy, Hittable, Pt3, Vec3};
+
+const SIZE: usize = 40;
+const NOISY_SAMPLES: usize = 8;
+const REFERENCE_SAMPLES: usize = 512;
+
+struct Render {
+    color: Vec<Vec3>,
+    albedo: Vec<Vec3>,
+    normal: Vec<Vec3>,
+}
+
+fn render(objects: &[Box<dyn Hittable>], sample_count: usize, seed: u64) -> Render {
+    let camera = Camera::new(
+        Pt3::new(278.0, 278.0, -800.0),
+        Pt3::new(278.0, 278.0, 0.0),
+        Vec3::y(),
+        40.0,
+        1.0,
+    );
+    let background: Arc<dyn Background> = Arc::new(Vec3::zeros());
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut image = Render {
+        color: vec![Vec3::zeros(); SIZE * SIZE],
+        albedo: vec![Vec3::zeros(); SIZE * SIZE],
+        normal: vec![Vec3::zeros(); SIZE * SIZE],
+    };
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let index = y * SIZE + x;
+            for _ in 0..sample_count {
+                let u = (x as FloatTy + rng.gen::<FloatTy>()) / SIZE as FloatTy;
+                let v = ((SIZE - y - 1) as FloatTy + rng.gen::<FloatTy>()) / SIZE as FloatTy;
+                let aovs = raytracer::compute_aovs(
+                    objects,
+                    &[],
+                    camera.get_ray(u, v),
+                    &*background,
+                    &mut rng,
+                );
+                image.color[index] += aovs.color;
+                image.albedo[index] += aovs.albedo;
+                image.normal[index] += aovs.normal;
+            }
+            image.color[index] /= sample_count as FloatTy;
+            image.albedo[index] /= sample_count as FloatTy;
+            image.normal[index] /= sample_count as FloatTy;
+        }
+    }
+    image
+}
+
+/// Squared error relative to the reference, so that the bright light does
+/// not outweigh the rest of the image.
+fn relative_error(image: &[Vec3], reference: &[Vec3]) -> FloatTy {
+    let total: FloatTy = image
+        .iter()
+        .zip(reference)
+        .map(|(a, b)| {
+            let d = a - b;
+            (d.component_mul(&d))
+                .component_div(&(b.component_mul(b) + Vec3::repeat(0.01)))
+                .mean()
+        })
+        .sum();
+    total / image.len() as FloatTy
+}
+
+fn denoise(image: &Render) -> Vec<Vec3> {
+    Denoiser::new().denoise(SIZE, SIZE, &image.color, &image.albedo, &image.normal)
+}
+
+/// Both checks share the renders, the reference taking most of the time.
+#[test]
+fn denoising_reduces_noise_and_keeps_converged_image() {
+    let objects = hittable::build_bvh(scenes::cornell_box());
+    let reference = render(&objects, REFERENCE_SAMPLES, 1);
+    let noisy = render(&objects, NOISY_SAMPLES, 2);
+    let noisy_error = relative_error(&noisy.color, &reference.color);
+
+    let denoised_error = relative_error(&denoise(&noisy), &reference.color);
+    assert!(
+        denoised_error < noisy_error * 0.5,
+        "noisy {} denoised {}",
+        noisy_error,
+        denoised_error
+    );
+
+    // the filter must not blur the edges of a clean image more than the
+    // noise it removes from a noisy one
+    let blur_error = relative_error(&denoise(&reference), &reference.color);
+    assert!(
+        blur_error < noisy_error * 0.1,
+        "noisy {} blurred {}",
+        noisy_error,
+        blur_error
+    );
+}
@@ -0,0 +1,72 @@
+use raytracer::{DisplayPipeline, ToneMapper, Vec3, NEUTRAL_TEMPERATURE};
+
+const TONE_MAPPERS: [ToneMapper; 4] = [
+    ToneMapper::Clamp,
+    ToneMapper::Reinhard,
+    ToneMapper::Aces,
+    ToneMapper::Agx,
+];
+
+#[test]
+fn black_stays_black() {
+    for &tone_mapper in &TONE_MAPPERS {
+        assert_eq!(
+            tone_mapper.apply(Vec3::zeros()),
+            Vec3::zeros(),
+            "{:?}",
+            tone_mapper
+        );
+    }
+}
+
+#[test]
+fn bright_colors_stay_in_the_display_range() {
+    let colors = [
+        Vec3::repeat(1.0),
+        Vec3::repeat(1e3),
+        Vec3::repeat(1e9),
+        Vec3::new(1e6, 0.0, 0.0),
+        Vec3::new(50.0, 20.0, 1e4),
+        Vec3::new(-5.0, 1e3, 0.1),
+    ];
+    for &tone_mapper in &TONE_MAPPERS {
+        for color in colors.iter() {
+            let mapped = tone_mapper.apply(*color);
+            assert!(
+                mapped.min() >= 0.0 && mapped.max() <= 1.0,
+                "{:?} maps {} to {}",
+                tone_mapper,
+                color,
+                mapped
+            );
+        }
+    }
+}
+
+#[test]
+fn one_stop_doubles_linear_values() {
+    let color = Vec3::new(0.05, 0.2, 0.4);
+    let pipeline = DisplayPipeline::new();
+    assert_eq!(
+        pipeline.with_exposure(1.0).apply(color),
+        2.0 * pipeline.apply(color)
+    );
+    assert_eq!(
+        pipeline.with_exposure(-2.0).apply(color),
+        0.25 * pipeline.apply(color)
+    );
+}
+
+#[test]
+fn neutral_temperature_leaves_colors_unchanged() {
+    let pipeline = DisplayPipeline::new().with_white_balance(NEUTRAL_TEMPERATURE);
+    assert!(
+        (pipeline.white_balance - Vec3::repeat(1.0)).amax() < 1e-9,
+        "{}",
+        pipeline.white_balance
+    );
+
+    // a warm light is compensated by making the image bluer
+    let warm = DisplayPipeline::new().with_white_balance(3000.0);
+    assert!(warm.white_balance.z > warm.white_balance.x);
+}
@@ -0,0 +1,266 @@
+use std::sync::Arc;
+
+use rand::prelude::*;
+
+use raytracer::fconsts;
+use raytracer::hittable::HitRecord;
+use raytracer::material::{
+    Coated, Conductor, Hair, Lambertian, Material, PrincipledBuilder, RoughDielectric,
+};
+use raytracer::texture::SolidTexture;
+use raytracer::{FloatTy, Pt3, Ray, Texture, Vec3};
+
+const SAMPLE_COUNT: usize = 100_000;
+const HAIR_SAMPLE_COUNT: usize = 60_000;
+
+/// Averages the throughput of many scattering events off a surface lit by a
+/// uniform white environment: the result is the albedo of the material for
+/// the given incident angle.
+fn furnace_albedo(material: Arc<dyn Material>, cos_theta: FloatTy, front_face: bool) -> Vec3 {
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+    let direction = Vec3::new(sin_theta, 0.0, -cos_theta);
+    let outward_normal = if front_face { Vec3::z() } else { -Vec3::z() };
+    let ray = Ray::new(Pt3::new(-sin_theta, 0.0, cos_theta), direction);
+    let record = HitRecord::new(
+        ray,
+        1.0,
+        Pt3::origin(),
+        outward_normal,
+        0.0,
+        0.0,
+        material.clone(),
+    );
+
+    let mut total = Vec3::zeros();
+    for _ in 0..SAMPLE_COUNT {
+        if let Some(scatter) = material.scatter(&ray, &record) {
+            if scatter.scattered.is_some() {
+                total += scatter.attenuation;
+            }
+        }
+    }
+    total / SAMPLE_COUNT as FloatTy
+}
+
+fn perfect_mirror(roughness: FloatTy) -> Arc<dyn Material> {
+    // a huge extinction coefficient makes the Fresnel term one everywhere
+    Arc::new(Conductor::new(
+        Vec3::repeat(1.0),
+        Vec3::repeat(1e4),
+        roughness,
+    ))
+}
+
+#[test]
+fn conductor_never_creates_energy() {
+    for &roughness in &[0.0, 0.2, 0.5, 0.8, 1.0] {
+        for &cos_theta in &[1.0, 0.7, 0.3, 0.1] {
+            let albedo = furnace_albedo(perfect_mirror(roughness), cos_theta, true);
+            assert!(
+                albedo.max() <= 1.0 + 1e-6,
+                "roughness {} cos {}: {}",
+                roughness,
+                cos_theta,
+                albedo
+            );
+        }
+    }
+}
+
+#[test]
+fn conductor_loses_little_energy_when_smooth() {
+    for &roughness in &[0.0, 0.2, 0.4] {
+        for &cos_theta in &[1.0, 0.7, 0.3] {
+            let albedo = furnace_albedo(perfect_mirror(roughness), cos_theta, true);
+            assert!(
+                albedo.min() > 0.85,
+                "roughness {} cos {}: {}",
+                roughness,
+                cos_theta,
+                albedo
+            );
+        }
+    }
+}
+
+#[test]
+fn conductor_presets_are_below_white() {
+    let presets = [
+        Conductor::gold(0.3),
+        Conductor::copper(0.3),
+        Conductor::aluminium(0.3),
+    ];
+    for preset in presets.iter() {
+        let albedo = furnace_albedo(Arc::new(preset.clone()), 0.8, true);
+        assert!(
+            albedo.max() < 1.0 && albedo.min() > 0.3,
+            "{:?}: {}",
+            preset,
+            albedo
+        );
+    }
+}
+
+#[test]
+fn rough_dielectric_conserves_energy() {
+    for &roughness in &[0.0, 0.2, 0.5, 1.0] {
+        for &cos_theta in &[1.0, 0.7, 0.3] {
+            for &front_face in &[true, false] {
+                let material = Arc::new(RoughDielectric::new(1.5, roughness));
+                let albedo = furnace_albedo(material, cos_theta, front_face);
+                assert!(
+                    albedo.max() <= 1.0 + 1e-6,
+                    "roughness {} cos {} front {}: {}",
+                    roughness,
+                    cos_theta,
+                    front_face,
+                    albedo
+                );
+                if roughness <= 0.5 {
+                    assert!(
+                        albedo.min() > 0.75,
+                        "roughness {} cos {} front {}: {}",
+                        roughness,
+                        cos_theta,
+                        front_face,
+                        albedo
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn principled_stays_close_to_energy_conservation() {
+    let constant =
+        |v: FloatTy| -> Arc<dyn Texture> { Arc::new(SolidTexture::new(Vec3::repeat(v))) };
+
+    for &metallic in &[0.0, 1.0] {
+        for &roughness in &[0.1, 0.5, 1.0] {
+            for &clearcoat in &[0.0, 1.0] {
+                for &transmission in &[0.0, 1.0] {
+                    let material: Arc<dyn Material> = Arc::new(
+                        PrincipledBuilder::new(constant(1.0))
+                            .with_metallic(constant(metallic))
+                            .with_roughness(constant(roughness))
+                            .with_clearcoat(constant(clearcoat))
+                            .with_transmission(constant(transmission))
+                            .with_sheen(constant(0.5))
+                            .build(),
+                    );
+                    for &cos_theta in &[1.0, 0.5, 0.2] {
+                        let albedo = furnace_albedo(material.clone(), cos_theta, true);
+                        // the Burley diffuse term is known to slightly exceed one
+                        assert!(
+                            albedo.max() <= 1.05,
+                            "metallic {} roughness {} clearcoat {} transmission {} cos {}: {}",
+                            metallic,
+                            roughness,
+                            clearcoat,
+                            transmission,
+                            cos_theta,
+                            albedo
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn uniform_sphere<R: Rng>(rng: &mut R) -> Vec3 {
+    let z = 1.0 - 2.0 * rng.gen::<FloatTy>();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * fconsts::PI * rng.gen::<FloatTy>();
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Integrates the BSDF of a fiber along X over the sphere of directions, for
+/// random outgoing directions and offsets across the fiber. Returns the
+/// estimate with its standard error.
+fn hair_reflectance<R: Rng>(hair: Arc<dyn Material>, rng: &mut R) -> (FloatTy, FloatTy) {
+    let (mut sum, mut sum_squared) = (0.0, 0.0);
+    for _ in 0..HAIR_SAMPLE_COUNT {
+        let h: FloatTy = rng.gen_range(-1.0, 1.0);
+        let wo = uniform_sphere(rng);
+        let wi = uniform_sphere(rng);
+        let ray = Ray::new(Pt3::from(wo), -wo);
+        let record = HitRecord::new(
+            ray,
+            1.0,
+            Pt3::origin(),
+            Vec3::z(),
+            0.0,
+            (h + 1.0) / 2.0,
+            hair.clone(),
+        )
+        .with_tangents(Vec3::x(), Vec3::y());
+
+        let value = hair.eval(&ray, &record, wi).mean() * 4.0 * fconsts::PI;
+        sum += value;
+        sum_squared += value * value;
+    }
+
+    let count = HAIR_SAMPLE_COUNT as FloatTy;
+    let mean = sum / count;
+    let variance = (sum_squared / count - mean * mean).max(0.0);
+    (mean, (variance / count).sqrt())
+}
+
+#[test]
+fn hair_without_absorption_is_white() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for &longitudinal in &[0.2, 0.5, 0.8] {
+        for &azimuthal in &[0.2, 0.5, 0.8] {
+            let hair = Hair::new(Vec3::zeros(), longitudinal, azimuthal).with_scale_angle(0.0);
+            let (reflectance, error) = hair_reflectance(Arc::new(hair), &mut rng);
+            assert!(
+                (reflectance - 1.0).abs() < 0.02 + 4.0 * error,
+                "beta_m {} beta_n {}: {} ± {}",
+                longitudinal,
+                azimuthal,
+                reflectance,
+                error
+            );
+        }
+    }
+}
+
+fn white_under_clear_coat(roughness: FloatTy) -> Arc<dyn Material> {
+    let base = Arc::new(Lambertian::from_solid_color(Vec3::repeat(1.0)));
+    Arc::new(Coated::new(base, 1.5).with_roughness(roughness))
+}
+
+#[test]
+fn coated_never_creates_energy() {
+    for &roughness in &[0.0, 0.1, 0.3, 0.6, 1.0] {
+        for &cos_theta in &[1.0, 0.7, 0.3, 0.1] {
+            let albedo = furnace_albedo(white_under_clear_coat(roughness), cos_theta, true);
+            assert!(
+                albedo.max() <= 1.0 + 1e-6,
+                "roughness {} cos {}: {}",
+                roughness,
+                cos_theta,
+                albedo
+            );
+        }
+    }
+}
+
+#[test]
+fn coated_loses_little_energy_when_smooth() {
+    // only the light reflected back under the coat is lost
+    for &roughness in &[0.0, 0.1] {
+        for &cos_theta in &[1.0, 0.7, 0.3, 0.1] {
+            let albedo = furnace_albedo(white_under_clear_coat(roughness), cos_theta, true);
+            assert!(
+                albedo.min() > 0.88,
+                "roughness {} cos {}: {}",
+                roughness,
+                cos_theta,
+                albedo
+            );
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use raytracer::light::{IesError, IesProfile};
+use raytracer::{FloatTy, Mat44, Vec3};
+
+const DOWNLIGHT: &str = include_str!("../../raytracer_cli/src/downlight.ies");
+
+/// Header of a type C file with a single horizontal angle, followed by its
+/// table.
+fn profile(tilt: &str, vertical_angles: &str, candela: &str) -> String {
+    let count = vertical_angles.split_whitespace().count();
+    format!(
+        "IESNA:LM-63-2002\n{}\n1 1000 1 {} 1 1 2 0.1 0.1 0.0\n1.0 1.0 10\n{}\n0\n{}\n",
+        tilt, count, vertical_angles, candela
+    )
+}
+
+fn parse_error(content: &str) -> String {
+    match IesProfile::parse(content) {
+        Ok(_) => panic!("invalid file was parsed"),
+        Err(IesError::Parse(message)) => message,
+        Err(err) => panic!("unexpected error: {}", err),
+    }
+}
+
+fn direction_from_down(degrees: FloatTy) -> Vec3 {
+    let angle = degrees.to_radians();
+    Vec3::new(angle.sin(), -angle.cos(), 0.0)
+}
+
+#[test]
+fn bundled_downlight_is_parsed() {
+    let profile = IesProfile::parse(DOWNLIGHT).unwrap();
+    assert_eq!(profile.max_candela(), 810.0);
+    assert!((profile.candela(-Vec3::y()) - 620.0).abs() < 1e-9);
+    assert!((profile.candela(direction_from_down(25.0)) - 810.0).abs() < 1e-9);
+    // halfway between the values at 40° and 45°
+    assert!((profile.candela(direction_from_down(42.5)) - 535.0).abs() < 1e-9);
+    assert_eq!(profile.candela(Vec3::y()), 0.0);
+    assert!((profile.intensity(-Vec3::y()) - 620.0 / 810.0).abs() < 1e-9);
+}
+
+#[test]
+fn single_horizontal_angle_is_rotationally_symmetric() {
+    let profile = IesProfile::parse(DOWNLIGHT).unwrap();
+    let reference = profile.candela(direction_from_down(30.0));
+    for &turn in &[45.0, 90.0, 200.0, 330.0] {
+        let rotation = Mat44::from_axis_angle(&Vec3::y_axis(), (turn as FloatTy).to_radians());
+        let direction = rotation.transform_vector(&direction_from_down(30.0));
+        assert!((profile.candela(direction) - reference).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn orientation_turns_the_profile() {
+    let rotation = Mat44::from_axis_angle(&Vec3::x_axis(), (90.0 as FloatTy).to_radians());
+    let profile = IesProfile::parse(DOWNLIGHT)
+        .unwrap()
+        .with_orientation(rotation)
+        .unwrap();
+    // the luminaire now points toward -Z
+    assert!((profile.candela(-Vec3::z()) - 620.0).abs() < 1e-9);
+    assert_eq!(profile.candela(-Vec3::y()), 0.0);
+}
+
+#[test]
+fn orientation_ignores_scale() {
+    let rotation = Mat44::from_axis_angle(&Vec3::x_axis(), (90.0 as FloatTy).to_radians());
+    let scale = Mat44::new_nonuniform_scaling(&Vec3::new(3.0, 0.5, 2.0));
+    let rotated = IesProfile::parse(DOWNLIGHT)
+        .unwrap()
+        .with_orientation(rotation)
+        .unwrap();
+    let scaled = IesProfile::parse(DOWNLIGHT)
+        .unwrap()
+        .with_orientation(Mat44::new_translation(&Vec3::repeat(5.0)) * rotation * scale)
+        .unwrap();
+    assert!((scaled.candela(-Vec3::z()) - 620.0).abs() < 1e-9);
+    for degrees in &[10.0, 25.0, 42.5, 60.0, 100.0] {
+        let angle = (*degrees as FloatTy).to_radians();
+        let direction = Vec3::new(angle.sin(), 0.0, -angle.cos());
+        let expected = rotated.candela(direction);
+        assert!((scaled.candela(direction) - expected).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn singular_orientation_is_rejected() {
+    let profile = IesProfile::parse(DOWNLIGHT).unwrap();
+    assert!(profile.clone().with_orientation(Mat44::zeros()).is_none());
+    let flat = Mat44::new_nonuniform_scaling(&Vec3::new(1.0, 0.0, 1.0));
+    assert!(profile.with_orientation(flat).is_none());
+}
+
+#[test]
+fn included_tilt_data_is_skipped() {
+    let content = profile("TILT=INCLUDE\n1\n3\n0 45 90\n1 0.9 0.8", "0 90", "100 50");
+    let profile = IesProfile::parse(&content).unwrap();
+    assert_eq!(profile.max_candela(), 100.0);
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    let valid = profile("TILT=NONE", "0 90", "100 50");
+    assert_eq!(
+        parse_error("IESNA:LM-63-2002\n1 1000 1 2 1 1 2 0.1 0.1 0.0\n"),
+        "missing TILT line"
+    );
+    assert_eq!(
+        parse_error(&profile("TILT=lamp.tlt", "0 90", "100 50")),
+        "external tilt files are not supported"
+    );
+    assert_eq!(
+        parse_error("IESNA:LM-63-2002\nTILT=INCLUDE\n1\n3\n0 45 90\n1"),
+        "unexpected end of file"
+    );
+    assert_eq!(
+        parse_error("IESNA:LM-63-2002\nTILT=NONE\n1 1000 1 2 1"),
+        "unexpected end of file"
+    );
+    assert_eq!(
+        parse_error(&profile("TILT=NONE", "0 90", "100 fifty")),
+        "invalid number `fifty`"
+    );
+    assert_eq!(
+        parse_error(&profile("TILT=NONE", "90 0", "100 50")),
+        "angles are not increasing"
+    );
+    assert_eq!(
+        parse_error(&valid.replacen(" 2 1 1 2", " 2 1 3 2", 1)),
+        "unsupported photometric type 3"
+    );
+    assert_eq!(
+        parse_error(&valid.replacen(" 2 1 1 2", " 2.5 1 1 2", 1)),
+        "invalid count `2.5`"
+    );
+}
@@ -0,0 +1,111 @@
+use raytracer::texture::{MipLevel, MipMap, SurfacePoint, TextureFilter, WrapMode};
+use raytracer::FloatTy;
+
+const FILTERS: [TextureFilter; 4] = [
+    TextureFilter::Nearest,
+    TextureFilter::Bilinear,
+    TextureFilter::Trilinear,
+    TextureFilter::Ewa,
+];
+
+fn constant(width: usize, height: usize, value: f32) -> MipMap {
+    MipMap::new(MipLevel::new(
+        width,
+        height,
+        vec![[value, value, value, 1.0]; width * height],
+    ))
+}
+
+/// Checker of single texels, black and white.
+fn checker(size: usize) -> MipMap {
+    let texels = (0..size * size)
+        .map(|i| {
+            let value = ((i % size + i / size) % 2) as f32;
+            [value, value, value, 1.0]
+        })
+        .collect();
+    MipMap::new(MipLevel::new(size, size, texels))
+}
+
+fn point(u: FloatTy, v: FloatTy, dx: (FloatTy, FloatTy), dy: (FloatTy, FloatTy)) -> SurfacePoint {
+    SurfacePoint {
+        dudx: dx.0,
+        dvdx: dx.1,
+        dudy: dy.0,
+        dvdy: dy.1,
+        ..SurfacePoint::from_uv(u, v)
+    }
+}
+
+#[test]
+fn constant_images_are_constant_under_every_filter() {
+    let footprints = [
+        ((0.0, 0.0), (0.0, 0.0)),
+        ((0.01, 0.0), (0.0, 0.01)),
+        ((0.3, 0.1), (-0.01, 0.02)),
+        ((0.0, 0.5), (0.0, 0.0)),
+        ((4.0, 0.0), (0.0, 4.0)),
+    ];
+    for &(width, height) in &[(8, 8), (5, 3), (1, 1)] {
+        let mipmap = constant(width, height, 0.25);
+        for &filter in &FILTERS {
+            for &wrap in &[WrapMode::Repeat, WrapMode::Clamp, WrapMode::Mirror] {
+                for &(u, v) in &[(0.0, 0.0), (0.5, 0.5), (0.99, 0.01), (1.3, -0.2)] {
+                    for &(dx, dy) in &footprints {
+                        let value = mipmap.lookup(&point(u, v, dx, dy), filter, wrap);
+                        for &channel in &value[..3] {
+                            assert!(
+                                (channel - 0.25).abs() < 1e-9,
+                                "{}x{} {:?} {:?} at ({}, {}) {:?} {:?}: {:?}",
+                                width,
+                                height,
+                                filter,
+                                wrap,
+                                u,
+                                v,
+                                dx,
+                                dy,
+                                value
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn large_footprints_read_coarse_levels() {
+    let mipmap = checker(16);
+    assert_eq!(mipmap.level_count(), 5);
+
+    // four texels, then the whole texture, per pixel
+    for &(size, level) in &[(0.25, 2), (1.0, 4)] {
+        let point = point(0.4, 0.3, (size, 0.0), (0.0, size));
+        for &filter in &[TextureFilter::Trilinear, TextureFilter::Ewa] {
+            assert_eq!(mipmap.finest_level_needed(&point, filter), level);
+            let value = mipmap.lookup(&point, filter, WrapMode::Repeat);
+            assert!(
+                (value[0] - 0.5).abs() < 1e-9,
+                "{:?} over {}: {:?}",
+                filter,
+                size,
+                value
+            );
+        }
+    }
+
+    // while the unfiltered lookups see the checker
+    let point = point(0.5 / 16.0, 0.5 / 16.0, (1.0, 0.0), (0.0, 1.0));
+    for &filter in &[TextureFilter::Nearest, TextureFilter::Bilinear] {
+        assert_eq!(mipmap.finest_level_needed(&point, filter), 0);
+        let value = mipmap.lookup(&point, filter, WrapMode::Repeat);
+        assert!(
+            value[0] == 0.0 || value[0] == 1.0,
+            "{:?}: {:?}",
+            filter,
+            value
+        );
+    }
+}
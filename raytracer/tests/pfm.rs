@@ -0,0 +1,67 @@
+use raytracer::texture::{parse_pfm, TextureError};
+
+fn pfm(header: &str, values: &[f32], little_endian: bool) -> Vec<u8> {
+    let mut content = header.as_bytes().to_vec();
+    for value in values {
+        if little_endian {
+            content.extend_from_slice(&value.to_le_bytes());
+        } else {
+            content.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+    content
+}
+
+fn parse_error(content: &[u8]) -> String {
+    match parse_pfm(content) {
+        Ok(_) => panic!("invalid file was parsed"),
+        Err(TextureError::Parse(message)) => message,
+        Err(err) => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn color_rows_go_from_the_bottom() {
+    let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let image = parse_pfm(&pfm("PF\n1 2\n-1.0\n", &values, true)).unwrap();
+    assert_eq!((image.width, image.height), (1, 2));
+    assert!(image.is_float);
+    assert_eq!(
+        image.texels,
+        vec![[4.0, 5.0, 6.0, 1.0], [1.0, 2.0, 3.0, 1.0]]
+    );
+}
+
+#[test]
+fn grayscale_big_endian_is_parsed() {
+    let image = parse_pfm(&pfm("Pf 2 1 1.0\n", &[0.25, -8.0], false)).unwrap();
+    assert_eq!(
+        image.texels,
+        vec![[0.25, 0.25, 0.25, 1.0], [-8.0, -8.0, -8.0, 1.0]]
+    );
+}
+
+#[test]
+fn malformed_headers_are_rejected() {
+    assert!(parse_error(b"P6\n1 1\n-1.0\n").contains("magic number `P6`"));
+    assert!(parse_error(b"PF\n1 1\n").contains("truncated PFM header"));
+    assert!(parse_error(b"PF\n1 -1\n-1.0\n").contains("invalid PFM size `-1`"));
+    assert!(parse_error(b"PF\n1 1\nscale\n").contains("invalid PFM scale"));
+    assert!(parse_error(b"PF\n0 4\n-1.0\n").contains("empty"));
+    assert!(parse_error(b"Pf\n4 0\n-1.0\n").contains("empty"));
+}
+
+#[test]
+fn sizes_overflowing_the_data_length_are_rejected() {
+    let huge = format!("PF\n{} {}\n-1.0\n", usize::MAX / 2, 3);
+    assert!(parse_error(huge.as_bytes()).contains("too large"));
+    let huge = format!("PF\n{} {}\n-1.0\n", usize::MAX / 12 + 1, 1);
+    assert!(parse_error(huge.as_bytes()).contains("too large"));
+}
+
+#[test]
+fn truncated_data_is_rejected() {
+    let content = pfm("PF\n2 2\n-1.0\n", &[0.5; 11], true);
+    assert!(parse_error(&content).contains("truncated PFM data"));
+    assert!(parse_error(b"Pf\n1 1\n-1.0").contains("truncated PFM data"));
+}
@@ -0,0 +1,65 @@
+use raytracer::texture::{FractalNoise, MarbleTexture, SurfacePoint, VoronoiTexture, WoodTexture};
+use raytracer::{FloatTy, Pt3, Texture, Vec3};
+
+/// Points off the lattice of the noise, where it is not zero.
+fn points() -> Vec<Pt3> {
+    (0..64)
+        .map(|i| {
+            let i = i as FloatTy;
+            Pt3::new(0.37 * i + 0.11, 0.53 * i - 0.29, 0.71 * i + 0.43)
+        })
+        .collect()
+}
+
+fn values(texture: &dyn Texture) -> Vec<Vec3> {
+    points()
+        .into_iter()
+        .map(|p| {
+            let point = SurfacePoint {
+                local_p: p,
+                p,
+                ..SurfacePoint::from_uv(p.x, p.y)
+            };
+            texture.value(&point)
+        })
+        .collect()
+}
+
+fn noise(seed: u32) -> FractalNoise {
+    FractalNoise::new(seed).with_octaves(4)
+}
+
+fn textures(seed: u32) -> Vec<Box<dyn Texture>> {
+    vec![
+        Box::new(VoronoiTexture::new(2.0, seed)),
+        Box::new(MarbleTexture::new(2.0, noise(seed))),
+        Box::new(WoodTexture::new(8.0, 2.0, noise(seed))),
+    ]
+}
+
+#[test]
+fn fractal_noise_follows_its_seed() {
+    let fbm = |seed| -> Vec<FloatTy> { points().into_iter().map(|p| noise(seed).fbm(p)).collect() };
+    let turbulence = |seed| -> Vec<FloatTy> {
+        points()
+            .into_iter()
+            .map(|p| noise(seed).turbulence(p))
+            .collect()
+    };
+    assert_eq!(fbm(7), fbm(7));
+    assert_ne!(fbm(7), fbm(8));
+    assert_eq!(turbulence(7), turbulence(7));
+    assert_ne!(turbulence(7), turbulence(8));
+}
+
+#[test]
+fn procedural_textures_follow_their_seed() {
+    for ((a, b), c) in textures(7)
+        .iter()
+        .zip(textures(7).iter())
+        .zip(textures(8).iter())
+    {
+        assert_eq!(values(&**a), values(&**b), "{:?}", a);
+        assert_ne!(values(&**a), values(&**c), "{:?}", a);
+    }
+}
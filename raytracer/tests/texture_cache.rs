@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use raytracer::texture::{SurfacePoint, TextureCache, TextureError, TextureFilter};
+use raytracer::{ColorSpace, Texture, Vec3};
+
+const SIZE: usize = 16;
+
+/// Directory of a test, emptied first.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("texture_cache_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes a grayscale PFM image of a single value.
+fn write_image(dir: &Path, name: &str, value: f32) -> PathBuf {
+    let mut content = format!("Pf\n{} {}\n-1.0\n", SIZE, SIZE).into_bytes();
+    for _ in 0..SIZE * SIZE {
+        content.extend_from_slice(&value.to_le_bytes());
+    }
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// Value of a texture read at full resolution.
+fn lookup(cache: &TextureCache, path: &Path) -> Vec3 {
+    cache
+        .texture(path, ColorSpace::Linear)
+        .unwrap()
+        .with_filter(TextureFilter::Nearest)
+        .value(&SurfacePoint::from_uv(0.5, 0.5))
+}
+
+/// Memory used by one of the images written, with all its levels.
+fn full_size(path: &Path) -> usize {
+    let cache = TextureCache::new();
+    lookup(&cache, path);
+    cache.memory_size()
+}
+
+/// Looks up an image long enough for the others to stop being recent.
+fn keep_using(cache: &TextureCache, path: &Path) {
+    let texture = cache.texture(path, ColorSpace::Linear).unwrap();
+    for _ in 0..100_000 {
+        texture.value(&SurfacePoint::from_uv(0.5, 0.5));
+    }
+}
+
+#[test]
+fn images_are_shared_by_path_and_color_space() {
+    let dir = test_dir("sharing");
+    let path = write_image(&dir, "a.pfm", 0.25);
+    let cache = TextureCache::new();
+
+    let image = cache.image(&path, ColorSpace::Linear).unwrap();
+    let relative = dir.join(".").join("a.pfm");
+    assert!(Arc::ptr_eq(
+        &image,
+        &cache.image(&relative, ColorSpace::Linear).unwrap()
+    ));
+    assert!(Arc::ptr_eq(
+        &image,
+        &cache.clone().image(&path, ColorSpace::Linear).unwrap()
+    ));
+    let srgb = cache.image(&path, ColorSpace::Srgb).unwrap();
+    assert!(!Arc::ptr_eq(&image, &srgb));
+
+    lookup(&cache, &relative);
+    assert!(image.memory_size() > 0);
+    assert_eq!(srgb.memory_size(), 0);
+    assert_eq!(cache.memory_size(), image.memory_size());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn images_are_decoded_on_first_lookup() {
+    let dir = test_dir("lazy");
+    let path = write_image(&dir, "a.pfm", 0.25);
+    let cache = TextureCache::new();
+
+    let image = cache.image(&path, ColorSpace::Linear).unwrap();
+    assert_eq!(cache.memory_size(), 0);
+    assert_eq!(lookup(&cache, &path), Vec3::repeat(0.25));
+    assert_eq!(cache.memory_size(), image.memory_size());
+    assert!(image.memory_size() > 0);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unreadable_images_are_reported_when_requested() {
+    let dir = test_dir("errors");
+    let cache = TextureCache::new();
+    match cache.image(dir.join("missing.pfm"), ColorSpace::Linear) {
+        Err(TextureError::Io(_)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let path = dir.join("corrupt.pfm");
+    fs::write(&path, b"P6\n4 4\n255\n").unwrap();
+    match cache.image(&path, ColorSpace::Linear) {
+        Err(TextureError::Parse(_)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // only the header is read before the first lookup, which then finds
+    // nothing to decode
+    let path = dir.join("truncated.pfm");
+    fs::write(&path, b"PF\n4 4\n-1.0\n").unwrap();
+    cache.image(&path, ColorSpace::Linear).unwrap();
+    assert_eq!(lookup(&cache, &path), Vec3::zeros());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unused_images_are_evicted_and_reloaded() {
+    let dir = test_dir("eviction");
+    let paths: Vec<PathBuf> = [0.25, 0.75, 0.5, 1.0]
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| write_image(&dir, &format!("{}.pfm", i), value))
+        .collect();
+    let full = full_size(&paths[0]);
+    let cache = TextureCache::bounded(full * 3 / 2);
+    let images: Vec<_> = paths
+        .iter()
+        .map(|path| cache.image(path, ColorSpace::Linear).unwrap())
+        .collect();
+
+    // loading an image drops the levels of the ones not used for long
+    lookup(&cache, &paths[0]);
+    keep_using(&cache, &paths[1]);
+    lookup(&cache, &paths[2]);
+    assert!(images[0].memory_size() < full);
+    assert_eq!(images[1].memory_size(), full);
+    assert_eq!(images[2].memory_size(), full);
+
+    // and a lookup at full resolution loads them again
+    assert_eq!(lookup(&cache, &paths[0]), Vec3::repeat(0.25));
+    assert_eq!(images[0].memory_size(), full);
+
+    // until the file is readable again, the levels left are used
+    fs::write(&paths[1], b"PF\n").unwrap();
+    keep_using(&cache, &paths[0]);
+    lookup(&cache, &paths[3]);
+    assert!(images[1].memory_size() < full);
+    assert_eq!(lookup(&cache, &paths[1]), Vec3::repeat(0.75));
+    assert!(images[1].memory_size() < full);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn recently_used_images_are_not_evicted() {
+    let dir = test_dir("hysteresis");
+    let a_path = write_image(&dir, "a.pfm", 0.25);
+    let b_path = write_image(&dir, "b.pfm", 0.75);
+    let full = full_size(&a_path);
+    let cache = TextureCache::bounded(full * 3 / 2);
+
+    // going over the budget instead of evicting each other
+    for _ in 0..10 {
+        assert_eq!(lookup(&cache, &a_path), Vec3::repeat(0.25));
+        assert_eq!(lookup(&cache, &b_path), Vec3::repeat(0.75));
+        assert_eq!(cache.memory_size(), 2 * full);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
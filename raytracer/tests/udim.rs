@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use raytracer::texture::{SurfacePoint, TextureFilter, UdimTexture};
+use raytracer::{ColorSpace, FloatTy, Texture, Vec3};
+
+/// Directory of tiles valued by their number, emptied first.
+fn tile_dir(name: &str, tiles: &[u32]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("udim_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for &tile in tiles {
+        let mut content = b"Pf\n2 2\n-1.0\n".to_vec();
+        for _ in 0..4 {
+            content.extend_from_slice(&(tile as f32).to_le_bytes());
+        }
+        fs::write(dir.join(format!("albedo.{}.pfm", tile)), content).unwrap();
+    }
+    dir
+}
+
+fn open(dir: &Path) -> UdimTexture {
+    let pattern = dir.join("albedo.<UDIM>.pfm");
+    UdimTexture::open_with_color_space(pattern.to_str().unwrap(), ColorSpace::Linear)
+        .unwrap()
+        .with_filter(TextureFilter::Nearest)
+}
+
+fn tile_at(texture: &UdimTexture, u: FloatTy, v: FloatTy) -> FloatTy {
+    texture.value(&SurfacePoint::from_uv(u, v)).x
+}
+
+#[test]
+fn tiles_are_numbered_along_u_then_v() {
+    let dir = tile_dir("numbers", &[1001, 1002, 1010, 1011, 1023]);
+    let texture = open(&dir);
+    let cases = [
+        (0.5, 0.5, 1001.0),
+        (1.5, 0.5, 1002.0),
+        (9.5, 0.5, 1010.0),
+        (0.5, 1.5, 1011.0),
+        (2.5, 2.5, 1023.0),
+    ];
+    for &(u, v, tile) in &cases {
+        assert_eq!(tile_at(&texture, u, v), tile, "({}, {})", u, v);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn tile_boundaries_belong_to_the_next_tile() {
+    let dir = tile_dir("boundaries", &[1001, 1002, 1011]);
+    let texture = open(&dir);
+    assert_eq!(tile_at(&texture, 0.0, 0.0), 1001.0);
+    assert_eq!(tile_at(&texture, 1.0, 0.5), 1002.0);
+    assert_eq!(tile_at(&texture, 0.5, 1.0), 1011.0);
+    assert_eq!(tile_at(&texture, 1.0 - 1e-9, 1.0 - 1e-9), 1001.0);
+
+    // where there is no tile, the texture is black and transparent
+    for &(u, v) in &[(1.0, 1.0), (-0.5, 0.5), (0.5, -1e-9), (10.0, 0.5)] {
+        let point = SurfacePoint::from_uv(u, v);
+        assert_eq!(texture.value(&point), Vec3::zeros(), "({}, {})", u, v);
+        assert_eq!(texture.alpha(&point), 0.0);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
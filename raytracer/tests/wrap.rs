@@ -0,0 +1,56 @@
+use raytracer::texture::{
+    DecodedImage, ImageTexture, MipLevel, SurfacePoint, TextureFilter, WrapMode,
+};
+use raytracer::{ColorSpace, FloatTy, Texture};
+
+/// Row of four texels, valued by their index.
+fn row() -> Vec<[f32; 4]> {
+    (0..4).map(|x| [x as f32, 0.0, 0.0, 1.0]).collect()
+}
+
+fn texture(wrap: WrapMode) -> ImageTexture {
+    let image = DecodedImage {
+        width: 4,
+        height: 1,
+        texels: row(),
+        is_float: true,
+    };
+    ImageTexture::from_decoded(image, ColorSpace::Linear)
+        .with_filter(TextureFilter::Nearest)
+        .with_wrap(wrap)
+}
+
+#[test]
+fn texels_outside_of_the_image_are_wrapped() {
+    let level = MipLevel::new(4, 1, row());
+    let cases = [
+        (WrapMode::Repeat, [2.0, 3.0, 0.0, 3.0, 0.0, 1.0]),
+        (WrapMode::Clamp, [0.0, 0.0, 0.0, 3.0, 3.0, 3.0]),
+        (WrapMode::Mirror, [1.0, 0.0, 0.0, 3.0, 3.0, 2.0]),
+    ];
+    for &(wrap, expected) in &cases {
+        for (&x, &value) in [-2, -1, 0, 3, 4, 5].iter().zip(expected.iter()) {
+            assert_eq!(level.texel(x, 0, wrap)[0], value, "{:?} at {}", wrap, x);
+        }
+    }
+}
+
+#[test]
+fn coordinates_just_outside_of_the_unit_square_are_wrapped() {
+    let cases = [
+        (WrapMode::Repeat, [2.0, 3.0, 0.0, 1.0]),
+        (WrapMode::Clamp, [0.0, 0.0, 3.0, 3.0]),
+        (WrapMode::Mirror, [1.0, 0.0, 3.0, 2.0]),
+    ];
+    let us: [FloatTy; 4] = [-0.26, -0.01, 1.01, 1.26];
+    for &(wrap, expected) in &cases {
+        let texture = texture(wrap);
+        for (&u, &value) in us.iter().zip(expected.iter()) {
+            let texel = texture.value(&SurfacePoint::from_uv(u, 0.5));
+            assert_eq!(texel.x, value, "{:?} at u = {}", wrap, u);
+            // the single row is wrapped the same way along v
+            let texel = texture.value(&SurfacePoint::from_uv(u, -0.01));
+            assert_eq!(texel.x, value, "{:?} at u = {}, v = -0.01", wrap, u);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use image::RgbaImage;
+
+use raytracer::{Aovs, DisplayPipeline, FloatTy, Vec3};
+
+use crate::exr::{Channel, SampleType};
+
+/// Render pass written besides the final image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    Albedo,
+    Normal,
+    Depth,
+    Position,
+    ObjectId,
+    MaterialId,
+    Uv,
+    Direct,
+    Indirect,
+    Emission,
+}
+
+/// How the samples of a pixel are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PassFilter {
+    Average,
+    /// Smallest value, so that edges do not get depths between objects.
+    Closest,
+    /// Value of the first sample, for IDs which cannot be averaged.
+    First,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 10] = [
+        Pass::Albedo,
+        Pass::Normal,
+        Pass::Depth,
+        Pass::Position,
+        Pass::ObjectId,
+        Pass::MaterialId,
+        Pass::Uv,
+        Pass::Direct,
+        Pass::Indirect,
+        Pass::Emission,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::Albedo => "albedo",
+            Pass::Normal => "normal",
+            Pass::Depth => "depth",
+            Pass::Position => "position",
+            Pass::ObjectId => "object_id",
+            Pass::MaterialId => "material_id",
+            Pass::Uv => "uv",
+            Pass::Direct => "direct",
+            Pass::Indirect => "indirect",
+            Pass::Emission => "emission",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Pass> {
+        Pass::ALL.iter().copied().find(|pass| pass.name() == name)
+    }
+
+    fn channels(self) -> &'static [&'static str] {
+        match self {
+            Pass::Albedo | Pass::Direct | Pass::Indirect | Pass::Emission => &["R", "G", "B"],
+            Pass::Normal | Pass::Position => &["X", "Y", "Z"],
+            Pass::Depth => &["Z"],
+            Pass::ObjectId | Pass::MaterialId => &["ID"],
+            Pass::Uv => &["U", "V"],
+        }
+    }
+
+    fn filter(self) -> PassFilter {
+        match self {
+            Pass::Depth => PassFilter::Closest,
+            Pass::ObjectId | Pass::MaterialId => PassFilter::First,
+            _ => PassFilter::Average,
+        }
+    }
+
+    /// Whether the pass holds light, which goes through the display
+    /// pipeline in 8-bit images.
+    fn is_radiance(self) -> bool {
+        matches!(self, Pass::Direct | Pass::Indirect | Pass::Emission)
+    }
+
+    fn value(self, aovs: &Aovs) -> Vec3 {
+        match self {
+            Pass::Albedo => aovs.albedo,
+            Pass::Normal => aovs.normal,
+            Pass::Depth => Vec3::repeat(aovs.depth),
+            Pass::Position => aovs.position,
+            Pass::ObjectId => Vec3::repeat(aovs.object_id as FloatTy),
+            Pass::MaterialId => Vec3::repeat(aovs.material_id as FloatTy),
+            Pass::Uv => Vec3::new(aovs.uv[0], aovs.uv[1], 0.0),
+            Pass::Direct => aovs.direct,
+            Pass::Indirect => aovs.indirect,
+            Pass::Emission => aovs.emission,
+        }
+    }
+}
+
+/// Color of an ID in 8-bit images, black for no ID.
+fn id_color(id: FloatTy) -> Vec3 {
+    if id == 0.0 {
+        return Vec3::zeros();
+    }
+    let hash = (id as u32).wrapping_mul(0x9e37_79b1);
+    let c = |shift: u32| FloatTy::from((hash >> shift) as u8) / 255.0;
+    Vec3::new(c(24), c(16), c(8))
+}
+
+/// Samples of a render pass, accumulated like `PixelData`.
+#[derive(Debug, Clone)]
+pub struct AovBuffer {
+    pass: Pass,
+    width: usize,
+    height: usize,
+    buffer: Vec<(Vec3, usize)>,
+}
+
+impl AovBuffer {
+    pub fn new(pass: Pass, width: usize, height: usize) -> Self {
+        AovBuffer {
+            pass,
+            width,
+            height,
+            buffer: vec![(Vec3::zeros(), 0); width * height],
+        }
+    }
+
+    pub fn pass(&self) -> Pass {
+        self.pass
+    }
+
+    pub fn append(&mut self, x: usize, y: usize, aovs: &Aovs) {
+        let value = self.pass.value(aovs);
+        if value.iter().any(|c| c.is_nan()) {
+            return;
+        }
+
+        let (current, count) = &mut self.buffer[y * self.width + x];
+        match self.pass.filter() {
+            PassFilter::Average => {
+                *current += value;
+                *count += 1;
+            }
+            PassFilter::Closest => {
+                if *count == 0 || value.x < current.x {
+                    *current = value;
+                }
+                *count = 1;
+            }
+            PassFilter::First => {
+                if *count == 0 {
+                    *current = value;
+                }
+                *count = 1;
+            }
+        }
+    }
+
+    /// Value of a pixel, from the samples received so far.
+    pub fn value(&self, x: usize, y: usize) -> Vec3 {
+        let (value, count) = self.buffer[y * self.width + x];
+        match count {
+            0 if self.pass == Pass::Depth => Vec3::repeat(FloatTy::INFINITY),
+            0 => Vec3::zeros(),
+            count => value / count as FloatTy,
+        }
+    }
+
+    /// Values from the top left pixel row by row. Material IDs are only
+    /// valid during the render, so they are numbered from one in the order
+    /// they appear in the image.
+    pub fn values(&self) -> Vec<Vec3> {
+        let values = (0..self.height).flat_map(|y| (0..self.width).map(move |x| self.value(x, y)));
+        if self.pass != Pass::MaterialId {
+            return values.collect();
+        }
+
+        let mut ids = HashMap::new();
+        values
+            .map(|value| {
+                if value.x == 0.0 {
+                    return value;
+                }
+                let next = ids.len() + 1;
+                let id = *ids.entry(value.x as u64).or_insert(next);
+                Vec3::repeat(id as FloatTy)
+            })
+            .collect()
+    }
+
+    /// OpenEXR channels of the pass, in a layer named after it. Passes
+    /// holding data rather than colors are always stored as 32-bit floats.
+    pub fn channels(&self, sample_type: SampleType) -> Vec<Channel> {
+        let sample_type = match self.pass {
+            Pass::Albedo | Pass::Normal | Pass::Direct | Pass::Indirect | Pass::Emission => {
+                sample_type
+            }
+            _ => SampleType::Float,
+        };
+        let values = self.values();
+        self.pass
+            .channels()
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                Channel::new(
+                    format!("{}.{}", self.pass.name(), name),
+                    values.iter().map(|v| v[i] as f32).collect(),
+                    sample_type,
+                )
+            })
+            .collect()
+    }
+
+    /// 8-bit image of the pass: light goes through the display pipeline,
+    /// albedos are sRGB encoded and data is remapped to visible colors.
+    pub fn as_image(&self, display: &DisplayPipeline) -> RgbaImage {
+        let values = self.values();
+        let finite_depths = values.iter().map(|v| v.x).filter(|d| d.is_finite());
+        let max_depth = finite_depths.fold(0.0, FloatTy::max);
+        let (min_position, max_position) = values.iter().fold(
+            (
+                Vec3::repeat(FloatTy::INFINITY),
+                Vec3::repeat(-FloatTy::INFINITY),
+            ),
+            |(min, max), v| (min.inf(v), max.sup(v)),
+        );
+
+        // casts to integers saturate
+        let to_byte = |c: FloatTy| (c * 255.0).round() as u8;
+        RgbaImage::from_fn(self.width as _, self.height as _, |x, y| {
+            let value = values[y as usize * self.width + x as usize];
+            let color = match self.pass {
+                _ if self.pass.is_radiance() => display.to_srgb8(value),
+                Pass::Albedo => DisplayPipeline::new().to_srgb8(value),
+                pass => {
+                    let data = match pass {
+                        Pass::Normal => (value + Vec3::repeat(1.0)) / 2.0,
+                        Pass::Depth if value.x.is_finite() && max_depth > 0.0 => {
+                            Vec3::repeat(value.x / max_depth)
+                        }
+                        Pass::Depth => Vec3::repeat(1.0),
+                        Pass::Position => (value - min_position).component_div(
+                            &(max_position - min_position).map(|c| c.max(FloatTy::EPSILON)),
+                        ),
+                        Pass::Uv => {
+                            Vec3::new(value.x.rem_euclid(1.0), value.y.rem_euclid(1.0), 0.0)
+                        }
+                        _ => id_color(value.x),
+                    };
+                    [to_byte(data.x), to_byte(data.y), to_byte(data.z)]
+                }
+            };
+            image::Rgba([color[0], color[1], color[2], 255])
+        })
+    }
+}
@@ -6,10 +6,22 @@ use maplit::hashmap;
 use rand;
 use rand::prelude::*;
 
-use raytracer::hittable::{self, make_box, Plane, Sphere, XYRect, XZRect, YZRect};
-use raytracer::material::{Dielectric, Lambertian, Light, Metal};
-use raytracer::texture::{CheckerTexture, ImageTexture, SolidTexture};
-use raytracer::{self, FloatTy, Hittable, HittableExt, Mat44, Pt3, Vec3};
+use raytracer::background::PreethamSky;
+use raytracer::hittable::{self, make_box, Curve, CurveType, Plane, Sphere, XYRect, XZRect};
+use raytracer::light::{DirectionalLight, IesProfile, PointLight, SpotLight};
+use raytracer::material::{
+    Coated, Conductor, Dielectric, Hair, Ior, Lambertian, Light, Metal, MixMaterial,
+    RoughDielectric, Subsurface, SubsurfaceMethod, ThinFilm,
+};
+use raytracer::scenes;
+use raytracer::texture::{
+    CheckerTexture, ColorRamp, FractalNoise, ImageTexture, MarbleTexture, PerlinTexture,
+    SolidTexture, VoronoiFeature, VoronoiTexture, WoodTexture,
+};
+use raytracer::{
+    self, Background, DisplayPipeline, FloatTy, Hittable, HittableExt, LightSource, Mat44, Pt3,
+    ToneMapper, Vec3,
+};
 
 use crate::{PresetConfig, SceneDescription};
 
@@ -23,6 +35,7 @@ pub fn default_scene_builder() -> SceneDescription {
         vfov: 20.0,
         sample_count: 1,
         background: Some(Vec3::repeat(0.1)),
+        display: DisplayPipeline::new(),
     };
 
     let test_preset = PresetConfig {
@@ -34,6 +47,7 @@ pub fn default_scene_builder() -> SceneDescription {
         vfov: 20.0,
         sample_count: 12,
         background: Some(Vec3::repeat(0.1)),
+        display: DisplayPipeline::new(),
     };
 
     let complete_preset = PresetConfig {
@@ -45,6 +59,7 @@ pub fn default_scene_builder() -> SceneDescription {
         vfov: 20.0,
         sample_count: 128,
         background: Some(Vec3::repeat(0.1)),
+        display: DisplayPipeline::new(),
     };
 
     let checker_texture = CheckerTexture::new(
@@ -121,7 +136,7 @@ pub fn default_scene_builder() -> SceneDescription {
         Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), None)),
     )));
 
-    let declarations = hittable::build_bvh(objects);
+    let declarations = hittable::build_bvh(hittable::numbered(objects));
     // let declarations = objects;
 
     SceneDescription {
@@ -131,6 +146,8 @@ pub fn default_scene_builder() -> SceneDescription {
             "test".into() => test_preset
         },
         declarations,
+        lights: Vec::new(),
+        background: None,
     }
 }
 
@@ -144,6 +161,7 @@ pub fn two_spheres() -> SceneDescription {
         vfov: 60.0,
         sample_count: 1,
         background: None,
+        display: DisplayPipeline::new(),
     };
 
     let test_preset = PresetConfig {
@@ -206,6 +224,8 @@ pub fn two_spheres() -> SceneDescription {
             "test".into() => test_preset
         },
         declarations,
+        lights: Vec::new(),
+        background: None,
     }
 }
 
@@ -219,6 +239,7 @@ pub fn cornell_box() -> SceneDescription {
         vfov: 40.0,
         sample_count: 40,
         background: None,
+        display: DisplayPipeline::new().with_tone_mapper(ToneMapper::Agx),
     };
 
     let test_preset = PresetConfig {
@@ -240,79 +261,917 @@ pub fn cornell_box() -> SceneDescription {
         ..complete_preset
     };
 
-    let red = Arc::new(Lambertian::from_solid_color(Vec3::new(0.7, 0.12, 0.05)));
-    let wall = Arc::new(Lambertian::from_solid_color(Vec3::repeat(1.0)));
-    let white = Arc::new(Lambertian::from_solid_color(Vec3::new(0.95, 0.95, 0.95)));
-    let green = Arc::new(Lambertian::from_solid_color(Vec3::new(0.2, 0.4, 0.36)));
-    let light = Arc::new(Light::new(Vec3::new(1.0, 0.7, 0.38) * 30.0));
-    let mirror = Arc::new(Metal::new(Vec3::repeat(0.9), None));
+    let declarations = hittable::build_bvh(hittable::numbered(scenes::cornell_box()));
 
-    let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
-    objects.push(Box::new(YZRect::new(
-        0.0,
-        555.0,
-        0.0,
-        555.0,
-        555.0,
-        red.clone(),
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "test".into() => test_preset,
+            "complete".into() => complete_preset,
+            "huge".into() => huge_preset,
+        },
+        declarations,
+        lights: Vec::new(),
+        background: None,
+    }
+}
+
+pub fn delta_lights() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(13.0, 4.0, 3.0),
+        look_at: Pt3::new(0.0, 1.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 30.0,
+        sample_count: 4,
+        background: Some(Vec3::repeat(0.02)),
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 32,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 256,
+        ..default_preset
+    };
+
+    let ground_texture = CheckerTexture::new(
+        Arc::new(SolidTexture::new(Vec3::repeat(0.2))),
+        Arc::new(SolidTexture::new(Vec3::repeat(0.8))),
+        5.0,
+    );
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(Plane::with_uv(
+        Pt3::origin(),
+        Vec3::new(0.0, 1.0, 0.0),
+        (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+        Arc::new(Lambertian::new(ground_texture)),
+    ))];
+
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(-3.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::from_solid_color(Vec3::new(0.7, 0.2, 0.1))),
     )));
 
-    objects.push(Box::new(YZRect::new(
-        0.0,
-        555.0,
-        0.0,
-        555.0,
-        0.0,
-        green.clone(),
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Conductor::gold(0.3)),
+    )));
+
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(3.0, 1.0, 0.0),
+        1.0,
+        Arc::new(RoughDielectric::new(1.5, 0.2)),
+    )));
+
+    let lights: Vec<Box<dyn LightSource>> = vec![
+        Box::new(PointLight::from_power(
+            Pt3::new(-3.0, 4.0, 3.0),
+            raytracer::spectrum::blackbody(2700.0),
+            800.0,
+        )),
+        Box::new(SpotLight::new(
+            Pt3::new(3.0, 6.0, 2.0),
+            Vec3::new(0.0, -1.0, -0.3),
+            Vec3::new(0.4, 0.6, 1.0) * 40.0,
+            25.0,
+            18.0,
+        )),
+        Box::new(
+            DirectionalLight::new(Vec3::new(-1.0, -2.0, -0.5), Vec3::repeat(1.5))
+                .with_angular_diameter(0.53),
+        ),
+    ];
+
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: None,
+    }
+}
+
+pub fn ies_lights() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(0.0, 2.0, 9.0),
+        look_at: Pt3::new(0.0, 1.5, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 40.0,
+        sample_count: 4,
+        background: Some(Vec3::zeros()),
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 32,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 256,
+        ..default_preset
+    };
+
+    let white = Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.73)));
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![
+        Box::new(XZRect::new(-5.0, 5.0, -3.0, 5.0, 0.0, white.clone())),
+        Box::new(XYRect::new(-5.0, 5.0, 0.0, 4.0, -1.0, white.clone())),
+    ];
+
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(0.0, 0.6, 1.0),
+        0.6,
+        Arc::new(Conductor::copper(0.2)),
     )));
-    objects.push(Box::new(XZRect::new(
+
+    // tilt the downlights toward the wall to graze it with their scallops
+    let profile = IesProfile::parse(include_str!("downlight.ies"))
+        .unwrap()
+        .with_orientation(Mat44::from_axis_angle(
+            &Vec3::x_axis(),
+            (20.0 as FloatTy).to_radians(),
+        ))
+        .unwrap();
+    let profile = Arc::new(profile);
+
+    let lights: Vec<Box<dyn LightSource>> = (-1..=1)
+        .map(|i| {
+            Box::new(PointLight::from_profile(
+                Pt3::new(i as FloatTy * 3.0, 3.9, -0.5),
+                raytracer::spectrum::blackbody(3000.0),
+                profile.clone(),
+            )) as Box<dyn LightSource>
+        })
+        .collect();
+
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: None,
+    }
+}
+
+pub fn daylight() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(13.0, 2.0, 3.0),
+        look_at: Pt3::new(0.0, 1.5, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 40.0,
+        sample_count: 4,
+        background: None,
+        // the sun is far brighter than the rest of the scene
+        display: DisplayPipeline::new()
+            .with_exposure(1.0)
+            .with_tone_mapper(ToneMapper::Aces),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 32,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 256,
+        ..default_preset
+    };
+
+    // summer morning at mid latitudes, scaled down to keep
+    // the sunlit ground in the displayable range
+    let sky = PreethamSky::from_location(172.0, 9.0, 45.0, 3.0)
+        .with_ground_albedo(Vec3::new(0.25, 0.22, 0.18))
+        .with_intensity(0.01);
+
+    let ground_texture = CheckerTexture::new(
+        Arc::new(SolidTexture::new(Vec3::repeat(0.2))),
+        Arc::new(SolidTexture::new(Vec3::repeat(0.6))),
+        2.0,
+    );
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(XZRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
         0.0,
-        555.0,
+        Arc::new(Lambertian::new(ground_texture)),
+    ))];
+
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(0.0, 1.0, -3.0),
+        1.0,
+        Arc::new(Lambertian::from_solid_color(Vec3::new(0.7, 0.7, 0.7))),
+    )));
+
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Metal::new(Vec3::repeat(0.9), None)),
+    )));
+
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(0.0, 1.0, 3.0),
+        1.0,
+        Arc::new(Conductor::copper(0.3)),
+    )));
+
+    let lights: Vec<Box<dyn LightSource>> = vec![Box::new(sky.sun())];
+
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: Some(Arc::new(sky) as Arc<dyn Background>),
+    }
+}
+
+/// Glass and diamond in front of thin white light strips, to be rendered with
+/// `--spectral` to show dispersion.
+pub fn dispersion() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(0.0, 1.0, 8.0),
+        look_at: Pt3::new(0.0, 1.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 30.0,
+        sample_count: 16,
+        background: Some(Vec3::repeat(0.01)),
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 64,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 512,
+        ..default_preset
+    };
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(XZRect::new(
+        -10.0,
+        10.0,
+        -10.0,
+        10.0,
         0.0,
-        555.0,
+        Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.3))),
+    ))];
+
+    for i in -6..=6 {
+        let x = i as FloatTy * 0.5;
+        objects.push(Box::new(XYRect::new(
+            x - 0.04,
+            x + 0.04,
+            0.0,
+            4.0,
+            -3.0,
+            Arc::new(Light::new(Vec3::repeat(8.0))),
+        )));
+    }
+
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(-1.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Dielectric::new(Ior::diamond())),
+    )));
+
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(1.2, 1.0, 0.0),
+        1.0,
+        Arc::new(RoughDielectric::new(Ior::bk7(), 0.05)),
+    )));
+
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights: Vec::new(),
+        background: None,
+    }
+}
+
+pub fn iridescence() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(0.0, 2.0, 9.0),
+        look_at: Pt3::new(0.0, 1.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        sample_count: 16,
+        background: None,
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 64,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 512,
+        ..default_preset
+    };
+
+    let sky = PreethamSky::from_location(172.0, 15.0, 45.0, 2.5).with_intensity(0.01);
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(XZRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
         0.0,
-        wall.clone(),
+        Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.4))),
+    ))];
+
+    // soap film thinning unevenly between 200 and 800 nanometers
+    let bubble =
+        ThinFilm::bubble(1.0, 1.33).with_thickness(Arc::new(PerlinTexture::new(6.0)), 800.0);
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(-2.4, 1.0, 0.0),
+        1.0,
+        Arc::new(bubble),
     )));
-    objects.push(Box::new(XYRect::new(
+
+    let titanium = Conductor::new(
+        Vec3::new(2.74, 2.54, 2.16),
+        Vec3::new(3.81, 3.43, 2.98),
+        0.15,
+    );
+    let anodized = ThinFilm::new(Arc::new(titanium), 300.0, 2.4)
+        .with_substrate(Vec3::new(2.74, 2.54, 2.16), Vec3::new(3.81, 3.43, 2.98));
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(anodized),
+    )));
+
+    let coated_lens = ThinFilm::new(Arc::new(Dielectric::new(1.52)), 100.0, 1.38);
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(2.4, 1.0, 0.0),
+        1.0,
+        Arc::new(coated_lens),
+    )));
+
+    let lights: Vec<Box<dyn LightSource>> = vec![Box::new(sky.sun())];
+
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: Some(Arc::new(sky) as Arc<dyn Background>),
+    }
+}
+
+pub fn subsurface() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(0.0, 2.0, 9.0),
+        look_at: Pt3::new(0.0, 1.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        sample_count: 16,
+        background: None,
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 64,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 512,
+        ..default_preset
+    };
+
+    // low sun behind the objects, to show light bleeding through them
+    let sky = PreethamSky::new(Vec3::new(0.3, 0.25, -1.0), 3.0).with_intensity(0.01);
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(XZRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
         0.0,
-        555.0,
+        Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.4))),
+    ))];
+
+    let skin = Subsurface::new(
+        Vec3::new(0.85, 0.55, 0.45),
+        Vec3::new(0.37, 0.14, 0.08),
+        1.4,
+    );
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(-2.4, 1.0, 0.0),
+        1.0,
+        Arc::new(skin),
+    )));
+
+    let marble = Subsurface::new(Vec3::new(0.83, 0.79, 0.75), Vec3::new(0.22, 0.26, 0.3), 1.5)
+        .with_method(SubsurfaceMethod::Diffusion);
+    objects.extend(make_box(
+        Pt3::new(-0.8, 0.0, -0.8),
+        Pt3::new(0.8, 1.6, 0.8),
+        Arc::new(marble),
+    ));
+
+    let milk = Subsurface::new(Vec3::new(0.95, 0.93, 0.85), Vec3::new(0.6, 0.5, 0.4), 1.35)
+        .with_anisotropy(0.7);
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(2.4, 1.0, 0.0),
+        1.0,
+        Arc::new(milk),
+    )));
+
+    let lights: Vec<Box<dyn LightSource>> = vec![
+        Box::new(sky.sun()),
+        Box::new(PointLight::new(
+            Pt3::new(3.0, 5.0, 6.0),
+            Vec3::new(1.0, 0.9, 0.8) * 60.0,
+        )),
+    ];
+
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: Some(Arc::new(sky) as Arc<dyn Background>),
+    }
+}
+
+pub fn layered() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(0.0, 2.0, 9.0),
+        look_at: Pt3::new(0.0, 1.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        sample_count: 16,
+        background: None,
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 64,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 512,
+        ..default_preset
+    };
+
+    let sky = PreethamSky::from_location(172.0, 10.0, 45.0, 3.0).with_intensity(0.01);
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(XZRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
         0.0,
-        555.0,
-        555.0,
-        wall.clone(),
+        Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.4))),
+    ))];
+
+    // car paint: glossy clearcoat over a rough metallic flake layer
+    let flakes = Conductor::new(Vec3::new(0.2, 1.2, 1.6), Vec3::new(3.0, 2.0, 1.8), 0.5);
+    let paint = MixMaterial::new(
+        Arc::new(Lambertian::from_solid_color(Vec3::new(0.5, 0.03, 0.03))),
+        Arc::new(flakes),
+        Arc::new(SolidTexture::new(Vec3::repeat(0.3))),
+    );
+    let car_paint = Coated::new(Arc::new(paint), 1.5);
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(-2.4, 1.0, 0.0),
+        1.0,
+        Arc::new(car_paint),
+    )));
+
+    let wood = Lambertian::new(CheckerTexture::new(
+        Arc::new(SolidTexture::new(Vec3::new(0.45, 0.25, 0.1))),
+        Arc::new(SolidTexture::new(Vec3::new(0.3, 0.15, 0.05))),
+        20.0,
+    ));
+    let varnished_wood = Coated::new(Arc::new(wood), 1.5)
+        .with_roughness(0.1)
+        .with_tint(Vec3::new(0.95, 0.85, 0.6));
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(varnished_wood),
     )));
-    objects.push(Box::new(XZRect::new(
+
+    let dusty_metal = MixMaterial::new(
+        Arc::new(Conductor::aluminium(0.1)),
+        Arc::new(Lambertian::from_solid_color(Vec3::new(0.5, 0.45, 0.4))),
+        Arc::new(PerlinTexture::new(4.0)),
+    );
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(2.4, 1.0, 0.0),
+        1.0,
+        Arc::new(dusty_metal),
+    )));
+
+    let lights: Vec<Box<dyn LightSource>> = vec![Box::new(sky.sun())];
+
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: Some(Arc::new(sky) as Arc<dyn Background>),
+    }
+}
+
+pub fn anisotropic() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(0.0, 2.0, 9.0),
+        look_at: Pt3::new(0.0, 1.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        sample_count: 16,
+        background: Some(Vec3::repeat(0.05)),
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 64,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 512,
+        ..default_preset
+    };
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(XZRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
         0.0,
-        555.0,
+        Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.4))),
+    ))];
+
+    // the tangent of spheres goes around their vertical axis
+    let roughnesses = [(0.3, 0.3), (0.05, 0.5), (0.5, 0.05)];
+    for (i, (along_tangent, along_bitangent)) in roughnesses.iter().enumerate() {
+        let brushed =
+            Conductor::aluminium(0.3).with_anisotropic_roughness(*along_tangent, *along_bitangent);
+        objects.push(Box::new(Sphere::new(
+            Pt3::new(-2.4 + 2.4 * i as FloatTy, 1.0, 0.0),
+            1.0,
+            Arc::new(brushed),
+        )));
+    }
+
+    let lights: Vec<Box<dyn LightSource>> = vec![
+        Box::new(PointLight::new(
+            Pt3::new(-3.0, 6.0, 6.0),
+            Vec3::new(1.0, 0.95, 0.9) * 80.0,
+        )),
+        Box::new(PointLight::new(
+            Pt3::new(4.0, 3.0, 5.0),
+            Vec3::new(0.6, 0.7, 1.0) * 40.0,
+        )),
+    ];
+
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: None,
+    }
+}
+
+pub fn hair() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(0.0, 1.5, 5.0),
+        look_at: Pt3::new(0.0, 0.9, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        sample_count: 16,
+        background: None,
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 64,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 512,
+        ..default_preset
+    };
+
+    let sky = PreethamSky::from_location(172.0, 16.0, 45.0, 3.0).with_intensity(0.01);
+    let mut rng = rand::thread_rng();
+
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(XZRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
         0.0,
-        555.0,
-        555.0,
-        wall.clone(),
-    )));
-    objects.push(Box::new(XZRect::new(
-        213.0, 343.0, 227.0, 332.0, 554.0, light,
+        Arc::new(Lambertian::from_solid_color(Vec3::new(0.3, 0.25, 0.2))),
+    ))];
+
+    // a fur ball with strands drooping under their weight
+    let center = Pt3::new(0.0, 0.9, 0.0);
+    let radius = 0.6;
+    objects.push(Box::new(Sphere::new(
+        center,
+        radius,
+        Arc::new(Lambertian::from_solid_color(Vec3::new(0.2, 0.12, 0.06))),
     )));
+    let fur = Arc::new(Hair::from_melanin(1.3, 0.3, 0.25, 0.3));
+    for _ in 0..4000 {
+        let normal = loop {
+            let v = Vec3::new(
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+            );
+            let norm = v.norm();
+            if norm > 0.1 && norm <= 1.0 && v.y > -0.6 * norm {
+                break v / norm;
+            }
+        };
+        let length = rng.gen_range(0.25, 0.4);
+        let points: Vec<Pt3> = (0..5)
+            .map(|i| {
+                let t = i as FloatTy / 4.0;
+                center + normal * (radius + length * t) - Vec3::y() * (0.15 * length * t * t)
+            })
+            .collect();
+        objects.extend(hittable::curves_from_polyline(
+            &points,
+            &[0.006, 0.005, 0.004, 0.002, 0.0005],
+            CurveType::Round,
+            fur.clone(),
+        ));
+    }
 
-    let box1 = make_box(Pt3::origin(), Pt3::new(165.0, 330.0, 165.0), mirror.clone());
-    let transform1 = Mat44::new_translation(&Vec3::new(265.0, 0.0, 295.0))
-        * Mat44::from_axis_angle(&Vec3::y_axis(), (15.0 as FloatTy).to_radians());
+    // grass blades facing random directions
+    let grass = Arc::new(Lambertian::from_solid_color(Vec3::new(0.15, 0.35, 0.05)));
+    for _ in 0..3000 {
+        let root = Pt3::new(rng.gen_range(-2.5, 2.5), 0.0, rng.gen_range(-1.5, 2.0));
+        if (root - Pt3::new(center.x, 0.0, center.z)).norm() < radius {
+            continue;
+        }
+        let angle = rng.gen_range(0.0, 2.0 * std::f64::consts::PI as FloatTy);
+        let facing = Vec3::new(angle.cos(), 0.0, angle.sin());
+        let height = rng.gen_range(0.1, 0.3);
+        let bend = facing * (height * rng.gen_range(0.1, 0.5));
+        let control_points = [
+            root,
+            root + Vec3::y() * (height / 3.0),
+            root + Vec3::y() * (2.0 * height / 3.0) + bend / 2.0,
+            root + Vec3::y() * height + bend,
+        ];
+        let curve_type = CurveType::Ribbon {
+            start_normal: facing,
+            end_normal: (facing - Vec3::y() * 0.5).normalize(),
+        };
+        objects.push(Box::new(Curve::new(
+            control_points,
+            0.02,
+            0.002,
+            curve_type,
+            grass.clone(),
+        )));
+    }
 
-    objects.push(Box::new(box1.transform(transform1)));
+    let lights: Vec<Box<dyn LightSource>> = vec![Box::new(sky.sun())];
 
-    let box2 = make_box(Pt3::origin(), Pt3::new(165.0, 165.0, 165.0), white);
-    let transform2 = Mat44::new_translation(&Vec3::new(130.0, 0.0, 65.0))
-        * Mat44::from_axis_angle(&Vec3::y_axis(), (-18.0 as FloatTy).to_radians());
-    objects.push(Box::new(box2.transform(transform2)));
+    SceneDescription {
+        presets: hashmap! {
+            "default".into() => default_preset,
+            "complete".into() => complete_preset,
+            "test".into() => test_preset
+        },
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: Some(Arc::new(sky) as Arc<dyn Background>),
+    }
+}
 
-    let declarations = hittable::build_bvh(objects);
+pub fn procedural() -> SceneDescription {
+    let default_preset = PresetConfig {
+        width: 600,
+        height: 400,
+        look_from: Pt3::new(0.0, 3.0, 9.0),
+        look_at: Pt3::new(0.0, 1.0, 0.0),
+        up: Vec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        sample_count: 16,
+        background: None,
+        display: DisplayPipeline::new(),
+    };
+
+    let test_preset = PresetConfig {
+        width: 900,
+        height: 600,
+        sample_count: 64,
+        ..default_preset
+    };
+
+    let complete_preset = PresetConfig {
+        width: 1200,
+        height: 800,
+        sample_count: 512,
+        ..default_preset
+    };
+
+    let sky = PreethamSky::from_location(172.0, 10.0, 45.0, 3.0).with_intensity(0.01);
+
+    // flagstones: a random shade per cell with darker joints between them
+    let stones = ColorRamp::new(
+        Arc::new(VoronoiTexture::new(0.8, 7).with_feature(VoronoiFeature::Cell)),
+        vec![
+            (0.0, Vec3::new(0.35, 0.33, 0.3)),
+            (1.0, Vec3::new(0.6, 0.55, 0.5)),
+        ],
+    );
+    let joints = ColorRamp::new(
+        Arc::new(VoronoiTexture::new(0.8, 7).with_feature(VoronoiFeature::Edge)),
+        vec![(0.0, Vec3::zeros()), (0.05, Vec3::repeat(1.0))],
+    );
+    let flagstones = MixMaterial::new(
+        Arc::new(Lambertian::from_solid_color(Vec3::repeat(0.1))),
+        Arc::new(Lambertian::new(stones)),
+        Arc::new(joints),
+    );
+    let mut objects: Vec<Box<dyn Hittable>> = vec![Box::new(XZRect::new(
+        -20.0,
+        20.0,
+        -20.0,
+        20.0,
+        0.0,
+        Arc::new(flagstones),
+    ))];
+
+    let marble = ColorRamp::new(
+        Arc::new(
+            MarbleTexture::new(
+                1.5,
+                FractalNoise::new(1)
+                    .with_octaves(6)
+                    .with_lacunarity(2.0)
+                    .with_gain(0.5),
+            )
+            .with_direction(Vec3::new(1.0, 1.0, 0.0)),
+        ),
+        vec![
+            (0.0, Vec3::new(0.2, 0.22, 0.25)),
+            (0.3, Vec3::new(0.75, 0.75, 0.72)),
+            (1.0, Vec3::new(0.9, 0.9, 0.88)),
+        ],
+    );
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(-2.4, 1.0, 0.0),
+        1.0,
+        Arc::new(Coated::new(Arc::new(Lambertian::new(marble)), 1.5)),
+    )));
+
+    // the trunk runs along the sphere, tilted to show the rings and grain
+    let wood = ColorRamp::new(
+        Arc::new(WoodTexture::new(
+            6.0,
+            2.0,
+            FractalNoise::new(2).with_octaves(3),
+        )),
+        vec![
+            (0.0, Vec3::new(0.45, 0.26, 0.1)),
+            (0.7, Vec3::new(0.5, 0.3, 0.12)),
+            (1.0, Vec3::new(0.25, 0.12, 0.04)),
+        ],
+    );
+    let varnished_wood = Coated::new(Arc::new(Lambertian::new(wood)), 1.5)
+        .with_roughness(0.1)
+        .with_tint(Vec3::new(0.95, 0.85, 0.6));
+    objects.push(Box::new(
+        Sphere::new(Pt3::origin(), 1.0, Arc::new(varnished_wood)).transform(
+            Mat44::new_translation(&Vec3::new(0.0, 1.0, 0.0))
+                * Mat44::from_euler_angles(1.2, 0.0, 0.4)
+                * Mat44::new_translation(&Vec3::new(0.3, 0.0, 0.2)),
+        ),
+    ));
+
+    let granite = ColorRamp::new(
+        Arc::new(
+            PerlinTexture::new(12.0)
+                .with_noise(FractalNoise::new(3).with_octaves(4).with_gain(0.7))
+                .turbulent(),
+        ),
+        vec![
+            (0.0, Vec3::new(0.05, 0.05, 0.05)),
+            (0.25, Vec3::new(0.55, 0.4, 0.38)),
+            (0.4, Vec3::new(0.8, 0.78, 0.75)),
+        ],
+    );
+    objects.push(Box::new(Sphere::new(
+        Pt3::new(2.4, 1.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(granite)),
+    )));
+
+    let lights: Vec<Box<dyn LightSource>> = vec![Box::new(sky.sun())];
 
     SceneDescription {
         presets: hashmap! {
             "default".into() => default_preset,
-            "test".into() => test_preset,
             "complete".into() => complete_preset,
-            "huge".into() => huge_preset,
+            "test".into() => test_preset
         },
-        declarations,
+        declarations: hittable::build_bvh(hittable::numbered(objects)),
+        lights,
+        background: Some(Arc::new(sky) as Arc<dyn Background>),
     }
 }
@@ -0,0 +1,351 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Type of the values stored in an OpenEXR file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleType {
+    /// 16-bit floats, enough for final images and half their size.
+    Half,
+    /// 32-bit floats, for data needing the precision such as depth.
+    Float,
+}
+
+impl SampleType {
+    fn id(self) -> i32 {
+        match self {
+            SampleType::Half => 1,
+            SampleType::Float => 2,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            SampleType::Half => 2,
+            SampleType::Float => 4,
+        }
+    }
+}
+
+/// Channel of an image, with its values from the top left pixel row by row.
+/// Channels of a layer are named `layer.R`, `layer.G`... while the main
+/// image uses `R`, `G` and `B`.
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub name: String,
+    pub values: Vec<f32>,
+    pub sample_type: SampleType,
+}
+
+impl Channel {
+    pub fn new<S: Into<String>>(name: S, values: Vec<f32>, sample_type: SampleType) -> Self {
+        Channel {
+            name: name.into(),
+            values,
+            sample_type,
+        }
+    }
+}
+
+/// Rounds a float to the closest half float, ties to even.
+fn to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+
+    if exponent == 0xff {
+        // infinities stay infinite and NaNs stay NaNs
+        let nan = if mantissa != 0 { 0x0200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    let round = |value: u32, shift: u32, full_mantissa: u32| {
+        let round_bit = 1 << (shift - 1);
+        let sticky = full_mantissa & (round_bit - 1) != 0;
+        if full_mantissa & round_bit != 0 && (sticky || value & 1 != 0) {
+            value + 1
+        } else {
+            value
+        }
+    };
+
+    if exponent <= 0 {
+        // subnormal halves, the implicit bit of the float becoming explicit
+        if exponent < -10 {
+            return sign;
+        }
+        let full_mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exponent) as u32;
+        return sign | round(full_mantissa >> shift, shift, full_mantissa) as u16;
+    }
+
+    // a carry out of the mantissa correctly increments the exponent
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    sign | round(half, 13, mantissa) as u16
+}
+
+fn write_attribute<W: Write>(
+    out: &mut W,
+    name: &str,
+    attribute_type: &str,
+    value: &[u8],
+) -> io::Result<()> {
+    out.write_all(name.as_bytes())?;
+    out.write_all(&[0])?;
+    out.write_all(attribute_type.as_bytes())?;
+    out.write_all(&[0])?;
+    out.write_all(&(value.len() as i32).to_le_bytes())?;
+    out.write_all(value)
+}
+
+/// Writes a single part, uncompressed scanline OpenEXR file.
+pub fn write_exr<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    channels: &[Channel],
+) -> io::Result<()> {
+    if width == 0 || height == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot write an empty image",
+        ));
+    }
+    for channel in channels {
+        if channel.values.len() != width * height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("channel `{}` does not match the image size", channel.name),
+            ));
+        }
+    }
+
+    // readers expect the channels sorted by name
+    let mut channels: Vec<&Channel> = channels.iter().collect();
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);
+    header.extend_from_slice(&2u32.to_le_bytes());
+
+    let mut channel_list = Vec::new();
+    for channel in &channels {
+        channel_list.extend_from_slice(channel.name.as_bytes());
+        channel_list.push(0);
+        channel_list.extend_from_slice(&channel.sample_type.id().to_le_bytes());
+        // perceptually linear flag and reserved bytes
+        channel_list.extend_from_slice(&[0, 0, 0, 0]);
+        channel_list.extend_from_slice(&1i32.to_le_bytes());
+        channel_list.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channel_list.push(0);
+
+    let mut window = Vec::new();
+    for coordinate in &[0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&coordinate.to_le_bytes());
+    }
+
+    write_attribute(&mut header, "channels", "chlist", &channel_list)?;
+    write_attribute(&mut header, "compression", "compression", &[0])?;
+    write_attribute(&mut header, "dataWindow", "box2i", &window)?;
+    write_attribute(&mut header, "displayWindow", "box2i", &window)?;
+    write_attribute(&mut header, "lineOrder", "lineOrder", &[0])?;
+    write_attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    )?;
+    let mut center = Vec::new();
+    center.extend_from_slice(&0f32.to_le_bytes());
+    center.extend_from_slice(&0f32.to_le_bytes());
+    write_attribute(&mut header, "screenWindowCenter", "v2f", &center)?;
+    write_attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    )?;
+    header.push(0);
+
+    // one block per scanline, made of its y, its size and its data
+    let line_size: usize = channels
+        .iter()
+        .map(|channel| width * channel.sample_type.size())
+        .sum();
+    let first_block = header.len() + height * 8;
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&header)?;
+    for y in 0..height {
+        let offset = first_block + y * (8 + line_size);
+        out.write_all(&(offset as u64).to_le_bytes())?;
+    }
+
+    for y in 0..height {
+        out.write_all(&(y as i32).to_le_bytes())?;
+        out.write_all(&(line_size as i32).to_le_bytes())?;
+        for channel in &channels {
+            for &value in &channel.values[y * width..(y + 1) * width] {
+                match channel.sample_type {
+                    SampleType::Half => out.write_all(&to_half(value).to_le_bytes())?,
+                    SampleType::Float => out.write_all(&value.to_le_bytes())?,
+                }
+            }
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn halves_are_rounded_to_nearest_even() {
+        assert_eq!(to_half(0.0), 0x0000);
+        assert_eq!(to_half(-0.0), 0x8000);
+        assert_eq!(to_half(1.0), 0x3c00);
+        assert_eq!(to_half(-2.0), 0xc000);
+        assert_eq!(to_half(65504.0), 0x7bff);
+        assert_eq!(to_half(65519.0), 0x7bff);
+        assert_eq!(to_half(65520.0), 0x7c00);
+        // smallest normal and subnormal halves
+        assert_eq!(to_half(2f32.powi(-14)), 0x0400);
+        assert_eq!(to_half(2f32.powi(-24)), 0x0001);
+        // halfway values go to the even neighbor
+        assert_eq!(to_half(1.0 + 2f32.powi(-11)), 0x3c00);
+        assert_eq!(to_half(1.0 + 3.0 * 2f32.powi(-11)), 0x3c02);
+        assert_eq!(to_half(2f32.powi(-25)), 0x0000);
+        assert_eq!(to_half(1.5 * 2f32.powi(-24)), 0x0002);
+        assert_eq!(to_half(1023.5 * 2f32.powi(-24)), 0x0400);
+        assert_eq!(to_half(2f32.powi(-26)), 0x0000);
+    }
+
+    #[test]
+    fn infinities_and_nans_are_kept() {
+        assert_eq!(to_half(f32::INFINITY), 0x7c00);
+        assert_eq!(to_half(f32::NEG_INFINITY), 0xfc00);
+        assert_eq!(to_half(1e10), 0x7c00);
+        for &nan in &[f32::NAN, f32::from_bits(0x7f80_0001)] {
+            let half = to_half(nan);
+            assert_eq!(half & 0x7c00, 0x7c00);
+            assert_ne!(half & 0x03ff, 0);
+        }
+    }
+
+    fn read_u32(content: &[u8], position: usize) -> u32 {
+        u32::from_le_bytes(content[position..position + 4].try_into().unwrap())
+    }
+
+    fn read_u64(content: &[u8], position: usize) -> u64 {
+        u64::from_le_bytes(content[position..position + 8].try_into().unwrap())
+    }
+
+    fn read_string(content: &[u8], position: &mut usize) -> String {
+        let end = *position + content[*position..].iter().position(|&b| b == 0).unwrap();
+        let string = String::from_utf8(content[*position..end].to_vec()).unwrap();
+        *position = end + 1;
+        string
+    }
+
+    #[test]
+    fn header_and_offsets_match_the_scanlines() {
+        let channels = [
+            Channel::new("Z", vec![1.0, 2.0, 3.0, 4.0], SampleType::Float),
+            Channel::new("B", vec![0.5, 1.0, 2.0, -1.0], SampleType::Half),
+        ];
+        let path = std::env::temp_dir().join(format!("write_exr_{}.exr", std::process::id()));
+        write_exr(&path, 2, 2, &channels).unwrap();
+        let content = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(content[..4], [0x76, 0x2f, 0x31, 0x01]);
+        assert_eq!(read_u32(&content, 4), 2);
+
+        let mut position = 8;
+        let mut attributes = Vec::new();
+        loop {
+            let name = read_string(&content, &mut position);
+            if name.is_empty() {
+                break;
+            }
+            let attribute_type = read_string(&content, &mut position);
+            let size = read_u32(&content, position) as usize;
+            let value = content[position + 4..position + 4 + size].to_vec();
+            position += 4 + size;
+            attributes.push((name, attribute_type, value));
+        }
+        let names: Vec<&str> = attributes.iter().map(|a| a.0.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "channels",
+                "compression",
+                "dataWindow",
+                "displayWindow",
+                "lineOrder",
+                "pixelAspectRatio",
+                "screenWindowCenter",
+                "screenWindowWidth"
+            ]
+        );
+
+        // channels sorted by name, with their type and sampling
+        let mut expected_channels = Vec::new();
+        for (name, id) in &[("B", 1i32), ("Z", 2i32)] {
+            expected_channels.extend_from_slice(name.as_bytes());
+            expected_channels.push(0);
+            expected_channels.extend_from_slice(&id.to_le_bytes());
+            expected_channels.extend_from_slice(&[0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        }
+        expected_channels.push(0);
+        assert_eq!(attributes[0].2, expected_channels);
+        let window: Vec<u8> = [0i32, 0, 1, 1]
+            .iter()
+            .flat_map(|c| c.to_le_bytes().to_vec())
+            .collect();
+        assert_eq!(attributes[2].2, window);
+
+        // each scanline holds its two halves then its two floats
+        let line_size = 2 * 2 + 2 * 4;
+        let first_block = position + 2 * 8;
+        assert_eq!(read_u64(&content, position) as usize, first_block);
+        assert_eq!(
+            read_u64(&content, position + 8) as usize,
+            first_block + 8 + line_size
+        );
+        assert_eq!(content.len(), first_block + 2 * (8 + line_size));
+        for y in 0..2 {
+            let block = first_block + y * (8 + line_size);
+            assert_eq!(read_u32(&content, block), y as u32);
+            assert_eq!(read_u32(&content, block + 4) as usize, line_size);
+        }
+        let second_line = first_block + 8 + line_size + 8;
+        assert_eq!(
+            content[second_line..second_line + 4],
+            [0x00, 0x40, 0x00, 0xbc]
+        );
+        assert_eq!(
+            content[second_line + 4..second_line + 12],
+            [3f32.to_le_bytes(), 4f32.to_le_bytes()].concat()[..]
+        );
+    }
+
+    #[test]
+    fn mismatched_and_empty_images_are_rejected() {
+        let path = std::env::temp_dir().join(format!("reject_exr_{}.exr", std::process::id()));
+        let channel = Channel::new("R", vec![0.0; 3], SampleType::Half);
+        assert!(write_exr(&path, 2, 2, &[channel]).is_err());
+        assert!(write_exr(&path, 0, 2, &[]).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -8,6 +8,8 @@ use winit::{
 };
 use zerocopy::AsBytes;
 
+use raytracer::DisplayPipeline;
+
 use crate::PixelData;
 
 pub trait DisplayProgram: 'static + Sized {
@@ -17,6 +19,7 @@ pub trait DisplayProgram: 'static + Sized {
         width: u32,
         height: u32,
         image: Arc<Mutex<PixelData>>,
+        display: DisplayPipeline,
     ) -> (Self, Option<wgpu::CommandBuffer>);
     fn resize(
         &mut self,
@@ -33,6 +36,7 @@ pub trait DisplayProgram: 'static + Sized {
 
 pub struct RayTracingGUI {
     image: Arc<Mutex<PixelData>>,
+    display: DisplayPipeline,
     width: u32,
     height: u32,
     vertex_buf: wgpu::Buffer,
@@ -64,6 +68,7 @@ impl DisplayProgram for RayTracingGUI {
         width: u32,
         height: u32,
         image: Arc<Mutex<PixelData>>,
+        display: DisplayPipeline,
     ) -> (Self, Option<wgpu::CommandBuffer>) {
         let vertex_size = std::mem::size_of::<Vertex>();
         let (vertex_data, index_data) = RayTracingGUI::create_vertices();
@@ -208,6 +213,7 @@ impl DisplayProgram for RayTracingGUI {
 
         let program = RayTracingGUI {
             image,
+            display,
             width,
             height,
             vertex_buf,
@@ -239,7 +245,7 @@ impl DisplayProgram for RayTracingGUI {
         let mut encoder =
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
-        let image_buf = self.image.lock().unwrap().as_image();
+        let image_buf = self.image.lock().unwrap().as_image(&self.display);
         let texels = image_buf.as_bytes();
         let texture_extent = wgpu::Extent3d {
             width: self.width,
@@ -285,7 +291,12 @@ impl DisplayProgram for RayTracingGUI {
     }
 }
 
-pub async fn run_async<D: DisplayProgram>(width: u32, height: u32, image: Arc<Mutex<PixelData>>) {
+pub async fn run_async<D: DisplayProgram>(
+    width: u32,
+    height: u32,
+    image: Arc<Mutex<PixelData>>,
+    display: DisplayPipeline,
+) {
     let event_loop = EventLoop::new();
 
     let (window, size, surface) = {
@@ -329,7 +340,7 @@ pub async fn run_async<D: DisplayProgram>(width: u32, height: u32, image: Arc<Mu
 
     let mut swap_chain = device.create_swap_chain(&surface, &sc_desc);
 
-    let (mut program, init_cmd_buf) = D::init(&sc_desc, &device, width, height, image);
+    let (mut program, init_cmd_buf) = D::init(&sc_desc, &device, width, height, image, display);
     if let Some(cmd_buf) = init_cmd_buf {
         queue.submit(&[cmd_buf]);
     }
@@ -380,8 +391,13 @@ pub async fn run_async<D: DisplayProgram>(width: u32, height: u32, image: Arc<Mu
     })
 }
 
-pub fn run<D: DisplayProgram>(width: usize, height: usize, image: Arc<Mutex<PixelData>>) {
-    futures::executor::block_on(run_async::<D>(width as u32, height as u32, image));
+pub fn run<D: DisplayProgram>(
+    width: usize,
+    height: usize,
+    image: Arc<Mutex<PixelData>>,
+    display: DisplayPipeline,
+) {
+    futures::executor::block_on(run_async::<D>(width as u32, height as u32, image, display));
 }
 
 #[repr(C)]
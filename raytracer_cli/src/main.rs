@@ -11,14 +11,23 @@ use rand;
 use rand::prelude::*;
 use threadpool::ThreadPool;
 
-use raytracer::{self, Camera, FloatTy, Hittable, Pt3, Vec3};
+use raytracer::texture::TextureCache;
+use raytracer::{
+    self, Aovs, Background, Camera, Denoiser, DisplayPipeline, FloatTy, Hittable, LightSource, Pt3,
+    Ray, ToneMapper, Vec3,
+};
 
+mod aov;
 mod default_scene;
+mod exr;
 #[cfg(feature = "gui")]
 mod gui;
 mod obj;
+mod pfm;
 mod pixel_data;
 
+use aov::Pass;
+use exr::SampleType;
 use pixel_data::PixelData;
 
 #[derive(Debug)]
@@ -31,34 +40,69 @@ pub struct PresetConfig {
     vfov: FloatTy,
     sample_count: usize,
     background: Option<Vec3>,
+    display: DisplayPipeline,
 }
 
 pub struct SceneDescription {
     pub declarations: Vec<Box<dyn Hittable>>,
+    pub lights: Vec<Box<dyn LightSource>>,
+    /// Replaces the background color of the presets when set.
+    pub background: Option<Arc<dyn Background>>,
     pub presets: HashMap<String, PresetConfig>,
 }
 
 const THREAD_CHUNK_SIZE: usize = 20000;
 
+/// Light along a camera ray, with the render passes when they are written.
+enum Sample {
+    Color(Vec3),
+    Aovs(Aovs),
+}
+
 fn compute_pixel<R: Rng>(
-    camera: &Camera,
     objects: &[Box<dyn Hittable>],
-    u: FloatTy,
-    v: FloatTy,
-    background: Vec3,
+    lights: &[Box<dyn LightSource>],
+    ray: Ray,
+    background: &dyn Background,
+    spectral: bool,
+    with_passes: bool,
     rng: &mut R,
-) -> Vec3 {
-    let ray = camera.get_ray(u, v);
-    let color_vec = raytracer::compute_color(&objects, ray, background, rng);
-    color_vec
+) -> Sample {
+    if with_passes && spectral {
+        Sample::Aovs(raytracer::compute_spectral_aovs(
+            objects, lights, ray, background, rng,
+        ))
+    } else if with_passes {
+        Sample::Aovs(raytracer::compute_aovs(
+            objects, lights, ray, background, rng,
+        ))
+    } else if spectral {
+        Sample::Color(raytracer::compute_spectral_color(
+            objects, lights, ray, background, rng,
+        ))
+    } else {
+        Sample::Color(raytracer::compute_color(
+            objects, lights, ray, background, rng,
+        ))
+    }
 }
 
-fn search_scene(name: &str) -> SceneDescription {
+fn search_scene(name: &str, textures: &TextureCache) -> SceneDescription {
     match name {
         "random_balls" => default_scene::default_scene_builder(),
         "two_spheres" => default_scene::two_spheres(),
         "cornell" => default_scene::cornell_box(),
-        other => obj::load_obj(other).unwrap(),
+        "lights" => default_scene::delta_lights(),
+        "ies" => default_scene::ies_lights(),
+        "sky" => default_scene::daylight(),
+        "dispersion" => default_scene::dispersion(),
+        "iridescence" => default_scene::iridescence(),
+        "subsurface" => default_scene::subsurface(),
+        "layered" => default_scene::layered(),
+        "anisotropic" => default_scene::anisotropic(),
+        "hair" => default_scene::hair(),
+        "procedural" => default_scene::procedural(),
+        other => obj::load_obj(other, textures).unwrap(),
     }
 }
 
@@ -91,14 +135,130 @@ fn main() {
                 .default_value("4")
                 .validator(validate_integer),
         )
+        .arg(
+            Arg::with_name("spectral")
+                .help("Renders with wavelengths instead of RGB colors, for dispersion.")
+                .long("spectral"),
+        )
+        .arg(
+            Arg::with_name("texture_memory")
+                .help("Sets the memory budget of the image textures, in megabytes.")
+                .long("texture-memory")
+                .value_name("MEGABYTES")
+                .takes_value(true)
+                .validator(validate_integer),
+        )
+        .arg(
+            Arg::with_name("output")
+                .help("Sets the output image, .exr and .pfm files keeping the radiance as floats.")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .default_value("./last_result.png"),
+        )
+        .arg(
+            Arg::with_name("exposure")
+                .help("Overrides the exposure of the preset, in stops.")
+                .long("exposure")
+                .value_name("EV")
+                .allow_hyphen_values(true)
+                .validator(validate_float),
+        )
+        .arg(
+            Arg::with_name("white_balance")
+                .help("Overrides the white balance of the preset with the temperature of the light rendered as white.")
+                .long("white-balance")
+                .value_name("KELVIN")
+                .validator(validate_temperature),
+        )
+        .arg(
+            Arg::with_name("tone_mapper")
+                .help("Overrides the tone mapper of the preset.")
+                .long("tone-mapper")
+                .value_name("TONE_MAPPER")
+                .possible_values(&["clamp", "reinhard", "aces", "agx"]),
+        )
+        .arg(
+            Arg::with_name("passes")
+                .help("Writes render passes besides the image, as a comma separated list or \"all\".")
+                .long("passes")
+                .value_name("PASSES")
+                .use_delimiter(true)
+                .possible_values(&[
+                    "all",
+                    "albedo",
+                    "normal",
+                    "depth",
+                    "position",
+                    "object_id",
+                    "material_id",
+                    "uv",
+                    "direct",
+                    "indirect",
+                    "emission",
+                ]),
+        )
+        .arg(
+            Arg::with_name("denoise")
+                .help("Denoises the image once rendered, also writing the albedo and normal passes guiding the filter.")
+                .long("denoise"),
+        )
+        .arg(
+            Arg::with_name("exr_precision")
+                .help("Sets the type of the values written to OpenEXR images.")
+                .long("exr-precision")
+                .value_name("PRECISION")
+                .possible_values(&["half", "float"])
+                .default_value("half"),
+        )
         .get_matches();
 
-    let scene = search_scene(matches.value_of("INPUT").unwrap());
+    let textures = match matches.value_of("texture_memory") {
+        Some(megabytes) => TextureCache::bounded(usize::from_str(megabytes).unwrap() << 20),
+        None => TextureCache::new(),
+    };
+    let scene = search_scene(matches.value_of("INPUT").unwrap(), &textures);
 
     let objects = Arc::new(scene.declarations);
+    let lights = Arc::new(scene.lights);
     let preset_name = matches.value_of("preset").unwrap_or("default");
     let preset = scene.presets.get(preset_name).unwrap();
     let job_count = u32::from_str(matches.value_of("job_count").unwrap()).unwrap();
+    let spectral = matches.is_present("spectral");
+    let mut display = preset.display;
+    if let Some(exposure) = matches.value_of("exposure") {
+        display = display.with_exposure(FloatTy::from_str(exposure).unwrap());
+    }
+    if let Some(kelvin) = matches.value_of("white_balance") {
+        display = display.with_white_balance(FloatTy::from_str(kelvin).unwrap());
+    }
+    if let Some(tone_mapper) = matches.value_of("tone_mapper") {
+        display = display.with_tone_mapper(match tone_mapper {
+            "reinhard" => ToneMapper::Reinhard,
+            "aces" => ToneMapper::Aces,
+            "agx" => ToneMapper::Agx,
+            _ => ToneMapper::Clamp,
+        });
+    }
+    let mut passes: Vec<Pass> = match matches.values_of("passes") {
+        Some(names) if names.clone().any(|name| name == "all") => Pass::ALL.to_vec(),
+        Some(names) => names.filter_map(Pass::from_name).collect(),
+        None => Vec::new(),
+    };
+    let denoise = matches.is_present("denoise");
+    if denoise {
+        for pass in &[Pass::Albedo, Pass::Normal] {
+            if !passes.contains(pass) {
+                passes.push(*pass);
+            }
+        }
+    }
+    let with_passes = !passes.is_empty();
+    let output_path = matches.value_of("output").unwrap().to_owned();
+    let sample_type = match matches.value_of("exr_precision").unwrap() {
+        "float" => SampleType::Float,
+        _ => SampleType::Half,
+    };
 
     let nx: usize = preset.width;
     let ny: usize = preset.height;
@@ -112,21 +272,29 @@ fn main() {
         aspect_ratio,
     ));
 
-    let background_color = preset.background.unwrap_or(Vec3::repeat(0.0));
+    let background = scene.background.unwrap_or_else(|| {
+        Arc::new(preset.background.unwrap_or(Vec3::repeat(0.0))) as Arc<dyn Background>
+    });
 
     let sample_count = preset.sample_count;
 
-    let image = Arc::new(Mutex::new(PixelData::new(nx, ny)));
+    let image = Arc::new(Mutex::new(PixelData::new(nx, ny).with_passes(&passes)));
 
     let (send, recv) = mpsc::channel();
     let pool = ThreadPool::new(job_count as usize);
 
+    // textures are filtered over a fraction of the pixel as the samples
+    // already average them over the whole pixel
+    let footprint = (1.0 / (sample_count as FloatTy).sqrt()).max(0.125);
+
     let main_iter = iproduct!(0..sample_count, 0..ny, 0..nx);
 
     for chunks in &main_iter.chunks(THREAD_CHUNK_SIZE) {
         let local_send = send.clone();
         let camera = camera.clone();
         let objects = objects.clone();
+        let lights = lights.clone();
+        let background = background.clone();
         let chunks: Vec<_> = chunks.collect();
 
         pool.execute(move || {
@@ -138,8 +306,21 @@ fn main() {
 
                 let u = (x as FloatTy + di) / nx as FloatTy;
                 let v = ((ny - y - 1) as FloatTy + dj) / ny as FloatTy;
-                let color = compute_pixel(&camera, &objects, u, v, background_color, &mut rng);
-                local_send.send((x, y, color)).unwrap();
+                let sample = compute_pixel(
+                    &objects,
+                    &lights,
+                    camera.get_ray_differential(
+                        u,
+                        v,
+                        footprint / nx as FloatTy,
+                        footprint / ny as FloatTy,
+                    ),
+                    &*background,
+                    spectral,
+                    with_passes,
+                    &mut rng,
+                );
+                local_send.send((x, y, sample)).unwrap();
             }
         })
     }
@@ -155,24 +336,30 @@ fn main() {
 
     let rec_image = image.clone();
     let recuperator = thread::spawn(move || {
-        for (x, y, color) in recv.into_iter() {
-            rec_image.lock().unwrap().append_pixel(x, y, color);
+        for (x, y, sample) in recv.into_iter() {
+            match sample {
+                Sample::Color(color) => rec_image.lock().unwrap().append_pixel(x, y, color),
+                Sample::Aovs(aovs) => rec_image.lock().unwrap().append_aovs(x, y, &aovs),
+            }
             progress_bar.inc(1);
         }
+        // saved here as the window of the GUI keeps the main thread
+        let mut rec_image = rec_image.lock().unwrap();
+        if denoise {
+            rec_image.denoise(&Denoiser::new());
+        }
+        let result = rec_image.save(&output_path, &display, sample_type);
+        if let Err(err) = result {
+            eprintln!("cannot save `{}`: {}", output_path, err);
+        }
     });
 
     #[cfg(feature = "gui")]
     {
-        gui::run::<gui::RayTracingGUI>(nx, ny, image);
+        gui::run::<gui::RayTracingGUI>(nx, ny, image, display);
     }
 
     recuperator.join().unwrap();
-
-    #[cfg(not(feature = "gui"))]
-    {
-        let output_path = "./last_result.png";
-        image.lock().unwrap().save(output_path).unwrap();
-    }
 }
 
 fn validate_integer(input_value: String) -> Result<(), String> {
@@ -180,3 +367,17 @@ fn validate_integer(input_value: String) -> Result<(), String> {
         .map(|_| ())
         .map_err(|err| err.to_string())
 }
+
+fn validate_float(input_value: String) -> Result<(), String> {
+    FloatTy::from_str(&input_value)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+fn validate_temperature(input_value: String) -> Result<(), String> {
+    match FloatTy::from_str(&input_value) {
+        Ok(kelvin) if kelvin > 0.0 && kelvin.is_finite() => Ok(()),
+        Ok(_) => Err("the temperature must be positive".to_owned()),
+        Err(err) => Err(err.to_string()),
+    }
+}
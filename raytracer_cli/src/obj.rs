@@ -1,28 +1,37 @@
 use std::error::Error;
+use std::path::Path;
 use std::sync::Arc;
 
-use lazy_static::lazy_static;
 use maplit::hashmap;
 use raytracer::hittable::{self, TriangleBuilder};
 use std::collections::HashMap;
 
-use raytracer::material::Lambertian;
-use raytracer::texture::{ImageTexture, SolidTexture};
-use raytracer::{FloatTy, Pt3, Vec3};
+use raytracer::material::{Lambertian, Material};
+use raytracer::texture::TextureCache;
+use raytracer::{ColorSpace, DisplayPipeline, FloatTy, HittableExt, Pt3, Vec3};
 use tobj;
 
 use crate::{PresetConfig, SceneDescription};
 
-lazy_static! {
-    static ref RED_MAT: Arc<Lambertian<SolidTexture>> =
-        Arc::new(Lambertian::from_solid_color(Vec3::new(0.9, 0.3, 0.3)));
-    static ref TEXTURE_MAT: Arc<Lambertian<ImageTexture>> = {
-        let texture = ImageTexture::open(
-            "/home/paul/Downloads/cartoon-house/textures/CoatedCartoonHouse_None_color.jpg",
-        )
-        .unwrap();
-        Arc::new(Lambertian::new(texture))
-    };
+/// Diffuse material of an MTL material, its texture being shared through
+/// the cache with the other materials using it.
+fn load_material(
+    material: &tobj::Material,
+    directory: &Path,
+    textures: &TextureCache,
+) -> Result<Arc<dyn Material>, Box<dyn Error>> {
+    if material.diffuse_texture.is_empty() {
+        let diffuse = material.diffuse;
+        Ok(Arc::new(Lambertian::from_solid_color(Vec3::new(
+            diffuse[0] as FloatTy,
+            diffuse[1] as FloatTy,
+            diffuse[2] as FloatTy,
+        ))))
+    } else {
+        let texture =
+            textures.texture(directory.join(&material.diffuse_texture), ColorSpace::Srgb)?;
+        Ok(Arc::new(Lambertian::new(texture)))
+    }
 }
 
 fn presets_for_obj(path: &str) -> HashMap<String, PresetConfig> {
@@ -36,6 +45,7 @@ fn presets_for_obj(path: &str) -> HashMap<String, PresetConfig> {
             vfov: 45.0,
             sample_count: 1,
             background: Some(Vec3::repeat(0.2)),
+            display: DisplayPipeline::new(),
         }
     } else if path.contains("CartoonHouse") {
         PresetConfig {
@@ -47,6 +57,7 @@ fn presets_for_obj(path: &str) -> HashMap<String, PresetConfig> {
             vfov: 45.0,
             sample_count: 1,
             background: Some(Vec3::new(138.0, 191.0, 233.0) / 256.0),
+            display: DisplayPipeline::new(),
         }
     } else {
         unimplemented!()
@@ -71,15 +82,28 @@ fn presets_for_obj(path: &str) -> HashMap<String, PresetConfig> {
     }
 }
 
-pub fn load_obj(path: &str) -> Result<SceneDescription, Box<dyn Error>> {
-    let (models, _) = tobj::load_obj(path)?;
+pub fn load_obj(path: &str, textures: &TextureCache) -> Result<SceneDescription, Box<dyn Error>> {
+    let (models, materials) = tobj::load_obj(path)?;
+
+    let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    let materials = materials
+        .iter()
+        .map(|material| load_material(material, directory, textures))
+        .collect::<Result<Vec<_>, _>>()?;
+    let default_material: Arc<dyn Material> =
+        Arc::new(Lambertian::from_solid_color(Vec3::new(0.9, 0.3, 0.3)));
 
     let mut objects: Vec<Box<dyn hittable::Hittable>> = Vec::new();
 
-    for model in models {
+    // each model is an object of its own in the object ID pass
+    for (id, model) in (1..).zip(models) {
         let mesh = &model.mesh;
         let has_normals = !mesh.normals.is_empty();
         let has_texcoords = !mesh.texcoords.is_empty();
+        let material = mesh
+            .material_id
+            .and_then(|id| materials.get(id))
+            .unwrap_or(&default_material);
 
         assert_eq!(mesh.indices.len() % 3, 0);
 
@@ -117,7 +141,7 @@ pub fn load_obj(path: &str) -> Result<SceneDescription, Box<dyn Error>> {
             }
 
             let mut builder =
-                TriangleBuilder::new([points[0], points[1], points[2]], TEXTURE_MAT.clone());
+                TriangleBuilder::new([points[0], points[1], points[2]], material.clone());
 
             if has_normals {
                 builder = builder.with_normals([normals[0], normals[1], normals[2]]);
@@ -128,7 +152,7 @@ pub fn load_obj(path: &str) -> Result<SceneDescription, Box<dyn Error>> {
             }
 
             let triangle = builder.build();
-            objects.push(Box::new(triangle));
+            objects.push(Box::new(triangle.with_id(id)));
         }
     }
 
@@ -137,5 +161,7 @@ pub fn load_obj(path: &str) -> Result<SceneDescription, Box<dyn Error>> {
     Ok(SceneDescription {
         presets: presets_for_obj(path),
         declarations,
+        lights: Vec::new(),
+        background: None,
     })
 }
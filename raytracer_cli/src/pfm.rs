@@ -0,0 +1,94 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes a color Portable Float Map from rows of RGB values going from the
+/// top of the image to its bottom.
+pub fn write_pfm<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    pixels: &[[f32; 3]],
+) -> io::Result<()> {
+    if width == 0 || height == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot write an empty image",
+        ));
+    }
+    if pixels.len() != width * height {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "pixels do not match the image size",
+        ));
+    }
+
+    let mut out = BufWriter::new(File::create(path)?);
+    // the negative scale marks little endian values
+    write!(out, "PF\n{} {}\n-1.0\n", width, height)?;
+    // rows are stored from the bottom of the image to its top
+    for row in pixels.chunks(width).rev() {
+        for pixel in row {
+            for value in pixel {
+                out.write_all(&value.to_le_bytes())?;
+            }
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use raytracer::texture::parse_pfm;
+
+    use super::*;
+
+    #[test]
+    fn written_images_are_read_back() {
+        let pixels = [
+            [0.0, 0.5, 1.0],
+            [2.0, -3.0, 1e-3],
+            [65504.0, 1e9, 0.25],
+            [7.0, 8.0, 9.0],
+            [0.1, 0.2, 0.3],
+            [f32::MIN_POSITIVE, 4.0, 5.0],
+        ];
+        let path = std::env::temp_dir().join(format!("write_pfm_{}.pfm", std::process::id()));
+        write_pfm(&path, 3, 2, &pixels).unwrap();
+        let content = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let image = parse_pfm(&content).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        for (texel, pixel) in image.texels.iter().zip(&pixels) {
+            assert_eq!(texel, &[pixel[0], pixel[1], pixel[2], 1.0]);
+        }
+    }
+
+    #[test]
+    fn header_is_little_endian_and_rows_go_up() {
+        let pixels = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let path = std::env::temp_dir().join(format!("pfm_rows_{}.pfm", std::process::id()));
+        write_pfm(&path, 1, 2, &pixels).unwrap();
+        let content = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(content[..header.len()], header[..]);
+        let values: Vec<u8> = [4f32, 5.0, 6.0, 1.0, 2.0, 3.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+        assert_eq!(content[header.len()..], values[..]);
+    }
+
+    #[test]
+    fn mismatched_and_empty_images_are_rejected() {
+        let path = std::env::temp_dir().join(format!("pfm_reject_{}.pfm", std::process::id()));
+        assert!(write_pfm(&path, 2, 2, &[[0.0; 3]; 3]).is_err());
+        assert!(write_pfm(&path, 0, 3, &[]).is_err());
+        assert!(!path.exists());
+    }
+}
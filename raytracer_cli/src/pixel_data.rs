@@ -1,14 +1,20 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use image::error::ImageError;
 use image::RgbaImage;
 
-use raytracer::{Color, FloatTy, Vec3};
+use raytracer::{Aovs, Denoiser, DisplayPipeline, FloatTy, Vec3};
+
+use crate::aov::{AovBuffer, Pass};
+use crate::exr::{write_exr, Channel, SampleType};
+use crate::pfm::write_pfm;
 
 #[derive(Debug, Clone)]
 pub struct PixelData {
     width: usize,
     height: usize,
     buffer: Vec<(Vec3, usize)>,
+    aovs: Vec<AovBuffer>,
 }
 
 impl PixelData {
@@ -17,9 +23,19 @@ impl PixelData {
             width,
             height,
             buffer: vec![(Vec3::zeros(), 0); width * height],
+            aovs: Vec::new(),
         }
     }
 
+    /// Also accumulates render passes, filled by `append_aovs`.
+    pub fn with_passes(self, passes: &[Pass]) -> Self {
+        let aovs = passes
+            .iter()
+            .map(|pass| AovBuffer::new(*pass, self.width, self.height))
+            .collect();
+        PixelData { aovs, ..self }
+    }
+
     pub fn append_pixel(&mut self, x: usize, y: usize, color: Vec3) {
         if color.x.is_nan() || color.y.is_nan() || color.z.is_nan() {
             return;
@@ -30,18 +46,126 @@ impl PixelData {
         self.buffer[(y * self.width + x)] = (next_color, count + 1);
     }
 
-    pub fn as_image(&self) -> RgbaImage {
+    pub fn append_aovs(&mut self, x: usize, y: usize, aovs: &Aovs) {
+        self.append_pixel(x, y, aovs.color);
+        for buffer in &mut self.aovs {
+            buffer.append(x, y, aovs);
+        }
+    }
+
+    /// Average of the samples of a pixel, black before its first sample.
+    pub fn color(&self, x: usize, y: usize) -> Vec3 {
+        let (sum_color, count) = self.buffer[y * self.width + x];
+        if count == 0 {
+            Vec3::zeros()
+        } else {
+            sum_color / (count as FloatTy)
+        }
+    }
+
+    /// Averaged colors from the top left pixel, row by row.
+    fn colors(&self) -> impl Iterator<Item = Vec3> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| self.color(x, y)))
+    }
+
+    /// Replaces the image by its denoised version, when the albedo and
+    /// normal passes are rendered.
+    pub fn denoise(&mut self, denoiser: &Denoiser) {
+        let pass_values = |pass| {
+            self.aovs
+                .iter()
+                .find(|buffer| buffer.pass() == pass)
+                .map(AovBuffer::values)
+        };
+        let (albedo, normal) = match (pass_values(Pass::Albedo), pass_values(Pass::Normal)) {
+            (Some(albedo), Some(normal)) => (albedo, normal),
+            _ => return,
+        };
+
+        let colors: Vec<_> = self.colors().collect();
+        let denoised = denoiser.denoise(self.width, self.height, &colors, &albedo, &normal);
+        self.buffer = denoised.into_iter().map(|color| (color, 1)).collect();
+    }
+
+    pub fn as_image(&self, display: &DisplayPipeline) -> RgbaImage {
         RgbaImage::from_fn(self.width as _, self.height as _, |x, y| {
-            let x = x as usize;
-            let y = y as usize;
-            let (sum_color, count) = self.buffer[y * self.width + x];
-            let color = sum_color / (count as FloatTy);
-            let color = Color::from_vec3(color).to_rgb();
+            let color = display.to_srgb8(self.color(x as usize, y as usize));
             image::Rgba([color[0], color[1], color[2], 255])
         })
     }
 
-    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), image::error::ImageError> {
-        self.as_image().save(path)
+    /// `R`, `G` and `B` channels of the linear radiance, followed by a layer
+    /// for each render pass.
+    pub fn channels(&self, sample_type: SampleType) -> Vec<Channel> {
+        let mut channels: Vec<_> = ["R", "G", "B"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                Channel::new(
+                    *name,
+                    self.colors().map(|c| c[i] as f32).collect(),
+                    sample_type,
+                )
+            })
+            .collect();
+        for buffer in &self.aovs {
+            channels.extend(buffer.channels(sample_type));
+        }
+        channels
+    }
+
+    /// Saves the image in the format given by the extension of the path:
+    /// `.exr` and `.pfm` files keep the linear radiance, other formats are
+    /// 8-bit images going through the display pipeline.
+    ///
+    /// Render passes are layers of OpenEXR files, and separate files in
+    /// other formats, the albedo of `image.png` going to `image.albedo.png`.
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        path: P,
+        display: &DisplayPipeline,
+        sample_type: SampleType,
+    ) -> Result<(), ImageError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("exr") => {
+                let channels = self.channels(sample_type);
+                write_exr(path, self.width, self.height, &channels)?;
+            }
+            Some("pfm") => {
+                let to_pixel = |c: Vec3| [c.x as f32, c.y as f32, c.z as f32];
+                let pixels: Vec<_> = self.colors().map(to_pixel).collect();
+                write_pfm(path, self.width, self.height, &pixels)?;
+                for buffer in &self.aovs {
+                    let pixels: Vec<_> = buffer.values().into_iter().map(to_pixel).collect();
+                    let pass_path = pass_path(path, buffer.pass());
+                    write_pfm(pass_path, self.width, self.height, &pixels)?;
+                }
+            }
+            _ => {
+                self.as_image(display).save(path)?;
+                for buffer in &self.aovs {
+                    buffer
+                        .as_image(display)
+                        .save(pass_path(path, buffer.pass()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `dir/image.ext` becomes `dir/image.pass.ext`.
+fn pass_path(path: &Path, pass: Pass) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}.{}", stem, pass.name());
+    if let Some(extension) = path.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
     }
+    path.with_file_name(name)
 }